@@ -0,0 +1,57 @@
+//! RFC 4648 base32 和 base32hex 编码
+use crate::encoding::{decode_bits, decode_map, encode_bits, BlockEncoding, Encoding};
+use crate::error::DecodeError;
+
+const STANDARD_CHARS: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const HEX_CHARS: &[u8; 32] = b"0123456789ABCDEFGHIJKLMNOPQRSTUV";
+
+/// 每 5 字节编码为 8 个字符；解码时不区分大小写，填充可有可无
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Base32 {
+    chars: &'static [u8; 32],
+    map: [u8; 128],
+    padding: bool,
+}
+
+impl Base32 {
+    pub const STANDARD: Base32 = Base32::new(STANDARD_CHARS, true);
+    pub const STANDARD_NO_PAD: Base32 = Base32::new(STANDARD_CHARS, false);
+    /// 扩展十六进制字母表，编码结果保持字节的排序
+    pub const HEX: Base32 = Base32::new(HEX_CHARS, true);
+    pub const HEX_NO_PAD: Base32 = Base32::new(HEX_CHARS, false);
+
+    const fn new(chars: &'static [u8; 32], padding: bool) -> Self {
+        Base32 { chars, map: decode_map(chars, true), padding }
+    }
+}
+
+impl Encoding for Base32 {
+    /// # Example
+    /// ```
+    /// use encoder::base32::Base32;
+    /// use encoder::encoding::Encoding;
+    ///
+    /// assert_eq!("MZXW6YTBOI======", Base32::STANDARD.encode(b"foobar"));
+    /// assert_eq!("CPNMUOJ1E8", Base32::HEX_NO_PAD.encode(b"foobar"));
+    /// ```
+    fn encode(&self, input: &[u8]) -> String {
+        encode_bits(input, 5, self.chars, 8, self.padding)
+    }
+
+    fn decode(&self, input: &str) -> Result<Vec<u8>, DecodeError> {
+        decode_bits(input, 5, &self.map)
+    }
+
+    fn encoded_len(&self, len: usize) -> usize {
+        match self.padding {
+            true => len.div_ceil(5) * 8,
+            false => (len * 8).div_ceil(5),
+        }
+    }
+}
+
+impl BlockEncoding for Base32 {
+    fn block(&self) -> (usize, usize) {
+        (5, 8)
+    }
+}
@@ -1,5 +1,9 @@
 //! base58 编码
 //base58 是 base64 的改进版，是比特币中使用的一种编码，用于生成比特币钱包地址，由中本聪引入。
+use sha2::{Digest, Sha256};
+
+pub use crate::error::DecodeError;
+use crate::encoding::Encoding;
 
 ///将 base64 中易错字符[0 O I l + /]剔除，剩下编码字符 58 个
 ///设计Base58主要的目的是：
@@ -7,29 +11,184 @@
 ///2. 不使用"+"和"/"的原因是非字母或数字的字符串作为帐号较难被接受。
 ///3. 编码结果没有标点符号，通常不会被从中间分行。
 ///4. 大部分软件支持双击选择整个字符串，因为不分行，所以双击字符串就能复制下来。
-const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
-
-/// 编码转换表
-const BASE58_DIGITS_MAP: &'static [u8] = &[
-    255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,
-    255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,
-    255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,
-    255,  0,  1,  2,  3,  4,  5,  6,  7,  8,255,255,255,255,255,255,
-    255,  9, 10, 11, 12, 13, 14, 15, 16,255, 17, 18, 19, 20, 21,255,
-     22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32,255,255,255,255,255,
-    255, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43,255, 44, 45, 46,
-     47, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57,255,255,255,255,255,
-];
-
-/// 数前面的 0 用 1 来代替
-const ALPHABET_INDEX_0: char = '1';
-
-/// 用于处理解码失败的错误类型
-#[derive(Debug, PartialEq)]
-pub enum DecodeError {
-    Invalid,
-    InvalidLength,
-    InvalidCharacter(char, usize),
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alphabet {
+    chars: [u8; 58],
+    /// 编码转换表，255 表示不在字母表中
+    map: [u8; 128],
+}
+
+impl Alphabet {
+    /// 比特币使用的字母表
+    pub const BITCOIN: Alphabet = Alphabet::new(b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz");
+    /// 瑞波币使用的字母表
+    pub const RIPPLE: Alphabet = Alphabet::new(b"rpshnaf39wBUDNEGHJKLM4PQRST7VWXYZ2bcdeCg65jkm8oFqi1tuvAxyz");
+    /// Flickr 短链接使用的字母表
+    pub const FLICKR: Alphabet = Alphabet::new(b"123456789abcdefghijkmnopqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ");
+
+    /// 字符须为互不相同的 ASCII 字符
+    pub const fn new(chars: &[u8; 58]) -> Self {
+        let mut map = [255u8; 128];
+        let mut i = 0;
+        while i < chars.len() {
+            map[chars[i] as usize] = i as u8;
+            i += 1;
+        }
+        Alphabet { chars: *chars, map }
+    }
+
+    /// 将任意长度的字节编码为 base58
+    ///
+    /// # Example
+    /// ```
+    /// use encoder::base58::Alphabet;
+    ///
+    /// assert_eq!("2NEpo7TZRRrLZSi2U", Alphabet::BITCOIN.encode(b"Hello World!"));
+    /// assert_eq!("p4NFofTZRRiLZS5p7", Alphabet::RIPPLE.encode(b"Hello World!"));
+    /// ```
+    pub fn encode(&self, input: &[u8]) -> String {
+        // 数前面的 0 用字母表的第一个字符来代替
+        let zcount = input.iter().take_while(|&&x| x == 0).count();
+
+        // 以 58 为基的大数，低位在前
+        let mut digits: Vec<u8> = Vec::with_capacity(input.len() * 138 / 100 + 1);
+        for &byte in &input[zcount..] {
+            let mut carry = byte as u32;
+            for digit in digits.iter_mut() {
+                carry += (*digit as u32) << 8;
+                *digit = (carry % 58) as u8;
+                carry /= 58;
+            }
+            while carry > 0 {
+                digits.push((carry % 58) as u8);
+                carry /= 58;
+            }
+        }
+
+        let mut base58_str = String::with_capacity(zcount + digits.len());
+        for _ in 0..zcount {
+            base58_str.push(self.chars[0] as char);
+        }
+        for &digit in digits.iter().rev() {
+            base58_str.push(self.chars[digit as usize] as char);
+        }
+
+        base58_str
+    }
+
+    /// 将 base58 解码为字节
+    pub fn decode(&self, input: &str) -> Result<Vec<u8>, DecodeError> {
+        let zcount = input.bytes().take_while(|&x| x == self.chars[0]).count();
+
+        // 以 256 为基的大数，低位在前
+        let mut bytes: Vec<u8> = Vec::with_capacity(input.len() * 733 / 1000 + 1);
+        for (i, c) in input.char_indices().skip(zcount) {
+            let val = match c.is_ascii() {
+                true => self.map[c as usize],
+                false => 255,
+            };
+            if val == 255 {
+                return Err(DecodeError::InvalidCharacter(c, i));
+            }
+
+            let mut carry = val as u32;
+            for byte in bytes.iter_mut() {
+                carry += *byte as u32 * 58;
+                *byte = (carry & 0xff) as u8;
+                carry >>= 8;
+            }
+            while carry > 0 {
+                bytes.push((carry & 0xff) as u8);
+                carry >>= 8;
+            }
+        }
+
+        let mut out = vec![0u8; zcount];
+        out.extend(bytes.iter().rev());
+        Ok(out)
+    }
+
+    /// base58check：版本字节、数据和双重 SHA-256 的前 4 字节校验和
+    ///
+    /// # Example
+    /// ```
+    /// use encoder::base58::Alphabet;
+    ///
+    /// let hash160 = [
+    ///     0x01, 0x09, 0x66, 0x77, 0x60, 0x06, 0x95, 0x3d, 0x55, 0x67,
+    ///     0x43, 0x9e, 0x5e, 0x39, 0xf8, 0x6a, 0x0d, 0x27, 0x3b, 0xee,
+    /// ];
+    /// let addr = Alphabet::BITCOIN.encode_check(0, &hash160);
+    /// assert_eq!("16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvM", addr);
+    /// assert_eq!(Ok((0, hash160.to_vec())), Alphabet::BITCOIN.decode_check(&addr));
+    /// ```
+    pub fn encode_check(&self, version: u8, payload: &[u8]) -> String {
+        let mut data = Vec::with_capacity(1 + payload.len() + CHECKSUM_LEN);
+        data.push(version);
+        data.extend_from_slice(payload);
+        let sum = checksum(&data);
+        data.extend_from_slice(&sum);
+        self.encode(&data)
+    }
+
+    /// 解码 base58check，返回版本字节和数据
+    pub fn decode_check(&self, input: &str) -> Result<(u8, Vec<u8>), DecodeError> {
+        let mut data = self.decode(input)?;
+        if data.len() < 1 + CHECKSUM_LEN {
+            return Err(DecodeError::InvalidLength);
+        }
+
+        let sum = data.split_off(data.len() - CHECKSUM_LEN);
+        if sum != checksum(&data) {
+            return Err(DecodeError::InvalidChecksum);
+        }
+        Ok((data[0], data[1..].to_vec()))
+    }
+}
+
+/// base58 不按组编码，encoded_len 为上界
+impl Encoding for Alphabet {
+    fn encode(&self, input: &[u8]) -> String {
+        Alphabet::encode(self, input)
+    }
+
+    fn decode(&self, input: &str) -> Result<Vec<u8>, DecodeError> {
+        Alphabet::decode(self, input)
+    }
+
+    fn encoded_len(&self, len: usize) -> usize {
+        len * 138 / 100 + 1
+    }
+}
+
+/// base58check 校验和的字节数
+const CHECKSUM_LEN: usize = 4;
+
+fn checksum(data: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let hash = Sha256::digest(Sha256::digest(data));
+    let mut sum = [0u8; CHECKSUM_LEN];
+    sum.copy_from_slice(&hash[..CHECKSUM_LEN]);
+    sum
+}
+
+/// 用比特币字母表编码
+pub fn encode(input: &[u8]) -> String {
+    Alphabet::BITCOIN.encode(input)
+}
+
+/// 用比特币字母表解码
+pub fn decode(input: &str) -> Result<Vec<u8>, DecodeError> {
+    Alphabet::BITCOIN.decode(input)
+}
+
+/// 用比特币字母表编码 base58check
+pub fn encode_check(version: u8, payload: &[u8]) -> String {
+    Alphabet::BITCOIN.encode_check(version, payload)
+}
+
+/// 用比特币字母表解码 base58check
+pub fn decode_check(input: &str) -> Result<(u8, Vec<u8>), DecodeError> {
+    Alphabet::BITCOIN.decode_check(input)
 }
 
 // 编码合解码 trait
@@ -53,48 +212,18 @@ impl Encoder for str {
     /// assert_eq!(des, src.encode_to_base58());
     /// ```
     fn encode_to_base58(&self) -> String {
-        let str_u8 = self.as_bytes();
-        let zcount = str_u8.iter().take_while(|&&x| x == 0).count();
-        let size = (str_u8.len() - zcount) * 138 / 100 + 1;
-
-        let mut i = zcount;
-        let mut high = size - 1;
-        let mut buffer = vec![0u8; size];
-        while i < str_u8.len() {
-            let mut j = size - 1;
-            let mut carry = str_u8[i] as u32;
-
-            while j > high || carry != 0 {
-                carry += 256 * buffer[j] as u32;
-                buffer[j] = (carry % 58) as u8;
-                carry /= 58;
-
-                if j > 0 {
-                    j -= 1;
-                }
-            }
-
-            i += 1;
-            high = j;
-        }
-
-        let mut base58_str = String::new();
-        for _ in 0..zcount {
-            base58_str.push(ALPHABET_INDEX_0);
-        }
-
-        let mut j = buffer.iter().take_while(|&&x| x == 0).count();
-        while j < size {
-            base58_str.push(ALPHABET[buffer[j] as usize] as char);
-            j += 1;
-        }
+        encode(self.as_bytes())
+    }
+}
 
-        base58_str
+impl Encoder for [u8] {
+    fn encode_to_base58(&self) -> String {
+        encode(self)
     }
 }
 
 impl Decoder for str {
-    /// 将 base58 编码解码为 String
+    /// 将 base58 编码解码为 String，结果不是 UTF-8 时报错，字节数据请用 decode
     ///
     /// # Example
     /// ```
@@ -105,75 +234,6 @@ impl Decoder for str {
     /// assert_eq!(des, src.decode_from_base58().unwrap());
     /// ```
     fn decode_from_base58(&self) -> Result<String, DecodeError> {
-        let mut bin = [0u8; 132];
-        let mut out = [0u32; (132 + 3) / 4];
-        let bytesleft = (bin.len() % 4) as u8;
-        let zeromask = match bytesleft {
-            0 => 0u32,
-            _ => 0xffffffff << (bytesleft * 8),
-        };
-
-        let zcount = self.chars().take_while(|&x| x == ALPHABET_INDEX_0).count();
-        let mut i = zcount;
-        let b58: Vec<u8> = self.bytes().collect();
-        while i < self.len() {
-            if (b58[i] & 0x80) != 0 {
-                return Err(DecodeError::InvalidCharacter(b58[i] as char, i));
-            }
-
-            if BASE58_DIGITS_MAP[b58[i] as usize] == 255 {
-                return Err(DecodeError::InvalidCharacter(b58[i] as char, i));
-            }
-
-            let mut j = out.len();
-            let mut c = BASE58_DIGITS_MAP[b58[i] as usize] as u64;
-            while j != 0 {
-                j -= 1;
-                let t = out[j] as u64 * 58 + c;
-                c = (t & 0x3f00000000) >> 32;
-                out[j] = (t & 0xffffffff) as u32;
-            }
-
-            if c != 0 {
-                return Err(DecodeError::InvalidLength);
-            }
-
-            if (out[0] & zeromask) != 0 {
-                return Err(DecodeError::InvalidLength);
-            }
-
-            i += 1;
-        }
-
-        let mut i = 1;
-        let mut j = 0;
-        bin[0] = match bytesleft {
-            3 => ((out[0] & 0xff0000) >> 16) as u8,
-            2 => ((out[0] & 0xff00) >> 8) as u8,
-            1 => {
-                j = 1;
-                (out[0] & 0xff) as u8
-            }
-            _ => {
-                i = 0;
-                bin[0]
-            }
-        };
-
-        while j < out.len() {
-            bin[i] = ((out[j] >> 0x18) & 0xff) as u8;
-            bin[i + 1] = ((out[j] >> 0x10) & 0xff) as u8;
-            bin[i + 2] = ((out[j] >> 8) & 0xff) as u8;
-            bin[i + 3] = ((out[j] >> 0) & 0xff) as u8;
-            i += 4;
-            j += 1;
-        }
-
-        let leading_zeros = bin.iter().take_while(|&&x| x == 0).count();
-        let new_str = String::from_utf8(bin[leading_zeros - zcount..].to_vec());
-        match new_str {
-            Ok(res) => Ok(res),
-            Err(_) => Err(DecodeError::Invalid),
-        }
+        String::from_utf8(decode(self)?).map_err(|_| DecodeError::Invalid)
     }
 }
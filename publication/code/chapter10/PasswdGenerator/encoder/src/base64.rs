@@ -0,0 +1,58 @@
+//! RFC 4648 base64 编码，标准字母表和 URL 安全字母表
+use crate::encoding::{decode_bits, decode_map, encode_bits, BlockEncoding, Encoding};
+use crate::error::DecodeError;
+
+const STANDARD_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL_SAFE_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// 每 3 字节编码为 4 个字符；解码时填充可有可无
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Base64 {
+    chars: &'static [u8; 64],
+    map: [u8; 128],
+    padding: bool,
+}
+
+impl Base64 {
+    pub const STANDARD: Base64 = Base64::new(STANDARD_CHARS, true);
+    pub const STANDARD_NO_PAD: Base64 = Base64::new(STANDARD_CHARS, false);
+    /// 用 - 和 _ 代替 + 和 /，可以直接放进 URL 和文件名
+    pub const URL_SAFE: Base64 = Base64::new(URL_SAFE_CHARS, true);
+    pub const URL_SAFE_NO_PAD: Base64 = Base64::new(URL_SAFE_CHARS, false);
+
+    const fn new(chars: &'static [u8; 64], padding: bool) -> Self {
+        Base64 { chars, map: decode_map(chars, false), padding }
+    }
+}
+
+impl Encoding for Base64 {
+    /// # Example
+    /// ```
+    /// use encoder::base64::Base64;
+    /// use encoder::encoding::Encoding;
+    ///
+    /// assert_eq!("+//+", Base64::STANDARD.encode(&[0xfb, 0xff, 0xfe]));
+    /// assert_eq!("-__-", Base64::URL_SAFE.encode(&[0xfb, 0xff, 0xfe]));
+    /// assert_eq!("YQ", Base64::STANDARD_NO_PAD.encode(b"a"));
+    /// ```
+    fn encode(&self, input: &[u8]) -> String {
+        encode_bits(input, 6, self.chars, 4, self.padding)
+    }
+
+    fn decode(&self, input: &str) -> Result<Vec<u8>, DecodeError> {
+        decode_bits(input, 6, &self.map)
+    }
+
+    fn encoded_len(&self, len: usize) -> usize {
+        match self.padding {
+            true => len.div_ceil(3) * 4,
+            false => (len * 8).div_ceil(6),
+        }
+    }
+}
+
+impl BlockEncoding for Base64 {
+    fn block(&self) -> (usize, usize) {
+        (3, 4)
+    }
+}
@@ -0,0 +1,137 @@
+//! base85 编码：Ascii85 和 ZeroMQ 的 Z85
+use crate::encoding::{decode_map, BlockEncoding, Encoding};
+use crate::error::DecodeError;
+
+const Z85_CHARS: &[u8; 85] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+/// Ascii85 使用 ! 到 u 的连续字符
+const fn ascii85_chars() -> [u8; 85] {
+    let mut chars = [0u8; 85];
+    let mut i = 0;
+    while i < 85 {
+        chars[i] = b'!' + i as u8;
+        i += 1;
+    }
+    chars
+}
+
+const ASCII85_CHARS: [u8; 85] = ascii85_chars();
+
+/// 每 4 字节编码为 5 个字符，最后不足 4 字节的 n 字节编码为 n + 1 个字符
+///
+/// Ascii85 把全 0 的一组写作 z，不带 <~ ~> 定界符；
+/// Z85 规范要求长度为 4 的倍数，这里按 Ascii85 的方式处理余下的字节
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Base85 {
+    chars: [u8; 85],
+    map: [u8; 128],
+    zero_group: bool,
+}
+
+impl Base85 {
+    pub const ASCII85: Base85 = Base85::new(ASCII85_CHARS, true);
+    pub const Z85: Base85 = Base85::new(*Z85_CHARS, false);
+
+    const fn new(chars: [u8; 85], zero_group: bool) -> Self {
+        Base85 { chars, map: decode_map(&chars, false), zero_group }
+    }
+}
+
+impl Encoding for Base85 {
+    /// # Example
+    /// ```
+    /// use encoder::base85::Base85;
+    /// use encoder::encoding::Encoding;
+    ///
+    /// assert_eq!("9jqo^BlbD-BleB1DJ+*+F(f,q", Base85::ASCII85.encode(b"Man is distinguished"));
+    /// assert_eq!("HelloWorld", Base85::Z85.encode(&[0x86, 0x4f, 0xd2, 0x6f, 0xb5, 0x59, 0xf7, 0x5b]));
+    /// ```
+    fn encode(&self, input: &[u8]) -> String {
+        let mut out = String::with_capacity(self.encoded_len(input.len()));
+        for chunk in input.chunks(4) {
+            if self.zero_group && chunk == [0, 0, 0, 0] {
+                out.push('z');
+                continue;
+            }
+
+            let mut group = [0u8; 4];
+            group[..chunk.len()].copy_from_slice(chunk);
+            let mut val = u32::from_be_bytes(group);
+            let mut digits = [0u8; 5];
+            for digit in digits.iter_mut().rev() {
+                *digit = self.chars[(val % 85) as usize];
+                val /= 85;
+            }
+            for &digit in &digits[..chunk.len() + 1] {
+                out.push(digit as char);
+            }
+        }
+        out
+    }
+
+    fn decode(&self, input: &str) -> Result<Vec<u8>, DecodeError> {
+        let mut out = Vec::with_capacity(input.len() / 5 * 4 + 4);
+        let mut digits = Vec::with_capacity(5);
+        for (i, c) in input.char_indices() {
+            if self.zero_group && c == 'z' && digits.is_empty() {
+                out.extend_from_slice(&[0; 4]);
+                continue;
+            }
+            let val = if c.is_ascii() { self.map[c as usize] } else { 255 };
+            if val == 255 {
+                return Err(DecodeError::InvalidCharacter(c, i));
+            }
+
+            digits.push(val);
+            if digits.len() == 5 {
+                out.extend_from_slice(&group_bytes(&digits)?);
+                digits.clear();
+            }
+        }
+
+        // 不足一组时用最大的数字补齐，只取前 n - 1 个字节
+        match digits.len() {
+            0 => {}
+            1 => return Err(DecodeError::InvalidLength),
+            n => {
+                digits.resize(5, 84);
+                out.extend_from_slice(&group_bytes(&digits)?[..n - 1]);
+            }
+        }
+        Ok(out)
+    }
+
+    fn encoded_len(&self, len: usize) -> usize {
+        len / 4 * 5 + if len.is_multiple_of(4) { 0 } else { len % 4 + 1 }
+    }
+}
+
+impl BlockEncoding for Base85 {
+    fn block(&self) -> (usize, usize) {
+        (4, 5)
+    }
+
+    /// z 单独构成一组
+    fn complete_prefix(&self, pending: &str) -> usize {
+        let mut count = 0;
+        let mut prefix = 0;
+        for (i, c) in pending.char_indices() {
+            if self.zero_group && c == 'z' && count == 0 {
+                prefix = i + 1;
+                continue;
+            }
+            count += 1;
+            if count == 5 {
+                count = 0;
+                prefix = i + c.len_utf8();
+            }
+        }
+        prefix
+    }
+}
+
+fn group_bytes(digits: &[u8]) -> Result<[u8; 4], DecodeError> {
+    let val = digits.iter().fold(0u64, |acc, &d| acc * 85 + d as u64);
+    let val = u32::try_from(val).map_err(|_| DecodeError::Invalid)?;
+    Ok(val.to_be_bytes())
+}
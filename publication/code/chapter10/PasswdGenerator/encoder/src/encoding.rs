@@ -0,0 +1,256 @@
+//! 编码 trait 和流式读写
+//!
+//! 各种编码都实现 Encoding；按固定分组编码的还实现 BlockEncoding，
+//! 可以用 EncoderWriter 和 DecoderReader 分块处理大文件。
+use std::io::{self, Read, Write};
+
+use crate::base32::Base32;
+use crate::base58::Alphabet;
+use crate::base64::Base64;
+use crate::base85::Base85;
+use crate::error::DecodeError;
+use crate::hex::Hex;
+
+/// 流式读取时每次从底层读取的字节数
+const CHUNK_LEN: usize = 8 * 1024;
+
+/// 字节与文本之间的编码
+pub trait Encoding {
+    fn encode(&self, input: &[u8]) -> String;
+    fn decode(&self, input: &str) -> Result<Vec<u8>, DecodeError>;
+    /// len 字节编码后的字符数，长度不固定的编码给出上界
+    fn encoded_len(&self, len: usize) -> usize;
+}
+
+/// 按固定分组编码，整组之间可以独立编解码
+pub trait BlockEncoding: Encoding {
+    /// 每组的字节数和字符数
+    fn block(&self) -> (usize, usize);
+
+    /// pending 中由整组构成的最长前缀的字节长度
+    fn complete_prefix(&self, pending: &str) -> usize {
+        let (_, chars) = self.block();
+        pending.len() / chars * chars
+    }
+}
+
+/// 按名称取编码，供命令行选择
+///
+/// # Example
+/// ```
+/// use encoder::encoding::by_name;
+///
+/// let hex = by_name("hex").unwrap();
+/// assert_eq!("cafe", hex.encode(&[0xca, 0xfe]));
+/// assert!(by_name("rot13").is_none());
+/// ```
+pub fn by_name(name: &str) -> Option<Box<dyn Encoding>> {
+    let encoding: Box<dyn Encoding> = match name {
+        "hex" => Box::new(Hex::LOWER),
+        "hex-upper" => Box::new(Hex::UPPER),
+        "base32" => Box::new(Base32::STANDARD),
+        "base32-nopad" => Box::new(Base32::STANDARD_NO_PAD),
+        "base32hex" => Box::new(Base32::HEX),
+        "base32hex-nopad" => Box::new(Base32::HEX_NO_PAD),
+        "base58" => Box::new(Alphabet::BITCOIN),
+        "base58-ripple" => Box::new(Alphabet::RIPPLE),
+        "base58-flickr" => Box::new(Alphabet::FLICKR),
+        "base64" => Box::new(Base64::STANDARD),
+        "base64-nopad" => Box::new(Base64::STANDARD_NO_PAD),
+        "base64url" => Box::new(Base64::URL_SAFE),
+        "base64url-nopad" => Box::new(Base64::URL_SAFE_NO_PAD),
+        "ascii85" => Box::new(Base85::ASCII85),
+        "z85" => Box::new(Base85::Z85),
+        _ => return None,
+    };
+    Some(encoding)
+}
+
+/// by_name 接受的全部名称
+pub const NAMES: [&str; 15] = [
+    "hex", "hex-upper", "base32", "base32-nopad", "base32hex", "base32hex-nopad", "base58", "base58-ripple",
+    "base58-flickr", "base64", "base64-nopad", "base64url", "base64url-nopad", "ascii85", "z85",
+];
+
+/// 由字母表生成解码表，255 表示不在字母表中
+pub(crate) const fn decode_map(chars: &[u8], fold_case: bool) -> [u8; 128] {
+    let mut map = [255u8; 128];
+    let mut i = 0;
+    while i < chars.len() {
+        map[chars[i] as usize] = i as u8;
+        if fold_case {
+            map[chars[i].to_ascii_lowercase() as usize] = i as u8;
+        }
+        i += 1;
+    }
+    map
+}
+
+/// 每 bits 位对应一个字符，base32 为 5，base64 为 6
+pub(crate) fn encode_bits(input: &[u8], bits: u32, chars: &[u8], block_chars: usize, padding: bool) -> String {
+    let mut out = String::with_capacity((input.len() * 8).div_ceil(bits as usize));
+    let mask = (1u32 << bits) - 1;
+    let mut buf = 0u32;
+    let mut nbits = 0;
+    for &b in input {
+        buf = (buf << 8) | b as u32;
+        nbits += 8;
+        while nbits >= bits {
+            nbits -= bits;
+            out.push(chars[((buf >> nbits) & mask) as usize] as char);
+        }
+    }
+    if nbits > 0 {
+        out.push(chars[((buf << (bits - nbits)) & mask) as usize] as char);
+    }
+    if padding {
+        while !out.len().is_multiple_of(block_chars) {
+            out.push('=');
+        }
+    }
+    out
+}
+
+/// encode_bits 的逆运算，末尾的填充可有可无
+pub(crate) fn decode_bits(input: &str, bits: u32, map: &[u8; 128]) -> Result<Vec<u8>, DecodeError> {
+    let data = input.trim_end_matches('=');
+
+    // 最后不足一字节的位数必须少于一个字符的位数
+    if (data.len() * bits as usize) % 8 >= bits as usize {
+        return Err(DecodeError::InvalidLength);
+    }
+
+    let mut out = Vec::with_capacity(data.len() * bits as usize / 8);
+    let mut buf = 0u32;
+    let mut nbits = 0;
+    for (i, c) in data.char_indices() {
+        let val = if c.is_ascii() { map[c as usize] } else { 255 };
+        if val == 255 {
+            return Err(DecodeError::InvalidCharacter(c, i));
+        }
+        buf = (buf << bits) | val as u32;
+        nbits += bits;
+        if nbits >= 8 {
+            nbits -= 8;
+            out.push((buf >> nbits) as u8);
+        }
+    }
+    Ok(out)
+}
+
+/// 写入时编码：凑满整组就写出，不足一组的留到 finish
+pub struct EncoderWriter<W: Write, E: BlockEncoding> {
+    inner: Option<W>,
+    encoding: E,
+    buf: Vec<u8>,
+}
+
+impl<W: Write, E: BlockEncoding> EncoderWriter<W, E> {
+    pub fn new(inner: W, encoding: E) -> Self {
+        EncoderWriter { inner: Some(inner), encoding, buf: Vec::new() }
+    }
+
+    /// 写出最后不足一组的数据和填充，返回底层的 writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_rest()?;
+        Ok(self.inner.take().expect("writer already finished"))
+    }
+
+    fn write_rest(&mut self) -> io::Result<()> {
+        if let Some(inner) = self.inner.as_mut() {
+            if !self.buf.is_empty() {
+                inner.write_all(self.encoding.encode(&self.buf).as_bytes())?;
+                self.buf.clear();
+            }
+            inner.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write, E: BlockEncoding> Write for EncoderWriter<W, E> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let inner = self.inner.as_mut().expect("writer already finished");
+        self.buf.extend_from_slice(data);
+        let (bytes, _) = self.encoding.block();
+        let full = self.buf.len() / bytes * bytes;
+        if full > 0 {
+            inner.write_all(self.encoding.encode(&self.buf[..full]).as_bytes())?;
+            self.buf.drain(..full);
+        }
+        Ok(data.len())
+    }
+
+    /// 只刷新已编码的整组，不足一组的数据要等 finish
+    fn flush(&mut self) -> io::Result<()> {
+        match self.inner.as_mut() {
+            Some(inner) => inner.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write, E: BlockEncoding> Drop for EncoderWriter<W, E> {
+    fn drop(&mut self) {
+        let _ = self.write_rest();
+    }
+}
+
+/// 读取时解码，忽略空白字符，因此可以读按行折断的文件
+pub struct DecoderReader<R: Read, E: BlockEncoding> {
+    inner: R,
+    encoding: E,
+    pending: String,
+    out: Vec<u8>,
+    pos: usize,
+    eof: bool,
+}
+
+impl<R: Read, E: BlockEncoding> DecoderReader<R, E> {
+    pub fn new(inner: R, encoding: E) -> Self {
+        DecoderReader { inner, encoding, pending: String::new(), out: Vec::new(), pos: 0, eof: false }
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        let mut chunk = [0u8; CHUNK_LEN];
+        let n = self.inner.read(&mut chunk)?;
+        for &b in &chunk[..n] {
+            if !b.is_ascii() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, DecodeError::Invalid));
+            }
+            if !b.is_ascii_whitespace() {
+                self.pending.push(b as char);
+            }
+        }
+
+        // 读完后解码剩下的全部，否则只解码整组
+        let prefix = if n == 0 {
+            self.eof = true;
+            self.pending.len()
+        } else {
+            self.encoding.complete_prefix(&self.pending)
+        };
+        self.out = self.encoding
+            .decode(&self.pending[..prefix])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.pos = 0;
+        self.pending.drain(..prefix);
+        Ok(())
+    }
+}
+
+impl<R: Read, E: BlockEncoding> Read for DecoderReader<R, E> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.out.len() {
+            if self.eof {
+                return Ok(0);
+            }
+            self.fill()?;
+        }
+
+        let n = buf.len().min(self.out.len() - self.pos);
+        buf[..n].copy_from_slice(&self.out[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
@@ -0,0 +1,24 @@
+//! 解码错误
+use std::fmt;
+
+/// 用于处理解码失败的错误类型，各种编码共用
+#[derive(Debug, PartialEq)]
+pub enum DecodeError {
+    Invalid,
+    InvalidLength,
+    InvalidCharacter(char, usize),
+    InvalidChecksum,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::Invalid => write!(f, "invalid encoded data"),
+            DecodeError::InvalidLength => write!(f, "invalid encoded length"),
+            DecodeError::InvalidCharacter(c, i) => write!(f, "invalid character `{}` at {}", c, i),
+            DecodeError::InvalidChecksum => write!(f, "invalid checksum"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
@@ -0,0 +1,62 @@
+//! 十六进制编码
+use crate::encoding::{BlockEncoding, Encoding};
+use crate::error::DecodeError;
+
+/// 编码时使用小写或大写，解码时两者都接受
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hex {
+    upper: bool,
+}
+
+impl Hex {
+    pub const LOWER: Hex = Hex { upper: false };
+    pub const UPPER: Hex = Hex { upper: true };
+}
+
+impl Encoding for Hex {
+    /// # Example
+    /// ```
+    /// use encoder::encoding::Encoding;
+    /// use encoder::hex::Hex;
+    ///
+    /// assert_eq!("00ff10", Hex::LOWER.encode(&[0, 255, 16]));
+    /// assert_eq!(vec![0, 255, 16], Hex::UPPER.decode("00FF10").unwrap());
+    /// ```
+    fn encode(&self, input: &[u8]) -> String {
+        let digits: &[u8; 16] = if self.upper { b"0123456789ABCDEF" } else { b"0123456789abcdef" };
+        let mut out = String::with_capacity(input.len() * 2);
+        for &b in input {
+            out.push(digits[(b >> 4) as usize] as char);
+            out.push(digits[(b & 0x0f) as usize] as char);
+        }
+        out
+    }
+
+    fn decode(&self, input: &str) -> Result<Vec<u8>, DecodeError> {
+        if !input.len().is_multiple_of(2) {
+            return Err(DecodeError::InvalidLength);
+        }
+
+        let mut out = Vec::with_capacity(input.len() / 2);
+        let mut high = 0u8;
+        for (i, c) in input.char_indices() {
+            let val = c.to_digit(16).ok_or(DecodeError::InvalidCharacter(c, i))? as u8;
+            if i % 2 == 0 {
+                high = val << 4;
+            } else {
+                out.push(high | val);
+            }
+        }
+        Ok(out)
+    }
+
+    fn encoded_len(&self, len: usize) -> usize {
+        len * 2
+    }
+}
+
+impl BlockEncoding for Hex {
+    fn block(&self) -> (usize, usize) {
+        (1, 2)
+    }
+}
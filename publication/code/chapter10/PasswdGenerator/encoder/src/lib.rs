@@ -1,8 +1,21 @@
+pub mod base32;
 pub mod base58;
+pub mod base64;
+pub mod base85;
+pub mod encoding;
+pub mod error;
+pub mod hex;
 
 #[cfg(test)]
 mod tests {
-    use crate::base58::{Decoder, Encoder};
+    use std::io::{Read, Write};
+
+    use crate::base32::Base32;
+    use crate::base58::{self, Alphabet, DecodeError, Decoder, Encoder};
+    use crate::base64::Base64;
+    use crate::base85::Base85;
+    use crate::encoding::{by_name, DecoderReader, EncoderWriter, Encoding, NAMES};
+    use crate::hex::Hex;
 
     #[test]
     fn encode_decode() {
@@ -12,4 +25,108 @@ mod tests {
             "7T5VrPqoBr9DeUXiUr2Fn".decode_from_base58().unwrap()
         );
     }
+
+    #[test]
+    fn base58_bytes() {
+        assert_eq!("", base58::encode(b""));
+        assert_eq!("1", base58::encode(&[0]));
+        assert_eq!("111233QC4", base58::encode(&[0, 0, 0, 0x28, 0x7f, 0xb4, 0xcd]));
+        assert_eq!("2NEpo7TZRRrLZSi2U", base58::encode(b"Hello World!"));
+        assert_eq!("2nePN7syqqRkyrH2t", Alphabet::FLICKR.encode(b"Hello World!"));
+        assert_eq!(b"Hello World!".to_vec(), Alphabet::RIPPLE.decode("p4NFofTZRRiLZS5p7").unwrap());
+
+        // 任意长度和非 UTF-8 的数据都能还原
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        for alphabet in [Alphabet::BITCOIN, Alphabet::RIPPLE, Alphabet::FLICKR] {
+            for len in [0, 1, 2, 31, 32, 33, 132, 133, 1000] {
+                assert_eq!(data[..len].to_vec(), alphabet.decode(&alphabet.encode(&data[..len])).unwrap());
+            }
+        }
+        assert!(base58::encode(&data[..512]).starts_with("18F5Kj9nzVrQwD7PzaVp"));
+        assert_eq!(Err(DecodeError::Invalid), base58::encode(&[0xff, 0xfe]).decode_from_base58());
+        assert_eq!(Err(DecodeError::InvalidCharacter('0', 2)), base58::decode("110"));
+        assert_eq!(Err(DecodeError::InvalidCharacter('é', 1)), base58::decode("2é"));
+
+        let addr = base58::encode_check(0x80, &[7; 32]);
+        assert_eq!(Ok((0x80, vec![7; 32])), base58::decode_check(&addr));
+        let mut tampered = base58::decode(&addr).unwrap();
+        tampered[5] ^= 1;
+        assert_eq!(Err(DecodeError::InvalidChecksum), base58::decode_check(&base58::encode(&tampered)));
+        assert_eq!(Err(DecodeError::InvalidLength), base58::decode_check("1111"));
+    }
+
+    #[test]
+    fn rfc4648_vectors() {
+        let inputs = ["", "f", "fo", "foo", "foob", "fooba", "foobar"];
+        let vectors: [(&dyn Encoding, [&str; 7]); 5] = [
+            (&Base64::STANDARD, ["", "Zg==", "Zm8=", "Zm9v", "Zm9vYg==", "Zm9vYmE=", "Zm9vYmFy"]),
+            (&Base64::URL_SAFE_NO_PAD, ["", "Zg", "Zm8", "Zm9v", "Zm9vYg", "Zm9vYmE", "Zm9vYmFy"]),
+            (&Base32::STANDARD, ["", "MY======", "MZXQ====", "MZXW6===", "MZXW6YQ=", "MZXW6YTB", "MZXW6YTBOI======"]),
+            (&Base32::HEX, ["", "CO======", "CPNG====", "CPNMU===", "CPNMUOG=", "CPNMUOJ1", "CPNMUOJ1E8======"]),
+            (&Hex::UPPER, ["", "66", "666F", "666F6F", "666F6F62", "666F6F6261", "666F6F626172"]),
+        ];
+        for (encoding, outputs) in vectors {
+            for (input, output) in inputs.iter().zip(outputs) {
+                assert_eq!(output, encoding.encode(input.as_bytes()));
+                assert_eq!(input.as_bytes(), encoding.decode(output).unwrap());
+            }
+        }
+
+        assert_eq!(b"foob".to_vec(), Base32::STANDARD.decode("mzxw6yq").unwrap());
+        assert_eq!(Err(DecodeError::InvalidLength), Base64::STANDARD.decode("Zm9vY"));
+        assert_eq!(Err(DecodeError::InvalidLength), Base32::STANDARD.decode("MZX"));
+        assert_eq!(Err(DecodeError::InvalidCharacter('=', 2)), Base64::STANDARD.decode("Zg=g"));
+        assert_eq!(Err(DecodeError::InvalidCharacter('-', 0)), Base64::STANDARD.decode("-__-"));
+        assert_eq!(Err(DecodeError::InvalidCharacter('g', 1)), Hex::LOWER.decode("0g"));
+        assert_eq!(Err(DecodeError::Invalid), Base85::ASCII85.decode("uuuuu"));
+        assert_eq!(b"\0\0\0\0abc".to_vec(), Base85::ASCII85.decode("z@:E^").unwrap());
+    }
+
+    #[test]
+    fn encodings_round_trip() {
+        let data: Vec<u8> = (0..=255u8).rev().chain([0; 9]).collect();
+        for name in NAMES {
+            let encoding = by_name(name).unwrap();
+            for len in 0..data.len() {
+                let input = &data[data.len() - len..];
+                let encoded = encoding.encode(input);
+                assert!(encoded.len() <= encoding.encoded_len(len), "{} {}", name, len);
+                assert_eq!(input, encoding.decode(&encoded).unwrap(), "{} {}", name, len);
+            }
+        }
+        assert_eq!(Base64::STANDARD.encoded_len(10), Base64::STANDARD.encode(&[1; 10]).len());
+        assert_eq!(Base32::HEX_NO_PAD.encoded_len(7), Base32::HEX_NO_PAD.encode(&[1; 7]).len());
+    }
+
+    #[test]
+    fn streaming_matches_one_shot() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i * 7 % 251) as u8).chain([0; 8]).collect();
+
+        fn check<E: crate::encoding::BlockEncoding + Copy>(encoding: E, data: &[u8]) {
+            let mut writer = EncoderWriter::new(Vec::new(), encoding);
+            for piece in data.chunks(7) {
+                writer.write_all(piece).unwrap();
+            }
+            let encoded = String::from_utf8(writer.finish().unwrap()).unwrap();
+            assert_eq!(encoding.encode(data), encoded);
+
+            // 按 76 个字符折行后仍能读回
+            let wrapped: Vec<u8> = encoded.as_bytes()
+                .chunks(76)
+                .flat_map(|line| line.iter().copied().chain([b'\r', b'\n']))
+                .collect();
+            let mut decoded = Vec::new();
+            DecoderReader::new(wrapped.as_slice(), encoding).read_to_end(&mut decoded).unwrap();
+            assert_eq!(data, decoded.as_slice());
+        }
+        check(Hex::LOWER, &data);
+        check(Base32::STANDARD, &data);
+        check(Base64::URL_SAFE_NO_PAD, &data);
+        check(Base85::ASCII85, &data);
+        check(Base85::Z85, &data);
+
+        let mut decoded = Vec::new();
+        let err = DecoderReader::new("Zm9v!!!!".as_bytes(), Base64::STANDARD).read_to_end(&mut decoded);
+        assert!(err.is_err());
+    }
 }
@@ -0,0 +1,3 @@
+// 口令短语词表，即 BIP39 英文助记词，共 2048 个单词，前四个字母互不相同，每个单词 11 位熵
+// 没有可用的 EFF 词表，与区块链示例共用同一份词表，不再单独保存
+include!("../../../blockchain/blockchain8/core/src/wordlist.rs");
@@ -0,0 +1,243 @@
+//! 离线泄露密码检查
+//!
+//! 数据来自 Pwned Passwords 按哈希排序的 SHA-1 文件，每行为 `哈希:次数`。
+//! 可直接在文件中按偏移二分查找，也可由它生成布隆过滤器，每次查询只读几个字节。
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Error, Result};
+use sha1::{Digest, Sha1};
+
+/// 布隆过滤器文件头：魔数、位数、哈希函数个数
+const BLOOM_MAGIC: &[u8; 8] = b"PWBLOOM1";
+const BLOOM_HEADER: u64 = 8 + 8 + 4;
+
+/// SHA-1 的十六进制长度
+const HASH_HEX_LEN: usize = 40;
+
+/// 检查结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breach {
+    Clean,
+    /// 在哈希文件中出现的次数
+    Found(u64),
+    /// 布隆过滤器命中，有很小的概率误报
+    Likely,
+}
+
+impl Breach {
+    pub fn is_breached(&self) -> bool {
+        *self != Breach::Clean
+    }
+}
+
+/// 大写十六进制的 SHA-1，与 Pwned Passwords 文件一致
+pub fn sha1_hex(passwd: &str) -> String {
+    Sha1::digest(passwd.as_bytes()).iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// 排序的哈希文件，按字节偏移二分查找，不需要载入内存
+pub struct HashFile {
+    reader: BufReader<File>,
+    len: u64,
+}
+
+impl HashFile {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+        let len = file.metadata()?.len();
+        Ok(HashFile { reader: BufReader::new(file), len })
+    }
+
+    /// 哈希在文件中出现的次数，没有时为 None
+    pub fn count(&mut self, hash: &str) -> Result<Option<u64>, Error> {
+        parse_hash(hash)?;
+        let target = hash.to_ascii_uppercase();
+        let (mut lo, mut hi) = (0, self.len);
+
+        // 每轮取 mid 之后的第一行，比较后缩小到它的前面或后面
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let (start, line) = self.line_at(mid)?;
+            if line.is_empty() {
+                hi = mid;
+                continue;
+            }
+
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim_end();
+            let (line_hash, count) = text.split_once(':').unwrap_or((text, "1"));
+            if line_hash.len() != HASH_HEX_LEN {
+                bail!("malformed hash file line at offset {}", start);
+            }
+            match line_hash.to_ascii_uppercase().as_str().cmp(target.as_str()) {
+                Ordering::Equal => {
+                    let count = count.trim().parse().map_err(|_| anyhow!("malformed count at offset {}", start))?;
+                    return Ok(Some(count));
+                }
+                Ordering::Less => lo = start + line.len() as u64,
+                Ordering::Greater => hi = mid,
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// 从 offset 起第一个完整行的起始偏移和内容，含换行符
+    fn line_at(&mut self, offset: u64) -> Result<(u64, Vec<u8>), Error> {
+        let mut start = offset;
+        if offset > 0 {
+            self.reader.seek(SeekFrom::Start(offset - 1))?;
+            let mut skipped = Vec::new();
+            start = offset - 1 + self.reader.read_until(b'\n', &mut skipped)? as u64;
+        } else {
+            self.reader.seek(SeekFrom::Start(0))?;
+        }
+
+        let mut line = Vec::new();
+        self.reader.read_until(b'\n', &mut line)?;
+        Ok((start, line))
+    }
+
+    pub fn check(&mut self, passwd: &str) -> Result<Breach, Error> {
+        Ok(match self.count(&sha1_hex(passwd))? {
+            Some(count) => Breach::Found(count),
+            None => Breach::Clean,
+        })
+    }
+}
+
+/// 保存在磁盘上的布隆过滤器，查询时只读取对应的字节
+pub struct BloomFilter {
+    file: File,
+    bits: u64,
+    hashes: u32,
+}
+
+impl BloomFilter {
+    /// 由哈希文件生成误报率约为 fp_rate 的过滤器
+    pub fn build<P: AsRef<Path>, Q: AsRef<Path>>(hashes: P, out: Q, fp_rate: f64) -> Result<Self, Error> {
+        if !(fp_rate > 0.0 && fp_rate < 1.0) {
+            bail!("false positive rate must between 0 and 1");
+        }
+        let hashes = hashes.as_ref();
+        let open = || -> Result<BufReader<File>, Error> {
+            let file = File::open(hashes).with_context(|| format!("failed to open {}", hashes.display()))?;
+            Ok(BufReader::new(file))
+        };
+
+        // 先数行数以确定大小：m = -n ln p / (ln 2)^2，k = m / n * ln 2
+        let n = open()?.lines().count().max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let bits = ((-n * fp_rate.ln() / (ln2 * ln2)).ceil() as u64).max(8);
+        let k = ((bits as f64 / n * ln2).round() as u32).max(1);
+
+        let mut filter = vec![0u8; bits.div_ceil(8) as usize];
+        for line in open()?.lines() {
+            let line = line?;
+            let hash = line.trim().split(':').next().unwrap_or("");
+            if hash.is_empty() {
+                continue;
+            }
+            for index in bloom_indexes(&parse_hash(hash)?, bits, k) {
+                filter[(index / 8) as usize] |= 1 << (index % 8);
+            }
+        }
+
+        let out = out.as_ref();
+        let mut writer = BufWriter::new(File::create(out).with_context(|| format!("failed to create {}", out.display()))?);
+        writer.write_all(BLOOM_MAGIC)?;
+        writer.write_all(&bits.to_be_bytes())?;
+        writer.write_all(&k.to_be_bytes())?;
+        writer.write_all(&filter)?;
+        writer.flush()?;
+        drop(writer);
+
+        BloomFilter::open(out)
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let mut file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+        let mut header = [0u8; BLOOM_HEADER as usize];
+        file.read_exact(&mut header).map_err(|_| anyhow!("{} is not a bloom filter", path.display()))?;
+        if &header[..8] != BLOOM_MAGIC {
+            bail!("{} is not a bloom filter", path.display());
+        }
+
+        let bits = u64::from_be_bytes(header[8..16].try_into()?);
+        let hashes = u32::from_be_bytes(header[16..20].try_into()?);
+        if bits == 0 || hashes == 0 || file.metadata()?.len() < BLOOM_HEADER + bits.div_ceil(8) {
+            bail!("bloom filter {} is truncated", path.display());
+        }
+
+        Ok(BloomFilter { file, bits, hashes })
+    }
+
+    pub fn contains(&mut self, hash: &str) -> Result<bool, Error> {
+        for index in bloom_indexes(&parse_hash(hash)?, self.bits, self.hashes) {
+            let mut byte = [0u8; 1];
+            self.file.seek(SeekFrom::Start(BLOOM_HEADER + index / 8))?;
+            self.file.read_exact(&mut byte)?;
+            if byte[0] & (1 << (index % 8)) == 0 {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    pub fn check(&mut self, passwd: &str) -> Result<Breach, Error> {
+        Ok(if self.contains(&sha1_hex(passwd))? { Breach::Likely } else { Breach::Clean })
+    }
+}
+
+/// SHA-1 本身分布均匀，直接取其中两段做双重哈希
+fn bloom_indexes(hash: &[u8; 20], bits: u64, k: u32) -> impl Iterator<Item = u64> {
+    let word = |i: usize| hash[i..i + 8].iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+    let (h1, h2) = (word(0), word(8) | 1);
+    (0..k as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % bits)
+}
+
+fn parse_hash(hash: &str) -> Result<[u8; 20], Error> {
+    if hash.len() != HASH_HEX_LEN || !hash.is_ascii() {
+        bail!("malformed sha-1 hash `{}`", hash);
+    }
+    let mut out = [0u8; 20];
+    for (i, b) in out.iter_mut().enumerate() {
+        *b = u8::from_str_radix(&hash[i * 2..i * 2 + 2], 16).map_err(|_| anyhow!("malformed sha-1 hash `{}`", hash))?;
+    }
+    Ok(out)
+}
+
+/// 哈希文件或布隆过滤器，按文件头自动识别
+pub enum BreachChecker {
+    HashFile(HashFile),
+    Bloom(BloomFilter),
+}
+
+impl BreachChecker {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let mut magic = [0u8; 8];
+        let is_bloom = File::open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?
+            .read_exact(&mut magic)
+            .is_ok() && &magic == BLOOM_MAGIC;
+
+        Ok(if is_bloom {
+            BreachChecker::Bloom(BloomFilter::open(path)?)
+        } else {
+            BreachChecker::HashFile(HashFile::open(path)?)
+        })
+    }
+
+    pub fn check(&mut self, passwd: &str) -> Result<Breach, Error> {
+        match self {
+            BreachChecker::HashFile(file) => file.check(passwd),
+            BreachChecker::Bloom(bloom) => bloom.check(passwd),
+        }
+    }
+}
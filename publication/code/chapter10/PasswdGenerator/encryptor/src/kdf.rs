@@ -0,0 +1,114 @@
+//! 密钥派生
+//!
+//! 主密码经内存困难的 KDF 拉伸，参数按版本固定，
+//! 同一版本在任何发行版中都派生出相同的结果。
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Error, Result};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+/// 当前默认使用的参数版本
+pub const LATEST_VERSION: u32 = 1;
+
+/// KDF 算法，Argon2id 优先，scrypt 次之，PBKDF2 用于受限环境
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    Argon2id,
+    Scrypt,
+    Pbkdf2,
+}
+
+impl FromStr for Algorithm {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "argon2id" => Ok(Algorithm::Argon2id),
+            "scrypt" => Ok(Algorithm::Scrypt),
+            "pbkdf2" => Ok(Algorithm::Pbkdf2),
+            _ => bail!("unknown kdf `{}`, expected argon2id, scrypt or pbkdf2", s),
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Algorithm::Argon2id => "argon2id",
+            Algorithm::Scrypt => "scrypt",
+            Algorithm::Pbkdf2 => "pbkdf2",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// KDF 参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KdfParams {
+    /// m_cost 单位为 KiB
+    Argon2id { m_cost: u32, t_cost: u32, p_cost: u32 },
+    /// N = 2^log_n
+    Scrypt { log_n: u8, r: u32, p: u32 },
+    /// HMAC-SHA256
+    Pbkdf2 { rounds: u32 },
+}
+
+impl KdfParams {
+    /// 按版本号取参数，已发布的版本不能再修改
+    ///
+    /// # Example
+    /// ```
+    /// use encryptor::kdf::{Algorithm, KdfParams, LATEST_VERSION};
+    ///
+    /// let params = KdfParams::from_version(LATEST_VERSION, Algorithm::Pbkdf2).unwrap();
+    /// assert_eq!(KdfParams::Pbkdf2 { rounds: 600_000 }, params);
+    /// assert!(KdfParams::from_version(0, Algorithm::Pbkdf2).is_err());
+    /// ```
+    pub fn from_version(version: u32, algorithm: Algorithm) -> Result<Self> {
+        match (version, algorithm) {
+            (1, Algorithm::Argon2id) => Ok(KdfParams::Argon2id { m_cost: 19 * 1024, t_cost: 2, p_cost: 1 }),
+            (1, Algorithm::Scrypt) => Ok(KdfParams::Scrypt { log_n: 15, r: 8, p: 1 }),
+            (1, Algorithm::Pbkdf2) => Ok(KdfParams::Pbkdf2 { rounds: 600_000 }),
+            _ => bail!("unknown kdf version {}, latest is {}", version, LATEST_VERSION),
+        }
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        match self {
+            KdfParams::Argon2id { .. } => Algorithm::Argon2id,
+            KdfParams::Scrypt { .. } => Algorithm::Scrypt,
+            KdfParams::Pbkdf2 { .. } => Algorithm::Pbkdf2,
+        }
+    }
+
+    /// 由密码和盐派生出 out.len() 字节的密钥
+    pub fn derive(&self, password: &[u8], salt: &[u8], out: &mut [u8]) -> Result<()> {
+        match *self {
+            KdfParams::Argon2id { m_cost, t_cost, p_cost } => {
+                let params = argon2::Params::new(m_cost, t_cost, p_cost, Some(out.len()))
+                    .map_err(|e| anyhow!("argon2: {}", e))?;
+                argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+                    .hash_password_into(password, salt, out)
+                    .map_err(|e| anyhow!("argon2: {}", e))?;
+            }
+            KdfParams::Scrypt { log_n, r, p } => {
+                let params = scrypt::Params::new(log_n, r, p, out.len())
+                    .map_err(|e| anyhow!("scrypt: {}", e))?;
+                scrypt::scrypt(password, salt, &params, out)
+                    .map_err(|e| anyhow!("scrypt: {}", e))?;
+            }
+            KdfParams::Pbkdf2 { rounds } => {
+                if rounds == 0 {
+                    bail!("pbkdf2: rounds must > 0");
+                }
+                pbkdf2::pbkdf2_hmac::<Sha256>(password, salt, rounds, out);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1 +1,417 @@
+pub mod bip39;
+pub mod breach;
+pub mod kdf;
+pub mod otp;
+pub mod passphrase;
 pub mod password;
+pub mod policy;
+pub mod strength;
+pub mod transfer;
+pub mod vault;
+
+#[cfg(test)]
+mod tests {
+    use crate::breach::{sha1_hex, BloomFilter, Breach, BreachChecker, HashFile};
+    use crate::kdf::{Algorithm, KdfParams, LATEST_VERSION};
+    use crate::otp::{hotp, Otp, OtpAlgorithm, OtpKind};
+    use crate::passphrase::{random_passphrase, PassphraseOptions};
+    use crate::password::{derive_encoded, derive_passphrase, derive_password, generate_encoded, generate_password};
+    use crate::policy::{Entropy, PasswordPolicy};
+    use encoder::base32::Base32;
+    use encoder::base64::Base64;
+    use encoder::encoding::{by_name, Encoding};
+    use encoder::hex::Hex;
+    use crate::strength::{estimate, Pattern};
+    use crate::transfer::{export, ColumnMap, Format, ImportPlan};
+    use crate::vault::{Entry, Secret, Vault};
+    use crate::bip39::WORDS;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn kdf_vectors() {
+        // RFC 7914 中的 PBKDF2-HMAC-SHA256 和 scrypt 测试向量
+        let mut out = [0u8; 64];
+        KdfParams::Pbkdf2 { rounds: 1 }.derive(b"passwd", b"salt", &mut out).unwrap();
+        assert_eq!("55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc\
+                    49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783", hex(&out));
+        KdfParams::Scrypt { log_n: 4, r: 1, p: 1 }.derive(b"", b"", &mut out).unwrap();
+        assert_eq!("77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442\
+                    fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906", hex(&out));
+        assert!(KdfParams::Pbkdf2 { rounds: 0 }.derive(b"", b"", &mut out).is_err());
+    }
+
+    #[test]
+    fn derived_password_is_reproducible() {
+        // 已发布版本的输出不能改变
+        let params = KdfParams::from_version(1, Algorithm::Argon2id).unwrap();
+        let passwd = derive_password("hunter2", "example.org", "kim", 1, 16, &params, &PasswordPolicy::default()).unwrap();
+        assert_eq!("=\"fps=vGM1c8./>Z", passwd);
+        assert!(KdfParams::from_version(LATEST_VERSION + 1, Algorithm::Argon2id).is_err());
+    }
+
+    #[test]
+    fn derived_password_covers_classes() {
+        let params = KdfParams::Pbkdf2 { rounds: 10 };
+        let policy = PasswordPolicy::default();
+        let base = derive_password("master", "example.org", "kim", 1, 6, &params, &policy).unwrap();
+        for variant in [
+            derive_password("master", "example.org", "kim", 2, 6, &params, &policy).unwrap(),
+            derive_password("master", "example.or", "gkim", 1, 6, &params, &policy).unwrap(),
+            derive_password("Master", "example.org", "kim", 1, 6, &params, &policy).unwrap(),
+        ] {
+            assert_ne!(base, variant);
+        }
+
+        for length in 6..=64 {
+            let passwd = derive_password("master", "example.org", "kim", 1, length, &params, &policy).unwrap();
+            assert_eq!(length, passwd.len());
+            assert!(passwd.chars().any(|c| c.is_ascii_lowercase()));
+            assert!(passwd.chars().any(|c| c.is_ascii_uppercase()));
+            assert!(passwd.chars().any(|c| c.is_ascii_digit()));
+            assert!(passwd.chars().any(|c| c.is_ascii_punctuation()));
+        }
+        assert!(derive_password("master", "example.org", "kim", 1, 5, &params, &policy).is_err());
+        assert!(derive_password("master", "example.org", "kim", 1, 129, &params, &policy).is_err());
+        assert!(derive_password("", "example.org", "kim", 1, 16, &params, &policy).is_err());
+    }
+
+    #[test]
+    fn policy_constrains_generated_passwords() {
+        let policy = PasswordPolicy {
+            min_digit: 3,
+            min_symbol: 2,
+            forbidden: "$%".to_string(),
+            exclude_ambiguous: true,
+            max_repeat: Some(1),
+            ..PasswordPolicy::default()
+        };
+        for seed in ["jdxjp", "jdwnp", "seed"] {
+            for length in 7..=40 {
+                let passwd = generate_password(seed, length, &policy).unwrap();
+                let passwd = passwd.strip_prefix(&format!("{}: ", seed)).unwrap();
+                assert_eq!(Ok(()), policy.check(passwd).map_err(|e| e.to_string()));
+                assert!(!passwd.contains(['$', '%', 'l', '1', 'O', '0']));
+            }
+        }
+        assert_eq!(generate_password("jdxjp", 16, &policy).unwrap(),
+                   generate_password("jdxjp", 16, &policy).unwrap());
+        assert!(generate_password("jdxjp", 6, &policy).is_err());
+
+        // 只有两个字符时仍能避免连续重复
+        let policy = PasswordPolicy {
+            allowed: Some("ab".to_string()),
+            min_upper: 0,
+            min_digit: 0,
+            min_symbol: 0,
+            max_repeat: Some(1),
+            ..PasswordPolicy::default()
+        };
+        let passwd = policy.generate(&mut Entropy::new(b"seed"), 64).unwrap();
+        assert!(passwd == "ab".repeat(32) || passwd == "ba".repeat(32));
+
+        let impossible = PasswordPolicy { allowed: Some("abc".to_string()), ..PasswordPolicy::default() };
+        assert!(impossible.validate(16).is_err());
+        assert!(PasswordPolicy { max_length: 8, ..PasswordPolicy::default() }.validate(9).is_err());
+        assert!(PasswordPolicy::default().check("aB3$aB3$").is_ok());
+        assert!(PasswordPolicy { max_repeat: Some(2), ..PasswordPolicy::default() }.check("aaaB3$").is_err());
+    }
+
+    #[test]
+    fn vault_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("passwd_vault_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("vault");
+        let _ = std::fs::remove_file(&path);
+
+        let kdf = KdfParams::Pbkdf2 { rounds: 10 };
+        let policy = PasswordPolicy::default();
+        let mut vault = Vault::create(&path, "master", kdf).unwrap();
+        assert!(Vault::create(&path, "master", kdf).is_err());
+        let generated = Secret::Generated { length: 16, counter: 1, kdf, policy: policy.clone() };
+        vault.add(Entry::new("example.org", "kim", generated.clone(), "")).unwrap();
+        vault.add(Entry::new("example.org", "lee", Secret::Stored { password: "hunter2".to_string() }, "pin")).unwrap();
+        assert!(vault.add(Entry::new("example.org", "kim", generated, "")).is_err());
+        vault.save().unwrap();
+        assert!(vault.backup_path().exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            for file in [&path, &vault.backup_path()] {
+                assert_eq!(0o600, std::fs::metadata(file).unwrap().permissions().mode() & 0o777);
+            }
+        }
+
+        // 重新打开后内容一致，派生的密码与直接派生相同
+        let mut vault = Vault::open(&path, "master").unwrap();
+        assert_eq!(2, vault.entries().len());
+        assert!(vault.get("example.org", None).is_err());
+        let kim = vault.get("example.org", Some("kim")).unwrap();
+        assert_eq!(derive_password("master", "example.org", "kim", 1, 16, &kdf, &policy).unwrap(),
+                   vault.password(kim).unwrap());
+        assert_eq!("hunter2", vault.password(vault.get("example.org", Some("lee")).unwrap()).unwrap());
+        assert!(Vault::open(&path, "Master").is_err());
+
+        let before = vault.password(vault.get("example.org", Some("kim")).unwrap()).unwrap();
+        let kim = vault.rotate("example.org", Some("kim"), &policy, 16).unwrap().clone();
+        assert_ne!(before, vault.password(&kim).unwrap());
+        let lee = vault.rotate("example.org", Some("lee"), &policy, 20).unwrap().clone();
+        assert_eq!(20, vault.password(&lee).unwrap().len());
+        assert!(vault.edit("example.org", Some("lee"), |e| e.username = "kim".to_string()).is_err());
+        vault.edit("example.org", Some("lee"), |e| e.notes = "pin 1234".to_string()).unwrap();
+        assert_eq!("lee", vault.remove("example.org", Some("lee")).unwrap().username);
+        vault.save().unwrap();
+        let vault = Vault::open(&path, "master").unwrap();
+        assert_eq!(vec![kim], vault.entries().to_vec());
+
+        // 篡改密文后无法打开
+        let data = std::fs::read_to_string(&path).unwrap();
+        let tampered = data.replacen("\"ciphertext\": \"", "\"ciphertext\": \"00", 1);
+        std::fs::write(&path, tampered).unwrap();
+        assert!(Vault::open(&path, "master").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn strength_detects_patterns() {
+        for (passwd, pattern) in [
+            ("password", Pattern::Dictionary),
+            ("P4$$w0rd", Pattern::Leet),
+            ("qwertyuiop", Pattern::Keyboard),
+            ("!@#$%^&*", Pattern::Keyboard),
+            ("aaaaaaaa", Pattern::Repeat),
+            ("xyzxyzxyz", Pattern::Repeat),
+            ("abcdefgh", Pattern::Sequence),
+            ("987654", Pattern::Sequence),
+        ] {
+            let strength = estimate(passwd);
+            assert!(strength.patterns.contains(&pattern), "{} {:?}", passwd, strength.patterns);
+            assert!(strength.score <= 1, "{} {}", passwd, strength.score);
+            assert!(!strength.feedback.is_empty());
+        }
+
+        // 模式加上随机部分后强度上升
+        let weak = estimate("monkey");
+        let strong = estimate("monkey#8Qz!vR2");
+        assert!(weak.guesses < strong.guesses);
+        assert_eq!(strong.entropy, strong.guesses.log2());
+        assert_eq!(0, estimate("").score);
+
+        // 长的重复密码也能很快估计
+        let start = std::time::Instant::now();
+        for passwd in ["a".repeat(128), "abc".repeat(43), "password1".repeat(15)] {
+            assert!(estimate(&passwd).score <= 1, "{}", passwd);
+        }
+        assert!(start.elapsed() < std::time::Duration::from_secs(2));
+
+        // 生成的密码都应是最高分
+        for seed in ["jdxjp", "jdwnp", "seed"] {
+            let passwd = generate_password(seed, 16, &PasswordPolicy::default()).unwrap();
+            let strength = estimate(passwd.strip_prefix(&format!("{}: ", seed)).unwrap());
+            assert_eq!(4, strength.score);
+            assert!(strength.feedback.is_empty());
+        }
+    }
+
+    #[test]
+    fn passphrases_use_word_list() {
+        let params = KdfParams::Pbkdf2 { rounds: 10 };
+        let options = PassphraseOptions::default();
+        let phrase = derive_passphrase("master", "example.org", "kim", 1, &params, &options).unwrap();
+        assert_eq!(phrase, derive_passphrase("master", "example.org", "kim", 1, &params, &options).unwrap());
+        assert_ne!(phrase, derive_passphrase("master", "example.org", "kim", 2, &params, &options).unwrap());
+        assert!(phrase.split('-').all(|word| WORDS.contains(&word)));
+
+        let options = PassphraseOptions {
+            words: 4,
+            separator: " ".to_string(),
+            capitalize: true,
+            digit: true,
+            symbol: true,
+        };
+        let phrase = random_passphrase(&options).unwrap();
+        assert_eq!(4, phrase.split(' ').count());
+        assert!(phrase.split(' ').all(|word| word.starts_with(|c: char| c.is_ascii_uppercase())));
+        assert_eq!(1, phrase.chars().filter(|c| c.is_ascii_digit()).count());
+        assert_eq!(1, phrase.chars().filter(|c| c.is_ascii_punctuation()).count());
+        assert_ne!(phrase, random_passphrase(&options).unwrap());
+
+        assert_eq!(44.0, PassphraseOptions { words: 4, ..PassphraseOptions::default() }.entropy());
+        assert!(options.entropy() > 44.0 + 10.0);
+        assert!(random_passphrase(&PassphraseOptions { words: 0, ..PassphraseOptions::default() }).is_err());
+    }
+
+    #[test]
+    fn encoded_passwords() {
+        let params = KdfParams::Pbkdf2 { rounds: 10 };
+        let hex = derive_encoded("hunter2", "example.org", "kim", 1, 20, &params, &Hex::LOWER).unwrap();
+        assert_eq!(20, hex.len());
+        assert!(hex.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(hex, derive_encoded("hunter2", "example.org", "kim", 1, 20, &params, &Hex::LOWER).unwrap());
+        assert_ne!(hex[..16], derive_password("hunter2", "example.org", "kim", 1, 16, &params, &PasswordPolicy::default()).unwrap());
+
+        // base64 的填充不会出现在密码中
+        let b64 = derive_encoded("hunter2", "example.org", "kim", 1, 8, &params, &Base64::STANDARD).unwrap();
+        assert_eq!(8, b64.len());
+        assert!(!b64.contains('='));
+
+        for name in ["base32", "base58", "base64url", "ascii85", "z85"] {
+            let passwd = generate_encoded("jdxjp", 16, &*by_name(name).unwrap()).unwrap();
+            assert_eq!(16, passwd.strip_prefix("jdxjp: ").unwrap().chars().count());
+        }
+        assert!(generate_encoded("jdxjp", 4, &Hex::LOWER).is_err());
+        assert_eq!("jdxjp: fU[pC{>5+rL_:Z<C", generate_password("jdxjp", 16, &PasswordPolicy::default()).unwrap());
+    }
+
+    #[test]
+    fn otp_rfc_vectors() {
+        // RFC 4226 附录 D
+        let secret = b"12345678901234567890";
+        let codes = ["755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583", "399871", "520489"];
+        for (counter, code) in codes.iter().enumerate() {
+            assert_eq!(*code, hotp(secret, counter as u64, 6, OtpAlgorithm::Sha1).unwrap());
+        }
+
+        // RFC 6238 附录 B
+        let seeds = [
+            (OtpAlgorithm::Sha1, &b"12345678901234567890"[..]),
+            (OtpAlgorithm::Sha256, &b"12345678901234567890123456789012"[..]),
+            (OtpAlgorithm::Sha512, &b"1234567890123456789012345678901234567890123456789012345678901234"[..]),
+        ];
+        let vectors = [
+            (59, ["94287082", "46119246", "90693936"]),
+            (1111111109, ["07081804", "68084774", "25091201"]),
+            (1111111111, ["14050471", "67062674", "99943326"]),
+            (1234567890, ["89005924", "91819424", "93441116"]),
+            (2000000000, ["69279037", "90698825", "38618901"]),
+            (20000000000, ["65353130", "77737706", "47863826"]),
+        ];
+        for (time, codes) in vectors {
+            for ((algorithm, seed), code) in seeds.iter().zip(codes) {
+                let uri = format!("otpauth://totp/ACME%20Co:kim?secret={}&algorithm={}&digits=8", Base32::STANDARD_NO_PAD.encode(seed), algorithm);
+                let otp: Otp = uri.parse().unwrap();
+                assert_eq!(code, otp.code_at(time).unwrap());
+            }
+        }
+
+        let otp: Otp = "otpauth://hotp/ACME%20Co:kim?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACME&counter=3".parse().unwrap();
+        assert_eq!(OtpKind::Hotp { counter: 3 }, otp.kind);
+        assert_eq!("ACME Co:kim", otp.label);
+        assert_eq!(Some("ACME".to_string()), otp.issuer);
+        assert_eq!("969429", otp.code_at(0).unwrap());
+        assert_eq!(otp, otp.to_uri().parse().unwrap());
+
+        let otp: Otp = "gezd gnbv gy3t qojq gezd gnbv gy3t qojq".parse().unwrap();
+        assert_eq!(b"12345678901234567890".to_vec(), otp.secret);
+        assert!("JBSWY3DP1".parse::<Otp>().is_err());
+        assert_eq!(Ok(30), "JBSWY3DPEHPK3PXP".parse::<Otp>().map(|otp| match otp.kind {
+            OtpKind::Totp { period } => period,
+            OtpKind::Hotp { .. } => 0,
+        }).map_err(|e| e.to_string()));
+        assert!("otpauth://totp/x?secret=JBSWY3DP&digits=9".parse::<Otp>().is_err());
+        assert!("otpauth://hotp/x?secret=JBSWY3DP".parse::<Otp>().is_err());
+        assert!("otpauth://totp/x?secret=JBSWY3D1".parse::<Otp>().is_err());
+    }
+
+    #[test]
+    fn import_and_export_formats() {
+        let dir = std::env::temp_dir().join(format!("passwd_transfer_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("vault");
+        let _ = std::fs::remove_file(&path);
+        let mut vault = Vault::create(&path, "master", KdfParams::Pbkdf2 { rounds: 10 }).unwrap();
+        vault.add(Entry::new("example.org", "kim", Secret::Stored { password: "old".to_string() }, "")).unwrap();
+
+        let keepass = "\"Group\",\"Title\",\"Username\",\"Password\",\"URL\",\"Notes\",\"TOTP\"\n\
+                       \"Root\",\"GitHub\",\"kim\",\"pa,ss\"\"1\",\"\",\"two\nlines\",\"JBSWY3DPEHPK3PXP\"\n\
+                       \"Root\",\"\",\"lee\",\"x\",\"https://mail.example/login\",\"\",\"\"\n\
+                       \"Root\",\"NoPass\",\"kim\",\"\",\"\",\"\",\"\"\n\
+                       \"Root\",\"GitHub\",\"kim\",\"dup\",\"\",\"\",\"\"\n\
+                       \"Root\",\"example.org\",\"kim\",\"new\",\"\",\"\",\"\"\n";
+        let plan = ImportPlan::new(&vault, keepass, Format::Keepass, None).unwrap();
+        let sites: Vec<&str> = plan.entries.iter().map(|e| e.site.as_str()).collect();
+        assert_eq!(vec!["GitHub", "mail.example"], sites);
+        assert_eq!("two\nlines", plan.entries[0].notes);
+        assert!(plan.entries[0].otp.as_ref().unwrap().starts_with("otpauth://totp/"));
+        assert_eq!("https://mail.example/login", plan.entries[1].url);
+        assert!(plan.entries[1].notes.is_empty());
+        let skipped: Vec<(usize, &str)> = plan.skipped.iter().map(|s| (s.row, s.reason.as_str())).collect();
+        assert_eq!(vec![(4, "no password"), (5, "duplicate row"), (6, "already in vault")], skipped);
+        assert_eq!(2, plan.apply(&mut vault).unwrap());
+        assert_eq!("pa,ss\"1", vault.password(vault.get("GitHub", None).unwrap()).unwrap());
+
+        let bitwarden = r#"{"encrypted": false, "items": [
+            {"type": 2, "name": "Note", "notes": "hi"},
+            {"type": 1, "name": "Bank", "login": {"username": "k", "password": "b", "uris": [{"uri": "https://bank.example"}]}}
+        ]}"#;
+        let plan = ImportPlan::new(&vault, bitwarden, Format::Bitwarden, None).unwrap();
+        assert_eq!(1, plan.entries.len());
+        assert_eq!("not a login item", plan.skipped[0].reason);
+        plan.apply(&mut vault).unwrap();
+        assert!(ImportPlan::new(&vault, r#"{"encrypted": true}"#, Format::Bitwarden, None).is_err());
+
+        let map: ColumnMap = "site=Name,username=Login,password=Secret".parse().unwrap();
+        let plan = ImportPlan::new(&vault, "Name,Login,Secret\nshop,kim,s3\n", Format::Csv, Some(&map)).unwrap();
+        assert_eq!(1, plan.entries.len());
+        assert!(ImportPlan::new(&vault, "a,b\n1,2\n", Format::Csv, Some(&map)).is_err());
+        assert!("site=Name".parse::<ColumnMap>().is_err());
+
+        // 导出后再导入到空密码库得到相同的条目
+        for format in [Format::Keepass, Format::Bitwarden, Format::Csv] {
+            let data = export(&vault, format, None).unwrap();
+            let other = dir.join(format!("{:?}", format));
+            let _ = std::fs::remove_file(&other);
+            let empty = Vault::create(&other, "master", KdfParams::Pbkdf2 { rounds: 10 }).unwrap();
+            let plan = ImportPlan::new(&empty, &data, format, None).unwrap();
+            assert!(plan.skipped.is_empty());
+            for (a, b) in vault.entries().iter().zip(&plan.entries) {
+                assert_eq!((&a.site, &a.username, &a.notes, &a.otp), (&b.site, &b.username, &b.notes, &b.otp));
+                if format != Format::Csv {
+                    assert_eq!(a.url, b.url);
+                }
+                assert_eq!(vault.password(a).unwrap(), empty.password(b).unwrap());
+            }
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn breached_passwords_are_found() {
+        let dir = std::env::temp_dir().join(format!("passwd_breach_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!("5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8", sha1_hex("password"));
+
+        let words: Vec<String> = (0..500).map(|i| format!("word{}", i)).collect();
+        let mut lines: Vec<String> = words.iter()
+            .enumerate()
+            .map(|(i, w)| format!("{}:{}", sha1_hex(w), i + 1))
+            .collect();
+        lines.sort();
+
+        // 每一行包括首尾都能找到，换行符可以是 \r\n 或 \n
+        for newline in ["\r\n", "\n"] {
+            let path = dir.join("hashes.txt");
+            std::fs::write(&path, lines.join(newline) + newline).unwrap();
+            let mut file = HashFile::open(&path).unwrap();
+            for (i, word) in words.iter().enumerate() {
+                assert_eq!(Breach::Found(i as u64 + 1), file.check(word).unwrap());
+            }
+            for i in 0..100 {
+                assert_eq!(Breach::Clean, file.check(&format!("other{}", i)).unwrap());
+            }
+            assert!(file.count("zz").is_err());
+        }
+
+        let bloom = dir.join("hashes.bloom");
+        BloomFilter::build(dir.join("hashes.txt"), &bloom, 0.01).unwrap();
+        let mut checker = BreachChecker::open(&bloom).unwrap();
+        assert!(matches!(checker, BreachChecker::Bloom(_)));
+        assert!(words.iter().all(|w| checker.check(w).unwrap() == Breach::Likely));
+        let false_positives = (0..1000).filter(|i| checker.check(&format!("other{}", i)).unwrap().is_breached()).count();
+        assert!(false_positives < 50);
+        assert!(matches!(BreachChecker::open(dir.join("hashes.txt")).unwrap(), BreachChecker::HashFile(_)));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,277 @@
+//! 一次性密码
+//!
+//! 按 RFC 4226 (HOTP) 和 RFC 6238 (TOTP) 生成动态口令，
+//! 密钥可以是 base32 字符串或 otpauth:// URI。
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Error, Result};
+use encoder::base32::Base32;
+use encoder::encoding::Encoding;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+
+/// HMAC 使用的哈希算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl FromStr for OtpAlgorithm {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_uppercase().as_str() {
+            "SHA1" => Ok(OtpAlgorithm::Sha1),
+            "SHA256" => Ok(OtpAlgorithm::Sha256),
+            "SHA512" => Ok(OtpAlgorithm::Sha512),
+            _ => bail!("unknown otp algorithm `{}`, expected SHA1, SHA256 or SHA512", s),
+        }
+    }
+}
+
+impl fmt::Display for OtpAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            OtpAlgorithm::Sha1 => "SHA1",
+            OtpAlgorithm::Sha256 => "SHA256",
+            OtpAlgorithm::Sha512 => "SHA512",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// 基于计数器或基于时间
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtpKind {
+    Hotp { counter: u64 },
+    Totp { period: u64 },
+}
+
+/// 一次性密码的全部参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Otp {
+    pub kind: OtpKind,
+    pub secret: Vec<u8>,
+    pub algorithm: OtpAlgorithm,
+    pub digits: u32,
+    pub label: String,
+    pub issuer: Option<String>,
+}
+
+impl FromStr for Otp {
+    type Err = Error;
+
+    /// otpauth:// URI 或 base32 密钥，后者使用 TOTP 的缺省参数
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.starts_with("otpauth://") {
+            Otp::from_uri(s)
+        } else {
+            Otp::totp(decode_secret(s)?)
+        }
+    }
+}
+
+impl Otp {
+    /// SHA1、6 位、30 秒的 TOTP，大多数服务使用这组参数
+    pub fn totp(secret: Vec<u8>) -> Result<Self, Error> {
+        let otp = Otp {
+            kind: OtpKind::Totp { period: 30 },
+            secret,
+            algorithm: OtpAlgorithm::Sha1,
+            digits: 6,
+            label: String::new(),
+            issuer: None,
+        };
+        otp.validate()?;
+        Ok(otp)
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.secret.is_empty() {
+            bail!("otp secret must not be empty");
+        }
+        if !(6..=8).contains(&self.digits) {
+            bail!("otp digits must between 6 and 8");
+        }
+        if self.kind == (OtpKind::Totp { period: 0 }) {
+            bail!("otp period must > 0");
+        }
+        Ok(())
+    }
+
+    /// 解析 otpauth://totp/label?secret=...&issuer=...&algorithm=...&digits=...&period=...
+    pub fn from_uri(uri: &str) -> Result<Self, Error> {
+        let rest = uri.strip_prefix("otpauth://").ok_or_else(|| anyhow!("not an otpauth uri"))?;
+        let (kind, rest) = rest.split_once('/').ok_or_else(|| anyhow!("otpauth uri has no label"))?;
+        let (label, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+        let mut secret = None;
+        let mut issuer = None;
+        let mut algorithm = OtpAlgorithm::Sha1;
+        let mut digits = 6;
+        let mut period = 30;
+        let mut counter = None;
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value)?;
+            match key {
+                "secret" => secret = Some(decode_secret(&value)?),
+                "issuer" => issuer = Some(value),
+                "algorithm" => algorithm = value.parse()?,
+                "digits" => digits = value.parse().map_err(|_| anyhow!("invalid otp digits `{}`", value))?,
+                "period" => period = value.parse().map_err(|_| anyhow!("invalid otp period `{}`", value))?,
+                "counter" => counter = Some(value.parse().map_err(|_| anyhow!("invalid otp counter `{}`", value))?),
+                _ => {}
+            }
+        }
+
+        let kind = match kind {
+            "totp" => OtpKind::Totp { period },
+            "hotp" => OtpKind::Hotp { counter: counter.ok_or_else(|| anyhow!("hotp uri needs a counter"))? },
+            _ => bail!("unknown otp type `{}`", kind),
+        };
+        let otp = Otp {
+            kind,
+            secret: secret.ok_or_else(|| anyhow!("otpauth uri has no secret"))?,
+            algorithm,
+            digits,
+            label: percent_decode(label)?,
+            issuer,
+        };
+        otp.validate()?;
+
+        Ok(otp)
+    }
+
+    pub fn to_uri(&self) -> String {
+        let (kind, param) = match self.kind {
+            OtpKind::Hotp { counter } => ("hotp", format!("counter={}", counter)),
+            OtpKind::Totp { period } => ("totp", format!("period={}", period)),
+        };
+        let mut uri = format!("otpauth://{}/{}?secret={}", kind, percent_encode(&self.label), Base32::STANDARD_NO_PAD.encode(&self.secret));
+        if let Some(issuer) = &self.issuer {
+            uri += &format!("&issuer={}", percent_encode(issuer));
+        }
+        uri + &format!("&algorithm={}&digits={}&{}", self.algorithm, self.digits, param)
+    }
+
+    /// time 时刻的口令，HOTP 忽略时间而使用当前计数器
+    pub fn code_at(&self, time: u64) -> Result<String, Error> {
+        match self.kind {
+            OtpKind::Hotp { counter } => hotp(&self.secret, counter, self.digits, self.algorithm),
+            OtpKind::Totp { period } => totp(&self.secret, time, period, self.digits, self.algorithm),
+        }
+    }
+
+    pub fn code(&self) -> Result<String, Error> {
+        self.code_at(unix_time())
+    }
+
+    /// TOTP 当前口令的剩余有效秒数
+    pub fn remaining(&self) -> Option<u64> {
+        match self.kind {
+            OtpKind::Hotp { .. } => None,
+            OtpKind::Totp { period } => Some(period - unix_time() % period),
+        }
+    }
+}
+
+/// RFC 4226 HOTP
+///
+/// # Example
+/// ```
+/// use encryptor::otp::{hotp, OtpAlgorithm};
+///
+/// assert_eq!("755224", hotp(b"12345678901234567890", 0, 6, OtpAlgorithm::Sha1).unwrap());
+/// ```
+pub fn hotp(secret: &[u8], counter: u64, digits: u32, algorithm: OtpAlgorithm) -> Result<String, Error> {
+    if !(6..=8).contains(&digits) {
+        bail!("otp digits must between 6 and 8");
+    }
+
+    let hash = hmac(algorithm, secret, &counter.to_be_bytes())?;
+    // 动态截断：以最后一字节的低 4 位为偏移取 31 位整数
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let code = u32::from_be_bytes([hash[offset], hash[offset + 1], hash[offset + 2], hash[offset + 3]]) & 0x7fff_ffff;
+
+    Ok(format!("{:0width$}", code % 10u32.pow(digits), width = digits as usize))
+}
+
+/// RFC 6238 TOTP，计数器为 time / period
+pub fn totp(secret: &[u8], time: u64, period: u64, digits: u32, algorithm: OtpAlgorithm) -> Result<String, Error> {
+    if period == 0 {
+        bail!("otp period must > 0");
+    }
+    hotp(secret, time / period, digits, algorithm)
+}
+
+fn hmac(algorithm: OtpAlgorithm, key: &[u8], msg: &[u8]) -> Result<Vec<u8>, Error> {
+    let hash = match algorithm {
+        OtpAlgorithm::Sha1 => {
+            let mut mac = Hmac::<Sha1>::new_from_slice(key).map_err(|e| anyhow!("hmac: {}", e))?;
+            mac.update(msg);
+            mac.finalize().into_bytes().to_vec()
+        }
+        OtpAlgorithm::Sha256 => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(key).map_err(|e| anyhow!("hmac: {}", e))?;
+            mac.update(msg);
+            mac.finalize().into_bytes().to_vec()
+        }
+        OtpAlgorithm::Sha512 => {
+            let mut mac = Hmac::<Sha512>::new_from_slice(key).map_err(|e| anyhow!("hmac: {}", e))?;
+            mac.update(msg);
+            mac.finalize().into_bytes().to_vec()
+        }
+    };
+    Ok(hash)
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// base32 密钥，忽略大小写、空格和填充
+fn decode_secret(s: &str) -> Result<Vec<u8>, Error> {
+    let s: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+    Base32::STANDARD_NO_PAD.decode(&s).map_err(|e| anyhow!("invalid base32 secret: {}", e))
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b':' | b'@' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> Result<String, Error> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3])?;
+                out.push(u8::from_str_radix(hex, 16).map_err(|_| anyhow!("invalid percent escape `%{}`", hex))?);
+                i += 3;
+            }
+            b'%' => bail!("truncated percent escape"),
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    Ok(String::from_utf8(out)?)
+}
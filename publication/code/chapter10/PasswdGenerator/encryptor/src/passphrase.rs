@@ -0,0 +1,99 @@
+//! 口令短语
+//!
+//! 从内置的 BIP39 英文词表中取若干单词组成好记的口令，熵可来自系统随机数，
+//! 也可来自主密码经 KDF 派生的密钥，后者对相同输入总是得到相同的口令。
+use anyhow::{bail, Error, Result};
+
+use crate::policy::{CharClass, Entropy};
+use crate::bip39::WORDS;
+
+/// 单词数上限
+pub const MAX_WORDS: usize = 64;
+
+/// 口令短语的组成方式，缺省为 6 个小写单词以 - 连接
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassphraseOptions {
+    pub words: usize,
+    pub separator: String,
+    /// 每个单词首字母大写
+    pub capitalize: bool,
+    /// 在随机一个单词后插入一个数字
+    pub digit: bool,
+    /// 在随机一个单词后插入一个符号
+    pub symbol: bool,
+}
+
+impl Default for PassphraseOptions {
+    fn default() -> Self {
+        PassphraseOptions {
+            words: 6,
+            separator: "-".to_string(),
+            capitalize: false,
+            digit: false,
+            symbol: false,
+        }
+    }
+}
+
+impl PassphraseOptions {
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.words == 0 || self.words > MAX_WORDS {
+            bail!("words must between 1 and {}", MAX_WORDS);
+        }
+        Ok(())
+    }
+
+    /// 口令的熵，单位为位；BIP39 词表每个单词 11 位，大小写和分隔符固定，不增加熵
+    ///
+    /// # Example
+    /// ```
+    /// use encryptor::passphrase::PassphraseOptions;
+    ///
+    /// let options = PassphraseOptions::default();
+    /// assert_eq!(66.0, options.entropy());
+    /// ```
+    pub fn entropy(&self) -> f64 {
+        let words = self.words as f64;
+        let mut bits = words * (WORDS.len() as f64).log2();
+        if self.digit {
+            bits += (CharClass::Digit.chars().len() as f64 * words).log2();
+        }
+        if self.symbol {
+            bits += (CharClass::Symbol.chars().len() as f64 * words).log2();
+        }
+        bits
+    }
+
+    /// 由熵源生成口令，相同的熵总是得到相同的口令
+    pub fn generate(&self, entropy: &mut Entropy) -> Result<String, Error> {
+        self.validate()?;
+
+        let mut words: Vec<String> = (0..self.words)
+            .map(|_| {
+                let word = WORDS[entropy.below(WORDS.len())];
+                if self.capitalize {
+                    let mut chars = word.chars();
+                    chars.next().map_or(String::new(), |c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                } else {
+                    word.to_string()
+                }
+            })
+            .collect();
+
+        for (enabled, class) in [(self.digit, CharClass::Digit), (self.symbol, CharClass::Symbol)] {
+            if enabled {
+                let set: Vec<char> = class.chars().chars().collect();
+                let c = set[entropy.below(set.len())];
+                let loc = entropy.below(words.len());
+                words[loc].push(c);
+            }
+        }
+
+        Ok(words.join(&self.separator))
+    }
+}
+
+/// 用系统随机数生成口令
+pub fn random_passphrase(options: &PassphraseOptions) -> Result<String, Error> {
+    options.generate(&mut Entropy::random()?)
+}
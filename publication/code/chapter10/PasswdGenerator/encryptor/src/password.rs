@@ -1,29 +1,51 @@
 use anyhow::{bail, Error, Result};
 use encoder::base58::Encoder;
+use encoder::encoding::Encoding;
 use hash::merhash::mersenne_hash;
+use sha2::{Digest, Sha256};
+
+use crate::kdf::KdfParams;
+use crate::passphrase::PassphraseOptions;
+use crate::policy::{Entropy, PasswordPolicy};
 
 /// 密码子 (长度 100)，可随意交换次序，增减字符，实现个性化定制
 const CRYPTO: &str = "!pqHr$*+STKU1%Vst_uv:w{WSX&YZ-/01_2.34<ABECo|x#yDE^FG?HEI[]JK>LM#NOBWPQ:Ra@}cde56R7=8l9f/9gIhi,jkzmn";
 
-/// 哈希密码函数，利用哈希值高次方值来选择密码子中字符
+/// KDF 输出的字节数
+const ENTROPY_LEN: usize = 32;
+
+/// 口令短语的盐后缀，使同一账户的密码和口令互不相关
+const PASSPHRASE_TAG: &[u8] = b"passphrase";
+
+/// 编码输出的盐后缀
+const ENCODED_TAG: &[u8] = b"encoded";
+
+/// 哈希密码函数，利用哈希值高次方值来选择密码子中字符，再按策略生成密码
 ///
 /// #Example
 /// ```
 /// use encryptor::password::generate_password;
+/// use encryptor::policy::PasswordPolicy;
 /// let seed = "jdwnp";
 /// let length = 16;
-/// let passwd = generate_password(seed, length);
+/// let passwd = generate_password(seed, length, &PasswordPolicy::default());
 /// match passwd {
 ///     Ok(val) => println!("{:#?}", val),
 ///     Err(err) => println!("{:#?}", err),
 /// }
 /// ```
-pub fn generate_password(seed: &str, length: usize) -> Result<String, Error> {
-    // 判断秘密长度
-    if length < 6 {
-        bail!("length must >= 6");
-    }
+pub fn generate_password(seed: &str, length: usize, policy: &PasswordPolicy) -> Result<String, Error> {
+    // 判断策略和长度
+    policy.validate(length)?;
+
+    let mut entropy = seed_entropy(seed, length);
+    let passwd = policy.generate(&mut entropy, length)?;
 
+    Ok(format!("{}: {}", seed, passwd))
+}
+
+/// 种子经梅森哈希和 base58 编码，以结果的哈希为熵
+fn seed_entropy(seed: &str, length: usize) -> Entropy {
     // 计算 mer_hash 的 p 次方
     let p = match length {
         6..=10 => 1,
@@ -40,7 +62,7 @@ pub fn generate_password(seed: &str, length: usize) -> Result<String, Error> {
         let loc = mer_hash % crypto_len;
         let nthc = CRYPTO.chars().nth(loc).expect("Error while getting char!");
         passwd.push(nthc);
-        mer_hash = mer_hash / crypto_len;
+        mer_hash /= crypto_len;
     }
 
     // 将 seed 中字符逐个加入 passwd
@@ -53,12 +75,135 @@ pub fn generate_password(seed: &str, length: usize) -> Result<String, Error> {
     // 将 passwd 编码为 base58
     passwd = passwd.encode_to_base58();
 
-    // 长度不够，interval 来凑
-    let interval = passwd.clone();
-    while passwd.len() < length {
-        passwd += &interval;
+    // 以编码结果的哈希为熵
+    Entropy::new(&Sha256::digest(passwd.as_bytes()))
+}
+
+/// 与 generate_password 相同的种子，但输出为指定编码的字符，不套用字符策略
+///
+/// #Example
+/// ```
+/// use encoder::hex::Hex;
+/// use encryptor::password::generate_encoded;
+/// let passwd = generate_encoded("jdwnp", 16, &Hex::LOWER).unwrap();
+/// assert_eq!(16 + "jdwnp: ".len(), passwd.len());
+/// ```
+pub fn generate_encoded(seed: &str, length: usize, encoding: &dyn Encoding) -> Result<String, Error> {
+    PasswordPolicy::default().validate(length)?;
+    let passwd = encode_entropy(&mut seed_entropy(seed, length), length, encoding)?;
+
+    Ok(format!("{}: {}", seed, passwd))
+}
+
+/// 派生密码函数，主密码、站点、登录名和计数器经 KDF 得到熵，再按策略映射到字符集
+///
+/// 修改密码时只需增加 counter，其他输入不变
+///
+/// #Example
+/// ```
+/// use encryptor::kdf::KdfParams;
+/// use encryptor::password::derive_password;
+/// use encryptor::policy::PasswordPolicy;
+/// let params = KdfParams::Pbkdf2 { rounds: 1000 };
+/// let policy = PasswordPolicy::default();
+/// let passwd = derive_password("master", "example.org", "jdxjp", 1, 16, &params, &policy).unwrap();
+/// assert_eq!(16, passwd.chars().count());
+/// assert!(policy.check(&passwd).is_ok());
+/// ```
+pub fn derive_password(
+    master: &str,
+    site: &str,
+    login: &str,
+    counter: u32,
+    length: usize,
+    params: &KdfParams,
+    policy: &PasswordPolicy,
+) -> Result<String, Error> {
+    policy.validate(length)?;
+    let mut entropy = derive_entropy(master, site, login, counter, params, b"")?;
+
+    policy.generate(&mut entropy, length)
+}
+
+/// 派生口令短语函数，与 derive_password 输入相同，但使用不同的盐
+///
+/// #Example
+/// ```
+/// use encryptor::kdf::KdfParams;
+/// use encryptor::passphrase::PassphraseOptions;
+/// use encryptor::password::derive_passphrase;
+/// let params = KdfParams::Pbkdf2 { rounds: 1000 };
+/// let options = PassphraseOptions::default();
+/// let phrase = derive_passphrase("master", "example.org", "jdxjp", 1, &params, &options).unwrap();
+/// assert_eq!(6, phrase.split('-').count());
+/// ```
+pub fn derive_passphrase(
+    master: &str,
+    site: &str,
+    login: &str,
+    counter: u32,
+    params: &KdfParams,
+    options: &PassphraseOptions,
+) -> Result<String, Error> {
+    options.validate()?;
+    let mut entropy = derive_entropy(master, site, login, counter, params, PASSPHRASE_TAG)?;
+
+    options.generate(&mut entropy)
+}
+
+/// 派生指定编码的密码，与 derive_password 输入相同，但使用不同的盐
+pub fn derive_encoded(
+    master: &str,
+    site: &str,
+    login: &str,
+    counter: u32,
+    length: usize,
+    params: &KdfParams,
+    encoding: &dyn Encoding,
+) -> Result<String, Error> {
+    PasswordPolicy::default().validate(length)?;
+    let mut entropy = derive_entropy(master, site, login, counter, params, ENCODED_TAG)?;
+
+    encode_entropy(&mut entropy, length, encoding)
+}
+
+/// 多取 8 字节编码后截取，去掉填充字符
+fn encode_entropy(entropy: &mut Entropy, length: usize, encoding: &dyn Encoding) -> Result<String, Error> {
+    let encoded = encoding.encode(&entropy.bytes(length + 8));
+    let passwd: String = encoded.chars().filter(|&c| c != '=').take(length).collect();
+    if passwd.chars().count() < length {
+        bail!("encoded password is shorter than {}", length);
     }
+    Ok(passwd)
+}
 
-    // 返回前 length 个字符作为密码
-    Ok(format!("{}: {}", seed, &passwd[..length]))
+fn derive_entropy(
+    master: &str,
+    site: &str,
+    login: &str,
+    counter: u32,
+    params: &KdfParams,
+    tag: &[u8],
+) -> Result<Entropy, Error> {
+    if master.is_empty() || site.is_empty() {
+        bail!("master password and site must not be empty");
+    }
+
+    let mut salt = kdf_salt(site, login, counter);
+    salt.extend_from_slice(tag);
+    let mut key = [0u8; ENTROPY_LEN];
+    params.derive(master.as_bytes(), &salt, &mut key)?;
+
+    Ok(Entropy::new(&key))
+}
+
+/// 各字段带长度前缀，避免 ("ab", "c") 和 ("a", "bc") 得到相同的盐
+fn kdf_salt(site: &str, login: &str, counter: u32) -> Vec<u8> {
+    let mut salt = Vec::new();
+    for field in [site, login] {
+        salt.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        salt.extend_from_slice(field.as_bytes());
+    }
+    salt.extend_from_slice(&counter.to_be_bytes());
+    salt
 }
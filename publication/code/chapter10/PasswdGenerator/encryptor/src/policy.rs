@@ -0,0 +1,276 @@
+//! 密码策略
+//!
+//! 规定字符类别的最少个数、可用和禁用字符、长度范围以及连续重复字符的上限，
+//! 由确定性的熵源生成满足策略的密码。
+use anyhow::{anyhow, bail, Error, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const LOWER: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGIT: &str = "0123456789";
+const SYMBOL: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+/// 容易看错的字符
+pub const AMBIGUOUS: &str = "Il1|O0o";
+
+/// 字符类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharClass {
+    Lower,
+    Upper,
+    Digit,
+    Symbol,
+}
+
+impl CharClass {
+    pub const ALL: [CharClass; 4] = [CharClass::Lower, CharClass::Upper, CharClass::Digit, CharClass::Symbol];
+
+    pub fn chars(&self) -> &'static str {
+        match self {
+            CharClass::Lower => LOWER,
+            CharClass::Upper => UPPER,
+            CharClass::Digit => DIGIT,
+            CharClass::Symbol => SYMBOL,
+        }
+    }
+
+    pub fn of(c: char) -> Option<CharClass> {
+        CharClass::ALL.into_iter().find(|class| class.chars().contains(c))
+    }
+}
+
+/// 密码策略，缺省要求四类字符各至少一个
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub min_lower: usize,
+    pub min_upper: usize,
+    pub min_digit: usize,
+    pub min_symbol: usize,
+    /// 指定后只使用其中的字符，否则使用全部四类字符
+    pub allowed: Option<String>,
+    pub forbidden: String,
+    pub exclude_ambiguous: bool,
+    /// 同一字符最多连续出现的次数
+    pub max_repeat: Option<usize>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        PasswordPolicy {
+            min_length: 6,
+            max_length: 128,
+            min_lower: 1,
+            min_upper: 1,
+            min_digit: 1,
+            min_symbol: 1,
+            allowed: None,
+            forbidden: String::new(),
+            exclude_ambiguous: false,
+            max_repeat: None,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    pub fn min_count(&self, class: CharClass) -> usize {
+        match class {
+            CharClass::Lower => self.min_lower,
+            CharClass::Upper => self.min_upper,
+            CharClass::Digit => self.min_digit,
+            CharClass::Symbol => self.min_symbol,
+        }
+    }
+
+    /// 可用字符：去掉禁用字符、易混淆字符和重复字符后的字母表
+    pub fn alphabet(&self) -> Vec<char> {
+        let source = match &self.allowed {
+            Some(allowed) => allowed.clone(),
+            None => CharClass::ALL.iter().map(|class| class.chars()).collect(),
+        };
+
+        let mut alphabet: Vec<char> = Vec::new();
+        for c in source.chars() {
+            if alphabet.contains(&c)
+                || self.forbidden.contains(c)
+                || (self.exclude_ambiguous && AMBIGUOUS.contains(c))
+            {
+                continue;
+            }
+            alphabet.push(c);
+        }
+
+        alphabet
+    }
+
+    fn class_alphabet(alphabet: &[char], class: Option<CharClass>) -> Vec<char> {
+        alphabet.iter().copied().filter(|&c| CharClass::of(c) == class).collect()
+    }
+
+    /// 检查策略本身能否生成 length 长的密码
+    pub fn validate(&self, length: usize) -> Result<(), Error> {
+        if self.min_length > self.max_length {
+            bail!("min length {} is greater than max length {}", self.min_length, self.max_length);
+        }
+        if length < self.min_length || length > self.max_length {
+            bail!("length must between {} and {}", self.min_length, self.max_length);
+        }
+        if self.max_repeat == Some(0) {
+            bail!("max repeat must >= 1");
+        }
+
+        let alphabet = self.alphabet();
+        if alphabet.is_empty() {
+            bail!("no characters left to choose from");
+        }
+        let mut required = 0;
+        for class in CharClass::ALL {
+            let min = self.min_count(class);
+            if min > 0 && Self::class_alphabet(&alphabet, Some(class)).is_empty() {
+                bail!("policy requires {:?} characters but none are allowed", class);
+            }
+            required += min;
+        }
+        if required > length {
+            bail!("policy requires {} characters but length is {}", required, length);
+        }
+
+        Ok(())
+    }
+
+    /// 检查密码是否满足策略
+    ///
+    /// # Example
+    /// ```
+    /// use encryptor::policy::PasswordPolicy;
+    ///
+    /// let policy = PasswordPolicy::default();
+    /// assert!(policy.check("aB3$xy").is_ok());
+    /// assert!(policy.check("abcdef").is_err());
+    /// ```
+    pub fn check(&self, passwd: &str) -> Result<(), Error> {
+        let chars: Vec<char> = passwd.chars().collect();
+        if chars.len() < self.min_length || chars.len() > self.max_length {
+            bail!("length must between {} and {}", self.min_length, self.max_length);
+        }
+
+        let alphabet = self.alphabet();
+        if let Some(c) = chars.iter().find(|c| !alphabet.contains(c)) {
+            bail!("character `{}` is not allowed", c);
+        }
+        for class in CharClass::ALL {
+            let count = chars.iter().filter(|&&c| CharClass::of(c) == Some(class)).count();
+            if count < self.min_count(class) {
+                bail!("needs at least {} {:?} characters", self.min_count(class), class);
+            }
+        }
+        if let Some(k) = self.max_repeat {
+            if chars.windows(k + 1).any(|w| w.iter().all(|&c| c == w[0])) {
+                bail!("a character repeats more than {} times in a row", k);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 由熵源生成满足策略的密码，相同的熵总是得到相同的密码
+    ///
+    /// 先从字母表中取出不受约束的部分，再按类别插入各类的最少字符，
+    /// 最后把超出连续上限的字符换成同类别的其他字符
+    pub fn generate(&self, entropy: &mut Entropy, length: usize) -> Result<String, Error> {
+        self.validate(length)?;
+
+        let alphabet = self.alphabet();
+        let required: usize = CharClass::ALL.iter().map(|&class| self.min_count(class)).sum();
+        let mut passwd: Vec<char> = (0..length - required)
+            .map(|_| alphabet[entropy.below(alphabet.len())])
+            .collect();
+        for class in CharClass::ALL {
+            let set = Self::class_alphabet(&alphabet, Some(class));
+            for _ in 0..self.min_count(class) {
+                let c = set[entropy.below(set.len())];
+                let loc = entropy.below(passwd.len() + 1);
+                passwd.insert(loc, c);
+            }
+        }
+
+        if let Some(k) = self.max_repeat {
+            let mut run = 1;
+            for i in 1..passwd.len() {
+                run = if passwd[i] == passwd[i - 1] { run + 1 } else { 1 };
+                if run <= k {
+                    continue;
+                }
+
+                // 换入的字符若与后一个相同，下一轮会继续处理
+                let set: Vec<char> = Self::class_alphabet(&alphabet, CharClass::of(passwd[i]))
+                    .into_iter()
+                    .filter(|&c| c != passwd[i - 1])
+                    .collect();
+                if set.is_empty() {
+                    bail!("not enough characters to avoid repeating `{}`", passwd[i]);
+                }
+                passwd[i] = set[entropy.below(set.len())];
+                run = 1;
+            }
+        }
+
+        Ok(passwd.into_iter().collect())
+    }
+}
+
+/// 确定性的熵源：把种子当作大端大整数逐次取余，
+/// 剩余的熵不够时用 SHA-256(种子 || 计数器) 扩展
+pub struct Entropy {
+    seed: Vec<u8>,
+    num: Vec<u8>,
+    bits: f64,
+    counter: u32,
+}
+
+impl Entropy {
+    pub fn new(seed: &[u8]) -> Self {
+        Entropy {
+            seed: seed.to_vec(),
+            num: seed.to_vec(),
+            bits: seed.len() as f64 * 8.0,
+            counter: 0,
+        }
+    }
+
+    /// 以系统随机数为种子
+    pub fn random() -> Result<Self, Error> {
+        let mut seed = [0u8; 32];
+        getrandom::getrandom(&mut seed).map_err(|e| anyhow!("failed to get random bytes: {}", e))?;
+        Ok(Entropy::new(&seed))
+    }
+
+    /// 取 n 个字节，供编码输出使用
+    pub fn bytes(&mut self, n: usize) -> Vec<u8> {
+        (0..n).map(|_| self.below(256) as u8).collect()
+    }
+
+    /// 取 [0, d) 中的一个数，保留 8 位余量以减小取余偏差
+    pub fn below(&mut self, d: usize) -> usize {
+        let need = (d as f64).log2();
+        while self.bits < need + 8.0 {
+            self.counter += 1;
+            let mut hasher = Sha256::new();
+            hasher.update(&self.seed);
+            hasher.update(self.counter.to_be_bytes());
+            self.num.extend_from_slice(&hasher.finalize());
+            self.bits += 256.0;
+        }
+        self.bits -= need;
+
+        let mut rem = 0;
+        for b in self.num.iter_mut() {
+            let cur = (rem << 8) | *b as usize;
+            *b = (cur / d) as u8;
+            rem = cur % d;
+        }
+        rem
+    }
+}
@@ -0,0 +1,404 @@
+//! 密码强度估计
+//!
+//! 在密码中查找字典词、变形词、键盘路径、重复和序列等模式，
+//! 取猜测次数最少的拆分作为估计，给出 0–4 分、猜测次数和改进建议。
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::policy::CharClass;
+
+/// 常见密码和单词，按常见程度排列，序号越小越先被猜到
+const DICTIONARY: &[&str] = &[
+    "123456", "password", "123456789", "12345678", "12345", "qwerty", "1234567", "111111",
+    "123123", "abc123", "1234567890", "000000", "iloveyou", "1234", "qwerty123", "admin",
+    "welcome", "monkey", "dragon", "letmein", "football", "baseball", "master", "login",
+    "sunshine", "princess", "starwars", "passw0rd", "shadow", "superman", "trustno1", "hello",
+    "freedom", "whatever", "michael", "jennifer", "charlie", "jordan", "hunter", "secret",
+    "summer", "winter", "spring", "autumn", "flower", "soccer", "killer", "batman",
+    "computer", "internet", "google", "pepper", "ginger", "cookie", "cheese", "orange",
+    "banana", "apple", "purple", "silver", "golden", "diamond", "matrix", "hockey",
+    "tigger", "thomas", "robert", "daniel", "andrew", "joshua", "ashley", "jessica",
+    "amanda", "nicole", "love", "lovely", "angel", "angels", "buster", "pass",
+    "test", "guest", "root", "user", "default", "changeme", "access", "private",
+    "qazwsx", "zaq12wsx", "asdfgh", "zxcvbn", "mustang", "harley", "ranger", "yankees",
+    "dallas", "london", "paris", "china", "money", "family", "friend", "friends",
+    "house", "mother", "father", "sister", "brother", "happy", "smile", "music",
+    "magic", "power", "ninja", "pokemon", "naruto", "rainbow", "blue", "green",
+    "black", "white", "red", "yellow", "dog", "cat", "fish", "bird",
+    "tiger", "lion", "bear", "wolf", "eagle", "horse", "dragon1", "monkey1",
+    "hello123", "admin123", "root123", "welcome1", "password1", "password123", "test123", "abc",
+    "qwe", "asd", "zxc", "god", "sex", "star", "moon", "sun",
+    "king", "queen", "prince", "boss", "life", "time", "game", "player",
+    "correct", "battery", "staple", "secure", "crypto", "bitcoin", "wallet",
+];
+
+/// 键盘各行，含按住 Shift 时的数字行
+const KEYBOARD_ROWS: &[&str] = &[
+    "`1234567890-=", "~!@#$%^&*()_+", "qwertyuiop[]\\", "asdfghjkl;'", "zxcvbnm,./",
+];
+
+/// 键盘上的按键数
+const KEYBOARD_KEYS: f64 = 47.0;
+
+/// 常见的字母替换
+const LEET: &[(char, char)] = &[
+    ('4', 'a'), ('@', 'a'), ('8', 'b'), ('(', 'c'), ('3', 'e'), ('6', 'g'), ('1', 'i'),
+    ('!', 'i'), ('|', 'i'), ('0', 'o'), ('$', 's'), ('5', 's'), ('7', 't'), ('+', 't'), ('2', 'z'),
+];
+
+/// 匹配到的模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pattern {
+    Dictionary,
+    Leet,
+    Keyboard,
+    Repeat,
+    Sequence,
+    BruteForce,
+}
+
+impl Pattern {
+    fn feedback(&self) -> Option<&'static str> {
+        match self {
+            Pattern::Dictionary => Some("avoid common words and passwords"),
+            Pattern::Leet => Some("substitutions like @ for a are easy to guess"),
+            Pattern::Keyboard => Some("avoid keyboard patterns like qwerty"),
+            Pattern::Repeat => Some("avoid repeated characters and words"),
+            Pattern::Sequence => Some("avoid sequences like abc or 123"),
+            Pattern::BruteForce => None,
+        }
+    }
+}
+
+/// 强度估计结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Strength {
+    /// 0 最弱，4 最强
+    pub score: u8,
+    /// 估计的猜测次数
+    pub guesses: f64,
+    /// 猜测次数的以 2 为底的对数
+    pub entropy: f64,
+    /// 估计所用拆分中出现的模式，按出现顺序
+    pub patterns: Vec<Pattern>,
+    pub feedback: Vec<String>,
+}
+
+impl fmt::Display for Strength {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "score: {}/4", self.score)?;
+        writeln!(f, "guesses: {:.3e}", self.guesses)?;
+        write!(f, "entropy: {:.1} bits", self.entropy)?;
+        for feedback in &self.feedback {
+            write!(f, "\n- {}", feedback)?;
+        }
+        Ok(())
+    }
+}
+
+/// 密码的一段，guesses 以 2 为底取对数
+struct Match {
+    start: usize,
+    end: usize,
+    bits: f64,
+    pattern: Pattern,
+}
+
+/// 估计密码强度
+///
+/// # Example
+/// ```
+/// use encryptor::strength::{estimate, Pattern};
+///
+/// let weak = estimate("p@ssw0rd");
+/// assert_eq!(0, weak.score);
+/// assert!(weak.patterns.contains(&Pattern::Leet));
+/// assert_eq!(4, estimate("x7#Kq!v9Lm$2Tz@e").score);
+/// ```
+pub fn estimate(passwd: &str) -> Strength {
+    let chars: Vec<char> = passwd.chars().collect();
+    if chars.is_empty() {
+        return Strength {
+            score: 0,
+            guesses: 1.0,
+            entropy: 0.0,
+            patterns: Vec::new(),
+            feedback: vec!["password is empty".to_string()],
+        };
+    }
+
+    let (entropy, patterns) = min_bits(&chars);
+    let guesses = 2f64.powf(entropy).min(f64::MAX);
+    let score = match guesses {
+        g if g < 1e3 => 0,
+        g if g < 1e6 => 1,
+        g if g < 1e8 => 2,
+        g if g < 1e10 => 3,
+        _ => 4,
+    };
+
+    let mut feedback: Vec<String> = Vec::new();
+    if score < 4 {
+        for pattern in &patterns {
+            if let Some(text) = pattern.feedback() {
+                if !feedback.iter().any(|f| f == text) {
+                    feedback.push(text.to_string());
+                }
+            }
+        }
+        if chars.len() < 12 {
+            feedback.push("use at least 12 characters".to_string());
+        }
+        let classes = CharClass::ALL.iter()
+            .filter(|&&class| chars.iter().any(|&c| CharClass::of(c) == Some(class)))
+            .count();
+        if classes < 3 {
+            feedback.push("mix lowercase, uppercase, digits and symbols".to_string());
+        }
+    }
+
+    Strength { score, guesses, entropy, patterns, feedback }
+}
+
+/// 字符类别决定的每个字符的猜测空间
+fn pool_size(chars: &[char]) -> f64 {
+    let mut pool = 0.0;
+    for class in CharClass::ALL {
+        if chars.iter().any(|&c| CharClass::of(c) == Some(class)) {
+            pool += class.chars().len() as f64;
+        }
+    }
+    if chars.iter().any(|&c| CharClass::of(c).is_none()) {
+        pool += 100.0;
+    }
+    pool
+}
+
+/// 动态规划求猜测次数最少的拆分，未被模式覆盖的字符按字符空间穷举
+fn min_bits(chars: &[char]) -> (f64, Vec<Pattern>) {
+    best_split(chars, &find_matches(chars))
+}
+
+fn best_split(chars: &[char], matches: &[Match]) -> (f64, Vec<Pattern>) {
+    let n = chars.len();
+    let char_bits = pool_size(chars).log2();
+
+    // best[i] 为前 i 个字符的最少位数，from[i] 为最后一段所用的匹配
+    let mut best = vec![0.0; n + 1];
+    let mut from: Vec<Option<usize>> = vec![None; n + 1];
+    let mut ending: Vec<Vec<usize>> = vec![Vec::new(); n + 1];
+    for (k, m) in matches.iter().enumerate() {
+        ending[m.end].push(k);
+    }
+    for i in 1..=n {
+        best[i] = best[i - 1] + char_bits;
+        for &k in &ending[i] {
+            let m = &matches[k];
+            if best[m.start] + m.bits < best[i] {
+                best[i] = best[m.start] + m.bits;
+                from[i] = Some(k);
+            }
+        }
+    }
+
+    let mut patterns = Vec::new();
+    let mut i = n;
+    while i > 0 {
+        match from[i] {
+            Some(k) => {
+                patterns.push(matches[k].pattern);
+                i = matches[k].start;
+            }
+            None => {
+                if patterns.last() != Some(&Pattern::BruteForce) {
+                    patterns.push(Pattern::BruteForce);
+                }
+                i -= 1;
+            }
+        }
+    }
+    patterns.reverse();
+
+    (best[n], patterns)
+}
+
+fn find_matches(chars: &[char]) -> Vec<Match> {
+    let mut matches = base_matches(chars);
+    repeat_matches(chars, &mut matches);
+    matches
+}
+
+/// 除重复以外的模式，用于给重复的整段估值
+fn base_matches(chars: &[char]) -> Vec<Match> {
+    let mut matches = Vec::new();
+    dictionary_matches(chars, &mut matches);
+    keyboard_matches(chars, &mut matches);
+    sequence_matches(chars, &mut matches);
+    matches
+}
+
+/// 大小写变化带来的额外猜测次数
+fn case_bits(word: &[char]) -> f64 {
+    let upper = word.iter().filter(|c| c.is_uppercase()).count();
+    let lower = word.iter().filter(|c| c.is_lowercase()).count();
+    if upper == 0 {
+        0.0
+    } else if lower == 0 || (upper == 1 && word[0].is_uppercase()) {
+        1.0
+    } else {
+        upper.min(lower) as f64
+    }
+}
+
+fn unleet(word: &[char], one: char) -> (String, usize) {
+    let mut subs = 0;
+    let plain = word.iter()
+        .map(|&c| {
+            let c = c.to_ascii_lowercase();
+            let plain = if c == '1' {
+                Some(one)
+            } else {
+                LEET.iter().find(|&&(leet, _)| leet == c).map(|&(_, plain)| plain)
+            };
+            match plain {
+                Some(plain) => {
+                    subs += 1;
+                    plain
+                }
+                None => c,
+            }
+        })
+        .collect();
+    (plain, subs)
+}
+
+fn dictionary_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let rank = |word: &str| DICTIONARY.iter().position(|&w| w == word);
+    // 比最长的词还长的片段不必查找
+    let longest = DICTIONARY.iter().map(|w| w.len()).max().unwrap_or(0);
+    for start in 0..chars.len() {
+        for end in start + 3..=chars.len().min(start + longest) {
+            let word = &chars[start..end];
+            let lower: String = word.iter().map(|c| c.to_ascii_lowercase()).collect();
+            if let Some(r) = rank(&lower) {
+                let bits = ((r + 1) as f64).log2() + case_bits(word);
+                matches.push(Match { start, end, bits, pattern: Pattern::Dictionary });
+                continue;
+            }
+
+            // 1 既可能代替 i 也可能代替 l
+            for one in ['i', 'l'] {
+                let (plain, subs) = unleet(word, one);
+                if subs == 0 {
+                    continue;
+                }
+                if let Some(r) = rank(&plain) {
+                    let bits = ((r + 1) as f64).log2() + case_bits(word) + subs as f64;
+                    matches.push(Match { start, end, bits, pattern: Pattern::Leet });
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// 同一行相邻的按键，正反两个方向
+fn keyboard_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let lower: Vec<char> = chars.iter().map(|c| c.to_ascii_lowercase()).collect();
+    let step = |a: char, b: char| -> Option<i32> {
+        KEYBOARD_ROWS.iter().find_map(|row| {
+            let i = row.find(a)? as i32;
+            let j = row.find(b)? as i32;
+            if (i - j).abs() == 1 { Some(j - i) } else { None }
+        })
+    };
+
+    let mut start = 0;
+    while start + 1 < lower.len() {
+        let dir = match step(lower[start], lower[start + 1]) {
+            Some(dir) => dir,
+            None => {
+                start += 1;
+                continue;
+            }
+        };
+        let mut end = start + 2;
+        while end < lower.len() && step(lower[end - 1], lower[end]) == Some(dir) {
+            end += 1;
+        }
+        if end - start >= 3 {
+            let len = (end - start) as f64;
+            let bits = (KEYBOARD_KEYS * 2.0 * len).log2() + case_bits(&chars[start..end]);
+            matches.push(Match { start, end, bits, pattern: Pattern::Keyboard });
+        }
+        start = end - 1;
+    }
+}
+
+/// 单个字符的连续重复，以及整段的重复，如 abcabc
+///
+/// 整段只按非重复模式估值且每种整段只算一次，重复的重复由更短的整段覆盖，
+/// 避免递归导致长密码的估计时间呈指数增长
+fn repeat_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let mut block_cache: HashMap<&[char], f64> = HashMap::new();
+    for start in 0..chars.len() {
+        for size in 1..=(chars.len() - start) / 2 {
+            let block = &chars[start..start + size];
+            let mut count = 1;
+            while start + (count + 1) * size <= chars.len()
+                && &chars[start + count * size..start + (count + 1) * size] == block
+            {
+                count += 1;
+            }
+            if count < 2 || (size == 1 && count < 3) {
+                continue;
+            }
+
+            let block_bits = if size == 1 {
+                pool_size(block).log2()
+            } else {
+                *block_cache.entry(block).or_insert_with(|| best_split(block, &base_matches(block)).0)
+            };
+            for k in 2..=count {
+                let bits = block_bits + (k as f64).log2();
+                matches.push(Match { start, end: start + k * size, bits, pattern: Pattern::Repeat });
+            }
+        }
+    }
+}
+
+/// 同一类别中编码依次加一或减一的字符，如 abcd、9876
+fn sequence_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let mut start = 0;
+    while start + 1 < chars.len() {
+        let class = CharClass::of(chars[start]);
+        let dir = chars[start + 1] as i32 - chars[start] as i32;
+        if class == Some(CharClass::Symbol) || class.is_none() || dir.abs() != 1
+            || CharClass::of(chars[start + 1]) != class
+        {
+            start += 1;
+            continue;
+        }
+
+        let mut end = start + 2;
+        while end < chars.len()
+            && CharClass::of(chars[end]) == class
+            && chars[end] as i32 - chars[end - 1] as i32 == dir
+        {
+            end += 1;
+        }
+        if end - start >= 3 {
+            // 从开头或结尾开始的序列最先被猜到
+            let first = chars[start];
+            let base: f64 = if "aAzZ019".contains(first) {
+                4.0
+            } else {
+                class.map_or(0, |class| class.chars().len()) as f64
+            };
+            let dir_bits = if dir < 0 { 1.0 } else { 0.0 };
+            let bits = (base * (end - start) as f64).log2() + dir_bits;
+            matches.push(Match { start, end, bits, pattern: Pattern::Sequence });
+        }
+        start = end - 1;
+    }
+}
@@ -0,0 +1,432 @@
+//! 导入导出
+//!
+//! 支持 KeePass CSV、Bitwarden 未加密 JSON 和按列映射的通用 CSV。
+//! 导入先生成预览，与已有条目去重并记录跳过的行，确认后再写入密码库。
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Error, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::otp::Otp;
+use crate::vault::{Entry, Secret, Vault};
+
+/// KeePass 各版本导出的列名
+const KEEPASS_SITE: &[&str] = &["Title", "Account"];
+const KEEPASS_USERNAME: &[&str] = &["Username", "User Name", "Login Name"];
+const KEEPASS_PASSWORD: &[&str] = &["Password"];
+const KEEPASS_URL: &[&str] = &["URL", "Web Site"];
+const KEEPASS_NOTES: &[&str] = &["Notes", "Comments"];
+const KEEPASS_OTP: &[&str] = &["TOTP"];
+
+/// Bitwarden 中登录条目的类型
+const BITWARDEN_LOGIN: u32 = 1;
+
+/// 文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Keepass,
+    Bitwarden,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "keepass" => Ok(Format::Keepass),
+            "bitwarden" => Ok(Format::Bitwarden),
+            "csv" => Ok(Format::Csv),
+            _ => bail!("unknown format `{}`, expected keepass, bitwarden or csv", s),
+        }
+    }
+}
+
+/// 通用 CSV 中各字段对应的列名，如 site=Name,username=Login,password=Secret
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMap {
+    pub site: String,
+    pub username: Option<String>,
+    pub password: String,
+    pub url: Option<String>,
+    pub notes: Option<String>,
+    pub otp: Option<String>,
+}
+
+impl Default for ColumnMap {
+    fn default() -> Self {
+        ColumnMap {
+            site: "site".to_string(),
+            username: Some("username".to_string()),
+            password: "password".to_string(),
+            url: None,
+            notes: Some("notes".to_string()),
+            otp: Some("otp".to_string()),
+        }
+    }
+}
+
+impl FromStr for ColumnMap {
+    type Err = Error;
+
+    /// 未列出的可选字段不导入
+    fn from_str(s: &str) -> Result<Self> {
+        let mut map = ColumnMap {
+            site: String::new(),
+            username: None,
+            password: String::new(),
+            url: None,
+            notes: None,
+            otp: None,
+        };
+        for pair in s.split(',') {
+            let (field, column) = pair.split_once('=')
+                .ok_or_else(|| anyhow!("column mapping `{}` must look like field=column", pair))?;
+            let column = column.trim().to_string();
+            match field.trim() {
+                "site" => map.site = column,
+                "username" => map.username = Some(column),
+                "password" => map.password = column,
+                "url" => map.url = Some(column),
+                "notes" => map.notes = Some(column),
+                "otp" => map.otp = Some(column),
+                field => bail!("unknown field `{}` in column mapping", field),
+            }
+        }
+        if map.site.is_empty() || map.password.is_empty() {
+            bail!("column mapping needs site and password");
+        }
+
+        Ok(map)
+    }
+}
+
+impl ColumnMap {
+    /// 按 KeePass 的列名在表头中查找各字段
+    fn keepass(headers: &[String]) -> Result<Self, Error> {
+        let find = |names: &[&str]| {
+            names.iter().find(|name| headers.iter().any(|h| h == *name)).map(|name| name.to_string())
+        };
+        Ok(ColumnMap {
+            site: find(KEEPASS_SITE).ok_or_else(|| anyhow!("keepass csv has no Title column"))?,
+            username: find(KEEPASS_USERNAME),
+            password: find(KEEPASS_PASSWORD).ok_or_else(|| anyhow!("keepass csv has no Password column"))?,
+            url: find(KEEPASS_URL),
+            notes: find(KEEPASS_NOTES),
+            otp: find(KEEPASS_OTP),
+        })
+    }
+
+    /// 映射中出现的 (字段, 列名)，按字段的固定次序
+    fn columns(&self) -> Vec<(&'static str, &str)> {
+        let mut columns = vec![("site", self.site.as_str())];
+        if let Some(column) = &self.username {
+            columns.push(("username", column));
+        }
+        columns.push(("password", &self.password));
+        for (field, column) in [("url", &self.url), ("notes", &self.notes), ("otp", &self.otp)] {
+            if let Some(column) = column {
+                columns.push((field, column));
+            }
+        }
+        columns
+    }
+}
+
+/// 一条待导入或导出的记录，密码为明文
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Record {
+    pub site: String,
+    pub username: String,
+    pub password: String,
+    pub url: String,
+    pub notes: String,
+    pub otp: String,
+}
+
+impl Record {
+    fn site_name(&self) -> String {
+        if !self.site.is_empty() {
+            return self.site.clone();
+        }
+        let url = self.url.split_once("://").map_or(self.url.as_str(), |(_, rest)| rest);
+        url.split(['/', '?', '#']).next().unwrap_or("").to_string()
+    }
+
+    /// 没有标题时用网址的主机名作站点
+    fn into_entry(self) -> Result<Entry, Error> {
+        let site = self.site_name();
+        if site.is_empty() {
+            bail!("no site or url");
+        }
+        if self.password.is_empty() {
+            bail!("no password");
+        }
+
+        let mut entry = Entry::new(&site, &self.username, Secret::Stored { password: self.password }, &self.notes);
+        entry.url = self.url;
+        if !self.otp.is_empty() {
+            let otp: Otp = self.otp.parse().map_err(|e| anyhow!("invalid totp: {}", e))?;
+            entry.otp = Some(otp.to_uri());
+        }
+
+        Ok(entry)
+    }
+}
+
+/// 导入时跳过的行，CSV 的表头为第 1 行，Bitwarden 为第几个条目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Skipped {
+    pub row: usize,
+    pub site: String,
+    pub reason: String,
+}
+
+impl fmt::Display for Skipped {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "row {}: {} ({})", self.row, self.site, self.reason)
+    }
+}
+
+/// 导入预览：将要加入的条目和跳过的行
+#[derive(Debug, Clone, Default)]
+pub struct ImportPlan {
+    pub entries: Vec<Entry>,
+    pub skipped: Vec<Skipped>,
+}
+
+impl ImportPlan {
+    /// 解析文件并与密码库中已有的条目去重，不修改密码库
+    pub fn new(vault: &Vault, data: &str, format: Format, map: Option<&ColumnMap>) -> Result<Self, Error> {
+        let rows = match format {
+            Format::Keepass => read_csv(data, None)?,
+            Format::Csv => read_csv(data, Some(map.cloned().unwrap_or_default()))?,
+            Format::Bitwarden => read_bitwarden(data)?,
+        };
+
+        let mut plan = ImportPlan::default();
+        for row in rows {
+            let (row, record) = match row {
+                Ok(row) => row,
+                Err(skipped) => {
+                    plan.skipped.push(skipped);
+                    continue;
+                }
+            };
+            let site = record.site_name();
+            let entry = match record.into_entry() {
+                Ok(entry) => entry,
+                Err(e) => {
+                    plan.skipped.push(Skipped { row, site, reason: e.to_string() });
+                    continue;
+                }
+            };
+
+            let same = |e: &Entry| e.site == entry.site && e.username == entry.username;
+            let reason = if vault.entries().iter().any(same) {
+                "already in vault"
+            } else if plan.entries.iter().any(same) {
+                "duplicate row"
+            } else {
+                plan.entries.push(entry);
+                continue;
+            };
+            plan.skipped.push(Skipped { row, site, reason: reason.to_string() });
+        }
+
+        Ok(plan)
+    }
+
+    /// 把预览中的条目加入密码库，调用方需保存
+    pub fn apply(self, vault: &mut Vault) -> Result<usize, Error> {
+        let count = self.entries.len();
+        for entry in self.entries {
+            vault.add(entry)?;
+        }
+        Ok(count)
+    }
+}
+
+type Row = Result<(usize, Record), Skipped>;
+
+fn read_csv(data: &str, map: Option<ColumnMap>) -> Result<Vec<Row>, Error> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(data.as_bytes());
+    let headers: Vec<String> = reader.headers()?.iter().map(|h| h.trim().to_string()).collect();
+    let map = match map {
+        Some(map) => map,
+        None => ColumnMap::keepass(&headers)?,
+    };
+    let mut index = HashMap::new();
+    for (field, column) in map.columns() {
+        let i = headers.iter()
+            .position(|h| h == column)
+            .ok_or_else(|| anyhow!("csv has no column `{}` for {}", column, field))?;
+        index.insert(field, i);
+    }
+
+    let mut rows = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let row = i + 2;
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                rows.push(Err(Skipped { row, site: String::new(), reason: e.to_string() }));
+                continue;
+            }
+        };
+        let get = |field| index.get(field).and_then(|&i| record.get(i)).unwrap_or("").to_string();
+        rows.push(Ok((row, Record {
+            site: get("site").trim().to_string(),
+            username: get("username"),
+            password: get("password"),
+            url: get("url").trim().to_string(),
+            notes: get("notes"),
+            otp: get("otp").trim().to_string(),
+        })));
+    }
+
+    Ok(rows)
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BitwardenExport {
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<serde_json::Value>,
+    #[serde(default)]
+    items: Vec<BitwardenItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BitwardenItem {
+    #[serde(rename = "type")]
+    kind: u32,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    notes: Option<String>,
+    #[serde(default)]
+    favorite: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    login: Option<BitwardenLogin>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BitwardenLogin {
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+    #[serde(default)]
+    totp: Option<String>,
+    #[serde(default)]
+    uris: Vec<BitwardenUri>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BitwardenUri {
+    uri: Option<String>,
+}
+
+fn read_bitwarden(data: &str) -> Result<Vec<Row>, Error> {
+    let export: BitwardenExport = serde_json::from_str(data)
+        .map_err(|e| anyhow!("malformed bitwarden export: {}", e))?;
+    if export.encrypted {
+        bail!("encrypted bitwarden exports are not supported, export as unencrypted json");
+    }
+
+    let rows = export.items.into_iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let row = i + 1;
+            let login = match item.login {
+                Some(login) if item.kind == BITWARDEN_LOGIN => login,
+                _ => return Err(Skipped { row, site: item.name, reason: "not a login item".to_string() }),
+            };
+            let url = login.uris.into_iter().find_map(|u| u.uri).unwrap_or_default();
+            Ok((row, Record {
+                site: item.name.trim().to_string(),
+                username: login.username.unwrap_or_default(),
+                password: login.password.unwrap_or_default(),
+                url,
+                notes: item.notes.unwrap_or_default(),
+                otp: login.totp.unwrap_or_default(),
+            }))
+        })
+        .collect();
+
+    Ok(rows)
+}
+
+/// 导出密码库的全部条目，派生的密码以明文导出
+pub fn export(vault: &Vault, format: Format, map: Option<&ColumnMap>) -> Result<String, Error> {
+    let mut records = Vec::new();
+    for entry in vault.entries() {
+        records.push(Record {
+            site: entry.site.clone(),
+            username: entry.username.clone(),
+            password: vault.password(entry)?,
+            url: entry.url.clone(),
+            notes: entry.notes.clone(),
+            otp: entry.otp.clone().unwrap_or_default(),
+        });
+    }
+
+    match format {
+        Format::Keepass => {
+            let map = ColumnMap {
+                site: "Title".to_string(),
+                username: Some("Username".to_string()),
+                password: "Password".to_string(),
+                url: Some("URL".to_string()),
+                notes: Some("Notes".to_string()),
+                otp: Some("TOTP".to_string()),
+            };
+            write_csv(&records, &map)
+        }
+        Format::Csv => write_csv(&records, &map.cloned().unwrap_or_default()),
+        Format::Bitwarden => write_bitwarden(records),
+    }
+}
+
+fn write_csv(records: &[Record], map: &ColumnMap) -> Result<String, Error> {
+    let columns = map.columns();
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(columns.iter().map(|(_, column)| column))?;
+    for record in records {
+        writer.write_record(columns.iter().map(|(field, _)| match *field {
+            "site" => &record.site,
+            "username" => &record.username,
+            "password" => &record.password,
+            "url" => &record.url,
+            "notes" => &record.notes,
+            _ => &record.otp,
+        }))?;
+    }
+
+    let data = writer.into_inner().map_err(|e| anyhow!("failed to write csv: {}", e))?;
+    Ok(String::from_utf8(data)?)
+}
+
+fn write_bitwarden(records: Vec<Record>) -> Result<String, Error> {
+    let some = |s: String| if s.is_empty() { None } else { Some(s) };
+    let items = records.into_iter()
+        .map(|record| BitwardenItem {
+            kind: BITWARDEN_LOGIN,
+            name: record.site,
+            notes: some(record.notes),
+            favorite: false,
+            login: Some(BitwardenLogin {
+                username: some(record.username),
+                password: some(record.password),
+                totp: some(record.otp),
+                uris: some(record.url).map(|uri| BitwardenUri { uri: Some(uri) }).into_iter().collect(),
+            }),
+        })
+        .collect();
+    let export = BitwardenExport { encrypted: false, folders: Vec::new(), items };
+
+    Ok(serde_json::to_string_pretty(&export)?)
+}
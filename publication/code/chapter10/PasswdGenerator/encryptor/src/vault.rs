@@ -0,0 +1,310 @@
+//! 加密密码库
+//!
+//! 密码库文件为 JSON 头部加密文，条目用主密码经 KDF 得到的密钥以
+//! ChaCha20-Poly1305 加密。写入时先写临时文件再改名，并保留上一版本的备份。
+//! 这些文件在 unix 上只有所有者可读写。
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Context, Error, Result};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use encoder::encoding::Encoding;
+use encoder::hex::Hex;
+use serde::{Deserialize, Serialize};
+
+use crate::kdf::KdfParams;
+use crate::password::derive_password;
+use crate::policy::{Entropy, PasswordPolicy};
+
+/// 密码库文件格式版本
+pub const VAULT_VERSION: u32 = 1;
+
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// 条目的密码：由主密码派生，或直接保存
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Secret {
+    Generated {
+        length: usize,
+        counter: u32,
+        kdf: KdfParams,
+        policy: PasswordPolicy,
+    },
+    Stored {
+        password: String,
+    },
+}
+
+/// 密码库条目，以站点和用户名区分
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub site: String,
+    pub username: String,
+    pub secret: Secret,
+    pub notes: String,
+    /// 站点网址，可为空
+    #[serde(default)]
+    pub url: String,
+    /// 两步验证密钥，保存为 otpauth:// URI
+    #[serde(default)]
+    pub otp: Option<String>,
+    pub created: u64,
+    pub updated: u64,
+}
+
+impl Entry {
+    pub fn new(site: &str, username: &str, secret: Secret, notes: &str) -> Self {
+        let now = now();
+        Entry {
+            site: site.to_string(),
+            username: username.to_string(),
+            secret,
+            notes: notes.to_string(),
+            url: String::new(),
+            otp: None,
+            created: now,
+            updated: now,
+        }
+    }
+}
+
+/// 文件中的明文部分，整体作为附加数据参与认证
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Header {
+    version: u32,
+    kdf: KdfParams,
+    salt: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VaultFile {
+    header: Header,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// 打开后的密码库，条目在内存中为明文，修改后需调用 save
+pub struct Vault {
+    path: PathBuf,
+    master: String,
+    header: Header,
+    key: [u8; KEY_LEN],
+    entries: Vec<Entry>,
+}
+
+impl Vault {
+    /// 新建空密码库，文件已存在时报错
+    pub fn create<P: AsRef<Path>>(path: P, master: &str, kdf: KdfParams) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        if path.exists() {
+            bail!("vault {} already exists", path.display());
+        }
+        if master.is_empty() {
+            bail!("master password must not be empty");
+        }
+
+        let header = Header { version: VAULT_VERSION, kdf, salt: Hex::LOWER.encode(&random_bytes(SALT_LEN)?) };
+        let key = derive_key(master, &header)?;
+        let vault = Vault { path, master: master.to_string(), header, key, entries: Vec::new() };
+        vault.save()?;
+
+        Ok(vault)
+    }
+
+    /// 打开密码库，主密码错误或文件被篡改时报错
+    pub fn open<P: AsRef<Path>>(path: P, master: &str) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let data = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read vault {}", path.display()))?;
+        let file: VaultFile = serde_json::from_str(&data).context("malformed vault file")?;
+        if file.header.version != VAULT_VERSION {
+            bail!("unsupported vault version {}", file.header.version);
+        }
+
+        let key = derive_key(master, &file.header)?;
+        let nonce = Hex::LOWER.decode(&file.nonce).context("malformed vault file")?;
+        if nonce.len() != NONCE_LEN {
+            bail!("malformed vault file");
+        }
+        let ciphertext = Hex::LOWER.decode(&file.ciphertext).context("malformed vault file")?;
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+        let aad = serde_json::to_vec(&file.header)?;
+        let plain = cipher
+            .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: &aad })
+            .map_err(|_| anyhow!("wrong master password or corrupted vault"))?;
+        let entries = serde_json::from_slice(&plain).context("malformed vault entries")?;
+
+        Ok(Vault { path, master: master.to_string(), header: file.header, key, entries })
+    }
+
+    /// 每次保存使用新的随机 nonce；先写临时文件再改名，旧文件保留为 .bak
+    pub fn save(&self) -> Result<(), Error> {
+        let nonce = random_bytes(NONCE_LEN)?;
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&self.key));
+        let aad = serde_json::to_vec(&self.header)?;
+        let plain = serde_json::to_vec(&self.entries)?;
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: &plain, aad: &aad })
+            .map_err(|_| anyhow!("failed to encrypt vault"))?;
+        let file = VaultFile {
+            header: self.header.clone(),
+            nonce: Hex::LOWER.encode(&nonce),
+            ciphertext: Hex::LOWER.encode(&ciphertext),
+        };
+
+        let tmp = self.sibling("tmp");
+        let mut out = create_private(&tmp, false)
+            .with_context(|| format!("failed to write {}", tmp.display()))?;
+        out.write_all(serde_json::to_string_pretty(&file)?.as_bytes())?;
+        out.sync_all()?;
+        if self.path.exists() {
+            let backup = self.backup_path();
+            let mut bak = create_private(&backup, false)
+                .with_context(|| format!("failed to write {}", backup.display()))?;
+            io::copy(&mut File::open(&self.path)?, &mut bak)?;
+            bak.sync_all()?;
+        }
+        fs::rename(&tmp, &self.path)?;
+
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn backup_path(&self) -> PathBuf {
+        self.sibling("bak")
+    }
+
+    fn sibling(&self, ext: &str) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(".");
+        name.push(ext);
+        self.path.with_file_name(name)
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    fn position(&self, site: &str, username: Option<&str>) -> Result<usize, Error> {
+        let found: Vec<usize> = self.entries.iter()
+            .enumerate()
+            .filter(|(_, e)| e.site == site && username.is_none_or(|u| e.username == u))
+            .map(|(i, _)| i)
+            .collect();
+        match found.as_slice() {
+            [i] => Ok(*i),
+            [] => bail!("no entry for {}", site),
+            _ => bail!("several entries for {}, specify the username", site),
+        }
+    }
+
+    /// 只给出站点时，该站点必须只有一个条目
+    pub fn get(&self, site: &str, username: Option<&str>) -> Result<&Entry, Error> {
+        Ok(&self.entries[self.position(site, username)?])
+    }
+
+    pub fn add(&mut self, entry: Entry) -> Result<(), Error> {
+        if self.position(&entry.site, Some(&entry.username)).is_ok() {
+            bail!("entry for {} ({}) already exists", entry.site, entry.username);
+        }
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// 修改条目并更新时间戳，改名后不能与其他条目重复
+    pub fn edit<F>(&mut self, site: &str, username: Option<&str>, f: F) -> Result<&Entry, Error>
+    where
+        F: FnOnce(&mut Entry),
+    {
+        let i = self.position(site, username)?;
+        let mut entry = self.entries[i].clone();
+        f(&mut entry);
+        if self.entries.iter().enumerate().any(|(j, e)| {
+            j != i && e.site == entry.site && e.username == entry.username
+        }) {
+            bail!("entry for {} ({}) already exists", entry.site, entry.username);
+        }
+
+        entry.updated = now();
+        self.entries[i] = entry;
+        Ok(&self.entries[i])
+    }
+
+    pub fn remove(&mut self, site: &str, username: Option<&str>) -> Result<Entry, Error> {
+        let i = self.position(site, username)?;
+        Ok(self.entries.remove(i))
+    }
+
+    /// 换一个新密码：派生的密码增加计数器，保存的密码按策略随机生成
+    pub fn rotate(&mut self, site: &str, username: Option<&str>, policy: &PasswordPolicy, length: usize)
+        -> Result<&Entry, Error>
+    {
+        let i = self.position(site, username)?;
+        let secret = match &self.entries[i].secret {
+            Secret::Generated { length, counter, kdf, policy } => Secret::Generated {
+                length: *length,
+                counter: counter.checked_add(1).ok_or_else(|| anyhow!("counter overflow"))?,
+                kdf: *kdf,
+                policy: policy.clone(),
+            },
+            Secret::Stored { .. } => {
+                Secret::Stored { password: policy.generate(&mut Entropy::random()?, length)? }
+            }
+        };
+        self.edit(site, username, |e| e.secret = secret)
+    }
+
+    /// 条目的密码明文
+    pub fn password(&self, entry: &Entry) -> Result<String, Error> {
+        match &entry.secret {
+            Secret::Generated { length, counter, kdf, policy } =>
+                derive_password(&self.master, &entry.site, &entry.username, *counter, *length, kdf, policy),
+            Secret::Stored { password } => Ok(password.clone()),
+        }
+    }
+}
+
+/// 以 0600 权限打开文件写入，new_only 时文件已存在则报错；已有文件的权限也收紧为 0600
+pub fn create_private(path: &Path, new_only: bool) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if new_only {
+        options.create_new(true);
+    } else {
+        options.create(true).truncate(true);
+    }
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let file = options.open(path)?;
+    #[cfg(unix)]
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    Ok(file)
+}
+
+fn derive_key(master: &str, header: &Header) -> Result<[u8; KEY_LEN], Error> {
+    let mut key = [0u8; KEY_LEN];
+    header.kdf.derive(master.as_bytes(), &Hex::LOWER.decode(&header.salt).context("malformed vault file")?, &mut key)?;
+    Ok(key)
+}
+
+fn random_bytes(len: usize) -> Result<Vec<u8>, Error> {
+    let mut buf = vec![0u8; len];
+    getrandom::getrandom(&mut buf).map_err(|e| anyhow!("failed to get random bytes: {}", e))?;
+    Ok(buf)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
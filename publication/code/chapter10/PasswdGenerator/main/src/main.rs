@@ -1,28 +1,499 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
 use anyhow::{bail, Result};
-use clap::Parser;
-use encryptor::password::generate_password;
+use clap::{Parser, Subcommand};
+use encoder::encoding::{self, Encoding};
+use encryptor::breach::{BloomFilter, Breach, BreachChecker};
+use encryptor::kdf::{Algorithm, KdfParams, LATEST_VERSION};
+use encryptor::passphrase::{random_passphrase, PassphraseOptions};
+use encryptor::password::{derive_encoded, derive_passphrase, derive_password, generate_encoded, generate_password};
+use encryptor::policy::PasswordPolicy;
+use encryptor::strength;
+use encryptor::transfer::{ColumnMap, Format};
+
+mod vault;
+
+/// 未设置时交互式读取主密码
+const MASTER_ENV: &str = "PASSWD_MASTER";
 
 /// A simple password generator for any account
 #[derive(Parser, Debug)]
-#[clap(version, about, long_about= None)]
+#[clap(version, about, long_about= None, subcommand_negates_reqs = true)]
 struct Args {
     /// Seed to generate a password
-    #[clap(short, long)]
-    seed: String,
+    #[clap(short, long, required_unless_present_any = &["site", "words"], conflicts_with_all = &["site", "words"])]
+    seed: Option<String>,
 
     /// Length of the password
     #[clap(short, long, default_value_t = 16)]
     length: usize,
+
+    /// Site to derive a password for from the master password ($PASSWD_MASTER or prompt)
+    #[clap(long)]
+    site: Option<String>,
+
+    /// Login name on the site
+    #[clap(long, default_value = "", requires = "site")]
+    login: String,
+
+    /// Increase to get a new password for the same site and login
+    #[clap(long, default_value_t = 1, requires = "site")]
+    counter: u32,
+
+    /// Output the password in this encoding (hex, base32, base58, base64, ascii85, ...) instead of the policy charset
+    #[clap(short, long, conflicts_with = "words")]
+    encoding: Option<String>,
+
+    #[clap(flatten)]
+    kdf: KdfArgs,
+
+    #[clap(flatten)]
+    policy: PolicyArgs,
+
+    #[clap(flatten)]
+    passphrase: PassphraseArgs,
+
+    /// Vault file, defaults to $PASSWD_VAULT or ~/.passwd_vault
+    #[clap(long, global = true)]
+    vault: Option<PathBuf>,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Args, Debug)]
+struct KdfArgs {
+    /// Key derivation function: argon2id, scrypt or pbkdf2
+    #[clap(long, default_value = "argon2id")]
+    kdf: Algorithm,
+
+    /// Version of the KDF parameters, keep it to reproduce old passwords
+    #[clap(long, default_value_t = LATEST_VERSION)]
+    kdf_version: u32,
+}
+
+impl KdfArgs {
+    fn params(&self) -> Result<KdfParams> {
+        KdfParams::from_version(self.kdf_version, self.kdf)
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct PolicyArgs {
+    /// Shortest length the policy accepts
+    #[clap(long, default_value_t = 6)]
+    min_length: usize,
+
+    /// Longest length the policy accepts
+    #[clap(long, default_value_t = 128)]
+    max_length: usize,
+
+    /// Minimum number of lowercase letters
+    #[clap(long, default_value_t = 1)]
+    min_lower: usize,
+
+    /// Minimum number of uppercase letters
+    #[clap(long, default_value_t = 1)]
+    min_upper: usize,
+
+    /// Minimum number of digits
+    #[clap(long, default_value_t = 1)]
+    min_digit: usize,
+
+    /// Minimum number of symbols
+    #[clap(long, default_value_t = 1)]
+    min_symbol: usize,
+
+    /// Only use these characters
+    #[clap(long)]
+    allowed: Option<String>,
+
+    /// Never use these characters
+    #[clap(long, default_value = "")]
+    forbidden: String,
+
+    /// Leave out characters that are easy to confuse, such as I, l, 1, O and 0
+    #[clap(long)]
+    no_ambiguous: bool,
+
+    /// Maximum times a character may repeat in a row
+    #[clap(long)]
+    max_repeat: Option<usize>,
+}
+
+impl PolicyArgs {
+    fn policy(&self) -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: self.min_length,
+            max_length: self.max_length,
+            min_lower: self.min_lower,
+            min_upper: self.min_upper,
+            min_digit: self.min_digit,
+            min_symbol: self.min_symbol,
+            allowed: self.allowed.clone(),
+            forbidden: self.forbidden.clone(),
+            exclude_ambiguous: self.no_ambiguous,
+            max_repeat: self.max_repeat,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct PassphraseArgs {
+    /// Generate a passphrase of this many BIP39 words (11 bits each) instead of a password, random unless --site is given
+    #[clap(short, long)]
+    words: Option<usize>,
+
+    /// Separator between the words of the passphrase
+    #[clap(long, default_value = "-", requires = "words")]
+    separator: String,
+
+    /// Capitalize each word of the passphrase
+    #[clap(long, requires = "words")]
+    capitalize: bool,
+
+    /// Add a digit to the passphrase
+    #[clap(long, requires = "words")]
+    digit: bool,
+
+    /// Add a symbol to the passphrase
+    #[clap(long, requires = "words")]
+    symbol: bool,
+}
+
+impl PassphraseArgs {
+    fn options(&self) -> Option<PassphraseOptions> {
+        Some(PassphraseOptions {
+            words: self.words?,
+            separator: self.separator.clone(),
+            capitalize: self.capitalize,
+            digit: self.digit,
+            symbol: self.symbol,
+        })
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    #[clap(flatten)]
+    Vault(VaultCommand),
+
+    /// Estimate the strength of a password, or of a generated one with --seed
+    Check {
+        /// Password to check, prompted for when neither it nor --seed is given
+        #[clap(conflicts_with = "seed")]
+        password: Option<String>,
+
+        /// Check the password generated from this seed
+        #[clap(short, long)]
+        seed: Option<String>,
+
+        /// Length of the generated password
+        #[clap(short, long, default_value_t = 16)]
+        length: usize,
+
+        #[clap(flatten)]
+        policy: PolicyArgs,
+
+        /// Also look the password up in a Pwned Passwords hash file or bloom filter
+        #[clap(long)]
+        breaches: Option<PathBuf>,
+    },
+
+    /// Look a password up in a local Pwned Passwords SHA-1 file or a bloom filter built from it
+    Breached {
+        /// Hash file sorted by hash, or a bloom filter
+        file: PathBuf,
+
+        /// Password to look up, prompted for when not given
+        password: Option<String>,
+
+        /// Build a bloom filter from the hash file at this path instead of looking up
+        #[clap(long, conflicts_with = "password")]
+        build_bloom: Option<PathBuf>,
+
+        /// False positive rate of the bloom filter
+        #[clap(long, default_value_t = 0.001, requires = "build-bloom")]
+        fp_rate: f64,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum VaultCommand {
+    /// Add an entry to the vault, creating the vault if needed
+    Add {
+        site: String,
+
+        /// Username on the site
+        #[clap(short, long, default_value = "")]
+        username: String,
+
+        /// Store a password typed at the prompt instead of deriving one
+        #[clap(long)]
+        store: bool,
+
+        /// Free-form notes
+        #[clap(long, default_value = "")]
+        notes: String,
+
+        /// URL of the site
+        #[clap(long, default_value = "")]
+        url: String,
+
+        /// Add a 2FA secret or otpauth:// URI typed at the prompt
+        #[clap(long)]
+        otp: bool,
+
+        /// Length of the derived password
+        #[clap(short, long, default_value_t = 16)]
+        length: usize,
+
+        #[clap(flatten)]
+        kdf: KdfArgs,
+
+        #[clap(flatten)]
+        policy: PolicyArgs,
+    },
+
+    /// Print the password of an entry
+    Get {
+        site: String,
+
+        /// Username, needed when the site has several entries
+        #[clap(short, long)]
+        username: Option<String>,
+    },
+
+    /// List the entries in the vault
+    List,
+
+    /// Change the site, username, notes, URL or stored password of an entry
+    Edit {
+        site: String,
+
+        /// Username, needed when the site has several entries
+        #[clap(short, long)]
+        username: Option<String>,
+
+        /// New site name
+        #[clap(long)]
+        new_site: Option<String>,
+
+        /// New username
+        #[clap(long)]
+        new_username: Option<String>,
+
+        /// New notes
+        #[clap(long)]
+        notes: Option<String>,
+
+        /// New URL
+        #[clap(long)]
+        url: Option<String>,
+
+        /// Replace the password with one typed at the prompt
+        #[clap(long)]
+        password: bool,
+
+        /// Replace the 2FA secret with one typed at the prompt
+        #[clap(long)]
+        otp: bool,
+    },
+
+    /// Print the current one-time code of an entry
+    Otp {
+        site: String,
+
+        /// Username, needed when the site has several entries
+        #[clap(short, long)]
+        username: Option<String>,
+    },
+
+    /// Import entries from a KeePass CSV, Bitwarden JSON or generic CSV file
+    Import {
+        file: PathBuf,
+
+        /// keepass, bitwarden or csv
+        #[clap(short, long)]
+        format: Format,
+
+        /// Columns of a generic CSV file, such as site=Name,username=Login,password=Secret
+        #[clap(long)]
+        map: Option<ColumnMap>,
+
+        /// Only show what would be imported
+        #[clap(long)]
+        dry_run: bool,
+
+        /// Import without asking for confirmation
+        #[clap(short, long)]
+        yes: bool,
+    },
+
+    /// Export all entries with their passwords in plain text
+    Export {
+        file: PathBuf,
+
+        /// keepass, bitwarden or csv
+        #[clap(short, long)]
+        format: Format,
+
+        /// Columns of a generic CSV file, such as site=Name,username=Login,password=Secret
+        #[clap(long)]
+        map: Option<ColumnMap>,
+
+        /// Overwrite the file if it exists
+        #[clap(long)]
+        force: bool,
+    },
+
+    /// Remove an entry from the vault
+    Rm {
+        site: String,
+
+        /// Username, needed when the site has several entries
+        #[clap(short, long)]
+        username: Option<String>,
+    },
+
+    /// Give an entry a new password
+    Rotate {
+        site: String,
+
+        /// Username, needed when the site has several entries
+        #[clap(short, long)]
+        username: Option<String>,
+
+        /// Length of a new stored password
+        #[clap(short, long, default_value_t = 16)]
+        length: usize,
+
+        #[clap(flatten)]
+        policy: PolicyArgs,
+    },
+}
+
+/// 估计密码强度，给出种子时检查生成的密码
+fn check(
+    password: Option<String>,
+    seed: Option<String>,
+    length: usize,
+    policy: &PasswordPolicy,
+    breaches: Option<PathBuf>,
+) -> Result<()> {
+    let passwd = match (password, seed) {
+        (Some(passwd), _) => passwd,
+        (None, Some(seed)) => {
+            let passwd = generate_password(&seed, length, policy)?;
+            let passwd = passwd.strip_prefix(&format!("{}: ", seed)).unwrap_or(&passwd).to_string();
+            println!("{}: {}", seed, passwd);
+            passwd
+        }
+        (None, None) => rpassword::prompt_password("Password: ")?,
+    };
+
+    println!("{}", strength::estimate(&passwd));
+    match breaches {
+        Some(path) => breached(&path, &passwd),
+        None => Ok(()),
+    }
+}
+
+/// 出现在泄露数据中的密码以错误退出
+fn breached(path: &Path, passwd: &str) -> Result<()> {
+    match BreachChecker::open(path)?.check(passwd)? {
+        Breach::Clean => println!("not found in breaches"),
+        Breach::Found(count) => bail!("password appears {} times in breaches", count),
+        Breach::Likely => bail!("password very likely appears in breaches"),
+    }
+    Ok(())
+}
+
+/// 主密码优先从环境变量读取
+fn master_password() -> Result<String> {
+    match env::var(MASTER_ENV) {
+        Ok(master) => Ok(master),
+        Err(_) => Ok(rpassword::prompt_password("Master password: ")?),
+    }
+}
+
+/// 新建密码库时主密码须输入两次且一致
+fn new_master_password() -> Result<String> {
+    if let Ok(master) = env::var(MASTER_ENV) {
+        return Ok(master);
+    }
+    let master = rpassword::prompt_password("New master password: ")?;
+    if rpassword::prompt_password("Repeat master password: ")? != master {
+        bail!("master passwords do not match");
+    }
+    Ok(master)
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
-    if args.seed.len() < 4 {
-        bail!("seed `{}` length must >= 4", &args.seed);
+    match args.command {
+        Some(Command::Vault(command)) => return vault::run(command, vault::path(args.vault)),
+        Some(Command::Check { password, seed, length, policy, breaches }) => {
+            return check(password, seed, length, &policy.policy(), breaches);
+        }
+        Some(Command::Breached { file, password, build_bloom, fp_rate }) => {
+            if let Some(out) = build_bloom {
+                BloomFilter::build(&file, &out, fp_rate)?;
+                println!("built bloom filter {}", out.display());
+                return Ok(());
+            }
+            let passwd = match password {
+                Some(passwd) => passwd,
+                None => rpassword::prompt_password("Password: ")?,
+            };
+            return breached(&file, &passwd);
+        }
+        None => {}
+    }
+
+    if let Some(options) = args.passphrase.options() {
+        let phrase = match &args.site {
+            Some(site) => {
+                let params = args.kdf.params()?;
+                let master = master_password()?;
+                let phrase = derive_passphrase(&master, site, &args.login, args.counter, &params, &options)?;
+                format!("{}: {}", site, phrase)
+            }
+            None => random_passphrase(&options)?,
+        };
+        println!("{}", phrase);
+        eprintln!("entropy: {:.1} bits", options.entropy());
+        return Ok(());
+    }
+
+    if let Some(name) = &args.encoding {
+        let encoding = encoding_by_name(name)?;
+        let passwd = match &args.site {
+            Some(site) => {
+                let params = args.kdf.params()?;
+                let master = master_password()?;
+                let passwd = derive_encoded(&master, site, &args.login, args.counter, args.length, &params, &*encoding)?;
+                format!("{}: {}", site, passwd)
+            }
+            None => generate_encoded(&seed_arg(&args)?, args.length, &*encoding)?,
+        };
+        println!("{}", passwd);
+        return Ok(());
     }
 
-    let (seed, length) = (args.seed, args.length);
-    let passwd = generate_password(&seed[..], length);
+    let policy = args.policy.policy();
+    if let Some(site) = &args.site {
+        let params = args.kdf.params()?;
+        let master = master_password()?;
+        let passwd = derive_password(&master, site, &args.login, args.counter, args.length, &params, &policy)?;
+        println!("{}: {}", site, passwd);
+        return Ok(());
+    }
+
+    let seed = seed_arg(&args)?;
+    let length = args.length;
+    let passwd = generate_password(&seed[..], length, &policy);
     match passwd {
         Ok(val) => println!("{}", val),
         Err(err) => println!("{}", err),
@@ -30,3 +501,18 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+fn seed_arg(args: &Args) -> Result<String> {
+    let seed = args.seed.clone().unwrap_or_default();
+    if seed.len() < 4 {
+        bail!("seed `{}` length must >= 4", &seed);
+    }
+    Ok(seed)
+}
+
+fn encoding_by_name(name: &str) -> Result<Box<dyn Encoding>> {
+    match encoding::by_name(name) {
+        Some(encoding) => Ok(encoding),
+        None => bail!("unknown encoding `{}`, expected one of: {}", name, encoding::NAMES.join(", ")),
+    }
+}
@@ -0,0 +1,229 @@
+//! 密码库子命令
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use encryptor::kdf::{Algorithm, KdfParams, LATEST_VERSION};
+use encryptor::otp::{Otp, OtpKind};
+use encryptor::transfer::{self, ImportPlan};
+use encryptor::vault::{create_private, Entry, Secret, Vault};
+
+use crate::{master_password, new_master_password, VaultCommand};
+
+const VAULT_ENV: &str = "PASSWD_VAULT";
+const VAULT_FILE: &str = ".passwd_vault";
+
+/// 依次取 --vault、$PASSWD_VAULT 和 ~/.passwd_vault
+pub fn path(vault: Option<PathBuf>) -> PathBuf {
+    if let Some(path) = vault {
+        return path;
+    }
+    if let Ok(path) = env::var(VAULT_ENV) {
+        return PathBuf::from(path);
+    }
+    let home = env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    Path::new(&home).join(VAULT_FILE)
+}
+
+fn open(path: &Path) -> Result<Vault> {
+    if !path.exists() {
+        bail!("vault {} does not exist, add an entry first", path.display());
+    }
+    Vault::open(path, &master_password()?)
+}
+
+/// 读入 base32 密钥或 otpauth:// URI，统一保存为 URI
+fn prompt_otp() -> Result<String> {
+    let secret = rpassword::prompt_password("OTP secret or otpauth URI: ")?;
+    Ok(secret.parse::<Otp>()?.to_uri())
+}
+
+/// 密码库不存在时用最新参数新建
+fn open_or_create(path: &Path) -> Result<Vault> {
+    if path.exists() {
+        return open(path);
+    }
+    let kdf = KdfParams::from_version(LATEST_VERSION, Algorithm::Argon2id)?;
+    let vault = Vault::create(path, &new_master_password()?, kdf)?;
+    println!("created vault {}", path.display());
+    Ok(vault)
+}
+
+fn confirm(question: &str) -> Result<bool> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+fn prompt_password() -> Result<String> {
+    let passwd = rpassword::prompt_password("Password: ")?;
+    if passwd.is_empty() {
+        bail!("password must not be empty");
+    }
+    Ok(passwd)
+}
+
+pub fn run(command: VaultCommand, path: PathBuf) -> Result<()> {
+    match command {
+        VaultCommand::Add { site, username, store, notes, url, otp, length, kdf, policy } => {
+            let params = kdf.params()?;
+            let secret = if store {
+                Secret::Stored { password: prompt_password()? }
+            } else {
+                let policy = policy.policy();
+                policy.validate(length)?;
+                Secret::Generated { length, counter: 1, kdf: params, policy }
+            };
+
+            let mut vault = open_or_create(&path)?;
+            let mut entry = Entry::new(&site, &username, secret, &notes);
+            entry.url = url;
+            if otp {
+                entry.otp = Some(prompt_otp()?);
+            }
+            vault.add(entry)?;
+            vault.save()?;
+            let entry = vault.get(&site, Some(&username))?;
+            println!("{}: {}", site, vault.password(entry)?);
+        }
+        VaultCommand::Get { site, username } => {
+            let vault = open(&path)?;
+            let entry = vault.get(&site, username.as_deref())?;
+            println!("{}: {}", site, vault.password(entry)?);
+            if !entry.username.is_empty() {
+                println!("username: {}", entry.username);
+            }
+            if !entry.url.is_empty() {
+                println!("url: {}", entry.url);
+            }
+            if !entry.notes.is_empty() {
+                println!("notes: {}", entry.notes);
+            }
+        }
+        VaultCommand::List => {
+            let vault = open(&path)?;
+            println!("{:<24} {:<24} {:<10} updated", "site", "username", "kind");
+            for entry in vault.entries() {
+                let kind = match entry.secret {
+                    Secret::Generated { .. } => "generated",
+                    Secret::Stored { .. } => "stored",
+                };
+                println!("{:<24} {:<24} {:<10} {}", entry.site, entry.username, kind, entry.updated);
+            }
+        }
+        VaultCommand::Edit { site, username, new_site, new_username, notes, url, password, otp } => {
+            let mut vault = open(&path)?;
+            let entry = vault.get(&site, username.as_deref())?;
+
+            // 派生的密码依赖站点和用户名，改名时保存当前密码以免密码变化
+            let renamed = new_site.is_some() || new_username.is_some();
+            let secret = if password {
+                Some(Secret::Stored { password: prompt_password()? })
+            } else if renamed && matches!(entry.secret, Secret::Generated { .. }) {
+                Some(Secret::Stored { password: vault.password(entry)? })
+            } else {
+                None
+            };
+            let otp = if otp { Some(prompt_otp()?) } else { None };
+
+            let entry = vault.edit(&site, username.as_deref(), |e| {
+                if let Some(new_site) = new_site {
+                    e.site = new_site;
+                }
+                if let Some(new_username) = new_username {
+                    e.username = new_username;
+                }
+                if let Some(notes) = notes {
+                    e.notes = notes;
+                }
+                if let Some(url) = url {
+                    e.url = url;
+                }
+                if let Some(secret) = secret {
+                    e.secret = secret;
+                }
+                if otp.is_some() {
+                    e.otp = otp;
+                }
+            })?;
+            println!("updated {} ({})", entry.site, entry.username);
+            vault.save()?;
+        }
+        VaultCommand::Otp { site, username } => {
+            let mut vault = open(&path)?;
+            let entry = vault.get(&site, username.as_deref())?;
+            let mut otp: Otp = match &entry.otp {
+                Some(uri) => uri.parse()?,
+                None => bail!("no otp secret for {} ({})", entry.site, entry.username),
+            };
+            println!("{}", otp.code()?);
+
+            // HOTP 的计数器用过即作废，保存下一个值
+            match &mut otp.kind {
+                OtpKind::Hotp { counter } => {
+                    *counter += 1;
+                    let uri = otp.to_uri();
+                    vault.edit(&site, username.as_deref(), |e| e.otp = Some(uri))?;
+                    vault.save()?;
+                }
+                OtpKind::Totp { .. } => {
+                    if let Some(remaining) = otp.remaining() {
+                        eprintln!("expires in {}s", remaining);
+                    }
+                }
+            }
+        }
+        VaultCommand::Import { file, format, map, dry_run, yes } => {
+            let data = fs::read_to_string(&file)
+                .with_context(|| format!("failed to read {}", file.display()))?;
+            let mut vault = open_or_create(&path)?;
+            let plan = ImportPlan::new(&vault, &data, format, map.as_ref())?;
+
+            for entry in &plan.entries {
+                println!("add  {:<24} {}", entry.site, entry.username);
+            }
+            for skipped in &plan.skipped {
+                println!("skip {}", skipped);
+            }
+            println!("{} to import, {} skipped", plan.entries.len(), plan.skipped.len());
+            if dry_run || plan.entries.is_empty() || !(yes || confirm("Import these entries?")?) {
+                return Ok(());
+            }
+
+            let count = plan.apply(&mut vault)?;
+            vault.save()?;
+            println!("imported {} entries", count);
+        }
+        VaultCommand::Export { file, format, map, force } => {
+            if file.exists() && !force {
+                bail!("{} already exists, use --force to overwrite", file.display());
+            }
+            let vault = open(&path)?;
+            let data = transfer::export(&vault, format, map.as_ref())?;
+            // 导出文件含明文密码，只允许所有者读写，未给出 --force 时不覆盖已有文件
+            create_private(&file, !force)
+                .and_then(|mut out| out.write_all(data.as_bytes()))
+                .with_context(|| format!("failed to write {}", file.display()))?;
+            eprintln!("exported {} entries to {}, the passwords are not encrypted", vault.entries().len(), file.display());
+        }
+        VaultCommand::Rm { site, username } => {
+            let mut vault = open(&path)?;
+            let entry = vault.remove(&site, username.as_deref())?;
+            vault.save()?;
+            println!("removed {} ({})", entry.site, entry.username);
+        }
+        VaultCommand::Rotate { site, username, length, policy } => {
+            let mut vault = open(&path)?;
+            vault.rotate(&site, username.as_deref(), &policy.policy(), length)?;
+            vault.save()?;
+            let entry = vault.get(&site, username.as_deref())?;
+            println!("{}: {}", site, vault.password(entry)?);
+        }
+    }
+
+    Ok(())
+}
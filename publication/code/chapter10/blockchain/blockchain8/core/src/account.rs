@@ -1,5 +1,8 @@
+use crate::error::TxError;
+use crate::params::NetworkParams;
 use crate::transaction::Transaction;
-use utils::serializer::{serialize, hash_str};
+use utils::serializer::serialize;
+use utils::hasher::ChainHasher;
 use serde::Serialize;
 
 #[derive(Serialize, Debug, Eq, PartialEq, Clone)]
@@ -9,53 +12,137 @@ pub struct Account {
     pub balance: u64,
     pub address: String,
     pub hash: String,
+    #[serde(skip)]
+    params: NetworkParams,
 }
 
 impl Account {
-    pub fn new(address: String, name: String) -> Self {
+    pub fn new(address: String, name: String, params: &NetworkParams) -> Self {
         let mut account = Account {
             nonce: 0,
-            name: name,
-            balance: 100,
-            address: address,
+            name,
+            balance: params.init_balance,
+            address,
             hash: "".to_string(),
+            params: params.clone(),
         };
         account.set_hash();
 
         account
     }
 
+    // 由链上状态恢复账户，名称即地址
+    pub fn restore(address: String, balance: u64, nonce: u64, params: &NetworkParams) -> Self {
+        let mut account = Account::new(address.clone(), address, params);
+        account.balance = balance;
+        account.nonce = nonce;
+        account.set_hash();
+
+        account
+    }
+
     fn set_hash(&mut self) {
+        self.hash = "".to_string();
         let data = serialize(&self);
-        self.hash = hash_str(&data);
+        self.hash = self.params.hasher.hash_str(&data);
     }
 
     pub fn transfer_to(&mut self, to: &mut Self, amount: u64, fee: u64)
-        -> Result<Transaction, String>
+        -> Result<Transaction, TxError>
     {
-        if amount + fee > self.balance {
-            return Err("Error: not enough amount!".to_string());
+        self.transfer_locked(to, amount, fee, 0)
+    }
+
+    // 托管类付款：lock_time 之前交易只能留在交易池中
+    pub fn transfer_locked(&mut self, to: &mut Self, amount: u64, fee: u64, lock_time: u64)
+        -> Result<Transaction, TxError>
+    {
+        if amount == 0 {
+            return Err(TxError::ZeroAmount);
+        }
+        if self.address == to.address {
+            return Err(TxError::SelfTransfer);
         }
 
-        self.balance -= amount;
-        self.balance -= fee;
-        self.nonce += 1;
+        let need = amount.checked_add(fee).ok_or(TxError::Overflow)?;
+        if need > self.balance {
+            return Err(TxError::InsufficientFunds { need, have: self.balance });
+        }
+        let to_balance = to.balance.checked_add(amount).ok_or(TxError::Overflow)?;
+        let nonce = self.nonce.checked_add(1).ok_or(TxError::Overflow)?;
+
+        // nonce 只记录本账户发出的交易数，收款方不变
+        self.balance -= need;
+        self.nonce = nonce;
         self.set_hash();
 
-        to.balance += amount;
-        to.nonce += 1;
+        to.balance = to_balance;
         to.set_hash();
 
         let sign = format!("{} -> {}: {} btc",
                            self.address.clone(),
                            to.address.clone(),
                            amount);
-        let tx = Transaction::new(self.address.clone(),
-                                  to.address.clone(),
-                                  amount, fee, self.nonce, sign);
+        let tx = Transaction::new_locked(self.address.clone(),
+                                         to.address.clone(),
+                                         amount, fee, self.nonce, sign,
+                                         lock_time, &self.params);
         Ok(tx)
     }
 
+    // 以更高的手续费重发一笔尚未打包的转账，nonce 不变
+    pub fn bump_fee(&mut self, to: &mut Self, tx: &Transaction, fee: u64)
+        -> Result<Transaction, TxError>
+    {
+        self.check_replace(to, tx, fee)?;
+        let extra = fee - tx.fee;
+        if extra > self.balance {
+            return Err(TxError::InsufficientFunds { need: extra, have: self.balance });
+        }
+
+        self.balance -= extra;
+        self.set_hash();
+
+        let tx = Transaction::new_locked(self.address.clone(), to.address.clone(),
+                                         tx.amount, fee, tx.nonce, tx.sign.clone(),
+                                         tx.lock_time, &self.params);
+        Ok(tx)
+    }
+
+    // 用转给自己的零额交易顶替一笔尚未打包的转账，收款方退回该笔金额
+    pub fn cancel(&mut self, to: &mut Self, tx: &Transaction, fee: u64)
+        -> Result<Transaction, TxError>
+    {
+        self.check_replace(to, tx, fee)?;
+        let available = tx.amount.checked_add(tx.fee)
+                                 .and_then(|spent| spent.checked_add(self.balance))
+                                 .ok_or(TxError::Overflow)?;
+        if fee > available {
+            return Err(TxError::InsufficientFunds { need: fee, have: available });
+        }
+
+        self.balance = available - fee;
+        self.set_hash();
+        to.balance = to.balance.saturating_sub(tx.amount);
+        to.set_hash();
+
+        let sign = format!("{} cancels nonce {}", self.address, tx.nonce);
+        let tx = Transaction::new(self.address.clone(), self.address.clone(),
+                                  0, fee, tx.nonce, sign, &self.params);
+        Ok(tx)
+    }
+
+    fn check_replace(&self, to: &Self, tx: &Transaction, fee: u64) -> Result<(), TxError> {
+        if tx.from != self.address || tx.to != to.address || tx.is_cancel() {
+            return Err(TxError::NothingToReplace);
+        }
+        if fee <= tx.fee {
+            return Err(TxError::ReplacementFeeTooLow { fee, min: tx.fee + 1 });
+        }
+
+        Ok(())
+    }
+
     pub fn account_info(&self) {
         println!("{:#?}", &self);
     }
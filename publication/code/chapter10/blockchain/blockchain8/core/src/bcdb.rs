@@ -1,13 +1,13 @@
 use leveldb::kv::KV;
 use leveldb::database::Database;
-use leveldb::options::{Options, WriteOptions};
+use leveldb::options::{Options, ReadOptions, WriteOptions};
 use utils::bkey;
 use std::{env, fs};
 
 pub struct BlockChainDb;
 
 impl BlockChainDb {
-    pub fn new(path: &str) -> Database<bkey::BKey> {
+    pub fn open(path: &str) -> Database<bkey::BKey> {
         let mut dir = env::current_dir().unwrap();
         dir.push(path);
 
@@ -28,9 +28,25 @@ impl BlockChainDb {
 
     pub fn write_db(db: &mut Database<bkey::BKey>, key: bkey::BKey, val: &[u8]) {
         let write_opts = WriteOptions::new();
-        match db.put(write_opts, key, &val) {
+        match db.put(write_opts, key, val) {
             Ok(_) => (),
             Err(e) => panic!("Failed to write block to database: {:?}", e),
         }
     }
+
+    pub fn read_db(db: &Database<bkey::BKey>, key: bkey::BKey) -> Option<Vec<u8>> {
+        let read_opts = ReadOptions::new();
+        match db.get(read_opts, key) {
+            Ok(val) => val,
+            Err(e) => panic!("Failed to read from database: {:?}", e),
+        }
+    }
+
+    pub fn delete_db(db: &mut Database<bkey::BKey>, key: bkey::BKey) {
+        let write_opts = WriteOptions::new();
+        match db.delete(write_opts, key) {
+            Ok(_) => (),
+            Err(e) => panic!("Failed to delete block from database: {:?}", e),
+        }
+    }
 }
@@ -1,9 +1,11 @@
 use chrono::prelude::*;
-use serde::Serialize;
-use utils::serializer::{serialize, hash_str};
+use serde::{Deserialize, Serialize};
+use utils::hasher::HashAlgo;
+use utils::serializer::serialize;
+use crate::merkle::{self, MerkleProof};
 use crate::transaction::Transaction;
 
-#[derive(Serialize, Debug, PartialEq, Eq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct BlockHeader {
     pub nonce: u32,
     pub time: i64,
@@ -12,7 +14,7 @@ pub struct BlockHeader {
     pub pre_hash: String,
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Block {
     pub header: BlockHeader,
     pub tranxs: Vec<Transaction>,
@@ -20,68 +22,40 @@ pub struct Block {
 }
 
 impl Block {
-    pub fn new(txs: Vec<Transaction>, pre_hash: String, bits: u32) -> Self {
+    pub fn new(txs: Vec<Transaction>, pre_hash: String, bits: u32, hasher: HashAlgo)
+        -> Self {
         let time = Utc::now().timestamp();
-        let txs_hash = Self::merkle_hash_str(&txs);
+        let txs_hash = Self::merkle_hash_str(&txs, hasher);
 
         Block {
             header: BlockHeader {
                 nonce: 0,
-                time: time,
-                bits: bits,
-                txs_hash: txs_hash,
-                pre_hash: pre_hash,
+                time,
+                bits,
+                txs_hash,
+                pre_hash,
             },
             tranxs: txs,
             hash: "".to_string(),
         }
     }
 
-    fn merkle_hash_str(txs: &Vec<Transaction>) -> String {
-        if txs.len() == 0 {
-            return "00000000".to_string();
-        }
-
-        let mut merkle_tree: Vec<String> = Vec::new();
-        for tx in txs {
-            merkle_tree.push(tx.hash.clone());
-        }
-
-        let mut j: u64 = 0;
-        let mut size = merkle_tree.len();
-        while size > 1 {
-            let mut i: u64 = 0;
-            let temp = size as u64;
-
-            while i < temp {
-                let k = Self::min(i + 1, temp - 1);
-                let idx1 = (j + i) as usize;
-                let idx2 = (j + k) as usize;
-                let hash1 = merkle_tree[idx1].clone();
-                let hash2 = merkle_tree[idx2].clone();
-                let merge: String = format!("{}-{}",hash1, hash2);
-                let merge_ser = serialize(&merge);
-                let merge_hash = hash_str(&merge_ser);
-                merkle_tree.push(merge_hash);
-                i += 2;
-            }
+    fn merkle_hash_str(txs: &[Transaction], hasher: HashAlgo) -> String {
+        let hashes: Vec<String> = txs.iter().map(|tx| tx.hash.clone()).collect();
+        merkle::merkle_root(&hashes, hasher)
+    }
 
-            j += temp;
-            size = (size + 1) >> 1;
-        }
+    // 共识重量：区块序列化后的字节数
+    pub fn weight(&self) -> usize {
+        serialize(self).len()
+    }
 
-        if merkle_tree.len() != 0 {
-            merkle_tree.pop().unwrap()
-        } else {
-            "00000000".to_string()
-        }
+    pub fn merkle_root(&self, hasher: HashAlgo) -> String {
+        Self::merkle_hash_str(&self.tranxs, hasher)
     }
 
-    fn min(num1: u64, num2: u64) -> u64 {
-        if num1 >= num2 {
-            num2
-        } else {
-            num1
-        }
+    pub fn merkle_proof(&self, index: usize, hasher: HashAlgo) -> Option<MerkleProof> {
+        let hashes: Vec<String> = self.tranxs.iter().map(|tx| tx.hash.clone()).collect();
+        merkle::merkle_proof(&hashes, index, hasher)
     }
 }
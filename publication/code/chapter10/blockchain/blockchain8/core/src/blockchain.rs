@@ -1,36 +1,53 @@
-use std::sync::Mutex;
 use std::collections::HashMap;
 use bigint::U256;
 use leveldb::database::Database;
 use utils::bkey::BKey;
-use utils::serializer::{serialize, hash_str, hash_u8};
-use crate::block::Block;
+use utils::serializer::{deserialize, serialize};
+use utils::hasher::{from_hex, ChainHasher, HashAlgo};
+use crate::params::NetworkParams;
+use crate::block::{Block, BlockHeader};
+use crate::bloom::BloomFilter;
+use crate::message::MerkleBlock;
 use crate::bcdb::BlockChainDb;
+use crate::error::InspectError;
+use crate::inspect::ChainInspector;
 use crate::transaction::Transaction;
 use crate::pow::ProofOfWork;
+use crate::state::State;
 
-const INIT_BITS: u32 = 0x2100FFFF;
-const SAVE_DIR: &str = "bc_db";
-const PRE_HASH: &str = "22caaf24ef0aea3522c13d133912d2b722caaf24ef0aea3522c13d133912d2b7";
+const MTP_SPAN: usize = 11;
 
 pub struct BlockChain {
     blocks_db: Box<Database<BKey>>,
-    blocks_index: Mutex<HashMap<String, Block>>,
+    blocks_index: HashMap<String, Block>, // 修剪模式下只保留最近的完整区块
+    headers_index: HashMap<String, BlockHeader>,
+    heights: HashMap<String, u64>,
     pub gnes_hash: String,
     pub curr_hash: String,
     pub curr_bits: u32,
+    pub curr_height: u64,
+    pub params: NetworkParams,
+    pub state: State,
 }
 
 impl BlockChain {
-    pub fn new() -> Self {
-        let mut db = BlockChainDb::new(SAVE_DIR);
-        let genesis = Self::genesis_block();
-        BlockChain::write_block(&mut db, &genesis);
+    pub fn new(params: &NetworkParams) -> Self {
+        let mut db = BlockChainDb::open(&params.save_dir);
+        let genesis = Self::genesis_block(params);
+        BlockChain::write_block(&mut db, &genesis, params.hasher);
         BlockChain::write_tail(&mut db, &genesis);
         println!("New produced block saved!\n");
 
+        let mut state = State::new(params);
+        state.apply_block(&genesis).expect("Invalid genesis block");
+        BlockChain::write_state(&mut db, &state);
+
         let gene_block = genesis.clone();
-        let mut block_index = Mutex::new(HashMap::new());
+        let mut block_index = HashMap::new();
+        let mut headers_index = HashMap::new();
+        let mut heights = HashMap::new();
+        headers_index.insert(genesis.hash.clone(), genesis.header.clone());
+        heights.insert(genesis.hash.clone(), 0);
         Self::update_hmap(&mut block_index, gene_block);
 
         let gnes_hash = genesis.hash.clone();
@@ -38,82 +55,273 @@ impl BlockChain {
         BlockChain {
             blocks_db: Box::new(db),
             blocks_index: block_index,
-            gnes_hash: gnes_hash,
-            curr_hash: curr_hash,
-            curr_bits: INIT_BITS,
+            headers_index,
+            heights,
+            gnes_hash,
+            curr_hash,
+            curr_bits: params.init_bits,
+            curr_height: 0,
+            params: params.clone(),
+            state,
         }
     }
 
-    fn genesis_block() -> Block {
+    // 打开已保存的链，浏览器只读查看，也可在其上继续挖矿
+    pub fn load(params: &NetworkParams) -> Result<Self, InspectError> {
+        let inspector = ChainInspector::load(params)?;
+        let db = BlockChainDb::open(&params.save_dir);
+        let state = Self::read_state(&db).ok_or(InspectError::NoChain)?
+                                         .with_params(params);
+
+        let mut blocks_index = HashMap::new();
+        let mut headers_index = HashMap::new();
+        let mut heights = HashMap::new();
+        for record in inspector.records() {
+            headers_index.insert(record.hash.clone(), record.header.clone());
+            heights.insert(record.hash.clone(), record.height);
+            if let Some(block) = &record.block {
+                Self::update_hmap(&mut blocks_index, block.clone());
+            }
+        }
+
+        let records = inspector.records();
+        let (genesis, tip) = (&records[0], &records[records.len() - 1]);
+        Ok(BlockChain {
+            blocks_db: Box::new(db),
+            blocks_index,
+            headers_index,
+            heights,
+            gnes_hash: genesis.hash.clone(),
+            curr_hash: tip.hash.clone(),
+            curr_bits: params.init_bits,
+            curr_height: tip.height,
+            params: params.clone(),
+            state,
+        })
+    }
+
+    // 创世区块完全由网络参数决定，各节点算出的哈希相同
+    pub fn genesis_block(params: &NetworkParams) -> Block {
         println!("Start mining .... ");
         let from = "0x0000".to_string();
         let to   = "0x0000".to_string();
-        let sign = "创世区块".to_string();
-        let tx = Transaction::new(from, to, 0, 0, 0, sign);
-        let mut block  = Block::new(vec![tx], PRE_HASH.to_string(), INIT_BITS);
+        let sign = params.genesis_sign.to_string();
+        let tx = Transaction::new(from, to, 0, 0, 0, sign, params);
+        let pre_hash = params.pre_hash.to_string();
+        let mut block  = Block::new(vec![tx], pre_hash, params.init_bits, params.hasher);
+        block.header.time = params.genesis_time;
 
         let header_ser = ProofOfWork::prepare_data(&mut block, 0);
-        block.hash = hash_str(&header_ser);
+        block.hash = params.hasher.hash_str(&header_ser);
         println!("Produced a new block!");
 
         block
     }
 
-    pub fn add_block(&mut self, block: Block) {
-        Self::write_block(&mut (self.blocks_db), &block);
+    // state 为调用方校验区块后得到的新状态
+    pub fn add_block(&mut self, block: Block, state: State) {
+        Self::write_block(&mut (self.blocks_db), &block, self.params.hasher);
         Self::write_tail(&mut (self.blocks_db), &block);
+        Self::write_state(&mut (self.blocks_db), &state);
         println!("New produced block saved!\n");
         self.curr_hash = block.hash.clone();
-        self.curr_bits = block.header.bits.clone();
+        self.curr_height += 1;
+        self.state = state;
+        self.headers_index.insert(block.hash.clone(), block.header.clone());
+        self.heights.insert(block.hash.clone(), self.curr_height);
         Self::update_hmap(&mut self.blocks_index, block);
+        self.prune();
     }
 
-    fn update_hmap(hmap: &mut Mutex<HashMap<String, Block>>, block: Block) {
-        let mut hmap = hmap.lock().unwrap();
+    // 只保留最近 prune_depth 个完整区块，更早的区块只留区块头
+    fn prune(&mut self) {
+        let depth = match self.params.prune_depth {
+            Some(depth) => depth.max(1),
+            None => return,
+        };
+        if self.curr_height < depth {
+            return;
+        }
+
+        let cutoff = self.curr_height - depth;
+        let heights = &self.heights;
+        let pruned: Vec<String> = self.blocks_index.keys()
+                                      .filter(|hash| heights[*hash] <= cutoff)
+                                      .cloned()
+                                      .collect();
+        for hash in pruned {
+            self.blocks_index.remove(&hash);
+            BlockChainDb::delete_db(&mut (self.blocks_db), Self::block_key(&hash));
+        }
+    }
+
+    // 最近 MTP_SPAN 个区块时间的中位数
+    pub fn median_time_past(&self) -> i64 {
+        let mut times = Vec::new();
+        let mut hash = self.curr_hash.clone();
+        while times.len() < MTP_SPAN {
+            match self.headers_index.get(&hash) {
+                Some(h) => {
+                    times.push(h.time);
+                    hash = h.pre_hash.clone();
+                },
+                None => break,
+            }
+        }
+        times.sort_unstable();
+
+        times[times.len() / 2]
+    }
+
+    pub fn get_block(&self, hash: &str) -> Option<&Block> {
+        self.blocks_index.get(hash)
+    }
+
+    pub fn get_header(&self, hash: &str) -> Option<&BlockHeader> {
+        self.headers_index.get(hash)
+    }
+
+    pub fn height_of(&self, hash: &str) -> Option<u64> {
+        self.heights.get(hash).copied()
+    }
+
+    fn update_hmap(hmap: &mut HashMap<String, Block>, block: Block) {
         let hash = block.hash.clone();
         hmap.insert(hash, block);
     }
 
-    fn write_block(db: &mut Database<BKey>, block: &Block) {
-        let header_ser = serialize(&(block.header));
+    // 区块以区块哈希为键
+    fn block_key(hash: &str) -> BKey {
+        let hash_u = from_hex(hash).unwrap_or_default();
+        BKey{ val: U256::from(hash_u.as_slice()) }
+    }
+
+    // 区块头单独存放，修剪区块后仍然保留
+    fn header_key(hash: &str, hasher: HashAlgo) -> BKey {
+        let key_ser = [b"header".as_ref(), hash.as_bytes()].concat();
         let mut hash_u: [u8; 32] = [0; 32];
-        hash_u8(&header_ser, &mut hash_u);
+        hasher.hash_u8(&key_ser, &mut hash_u);
+
+        BKey{ val: U256::from(hash_u) }
+    }
 
-        let key = BKey{ val: U256::from(hash_u) };
+    fn write_block(db: &mut Database<BKey>, block: &Block, hasher: HashAlgo) {
+        let key = Self::block_key(&block.hash);
         let val = serialize(&block);
         BlockChainDb::write_db(db, key, &val);
+
+        let key = Self::header_key(&block.hash, hasher);
+        let val = serialize(&(block.header));
+        BlockChainDb::write_db(db, key, &val);
+    }
+
+    pub fn read_block(db: &Database<BKey>, hash: &str) -> Option<Block> {
+        BlockChainDb::read_db(db, Self::block_key(hash))
+            .and_then(|val| deserialize(&val).ok())
+    }
+
+    pub fn read_header(db: &Database<BKey>, hash: &str, hasher: HashAlgo)
+        -> Option<BlockHeader>
+    {
+        BlockChainDb::read_db(db, Self::header_key(hash, hasher))
+            .and_then(|val| deserialize(&val).ok())
+    }
+
+    pub fn read_tail(db: &Database<BKey>) -> Option<String> {
+        let key = BKey{ val: U256::from("tail".as_bytes()) };
+        BlockChainDb::read_db(db, key)
+            .and_then(|val| deserialize(&val).ok())
+    }
+
+    fn write_state(db: &mut Database<BKey>, state: &State) {
+        let key = BKey{ val: U256::from("state".as_bytes()) };
+        let val = serialize(state);
+        BlockChainDb::write_db(db, key, &val);
     }
 
-    fn write_tail(mut db: &mut Database<BKey>, block: &Block) {
+    fn read_state(db: &Database<BKey>) -> Option<State> {
+        let key = BKey{ val: U256::from("state".as_bytes()) };
+        BlockChainDb::read_db(db, key)
+            .and_then(|val| deserialize(&val).ok())
+    }
+
+    fn write_tail(db: &mut Database<BKey>, block: &Block) {
         let key = BKey{ val: U256::from("tail".as_bytes()) };
         let val = serialize(&(block.hash));
-        BlockChainDb::write_db(&mut db, key, &val);
+        BlockChainDb::write_db(db, key, &val);
     }
 
-    pub fn block_info(&self) {
+    // 修剪后只返回仍保留完整数据的区块
+    pub fn blocks(&self) -> Vec<Block> {
         let mut hash = self.curr_hash.clone();
-        let hmap = self.blocks_index.lock().unwrap();
+        let hmap = &self.blocks_index;
         let mut blocks: Vec<Block> = Vec::new();
 
-        loop {
-            if let Some(b) = hmap.get(&hash) {
-                blocks.push(b.clone());
-                hash = b.header.pre_hash.clone();
-            } else {
-                panic!("Error getting block");
+        while let Some(b) = hmap.get(&hash) {
+            blocks.push(b.clone());
+            hash = b.header.pre_hash.clone();
+            if b.hash == self.gnes_hash {
+                break;
             }
+        }
+        blocks.reverse();
+
+        blocks
+    }
 
+    // 主链上指定高度的区块哈希
+    pub fn hash_at(&self, height: u64) -> Option<String> {
+        if height > self.curr_height {
+            return None;
+        }
+
+        let mut hash = self.curr_hash.clone();
+        for _ in height..self.curr_height {
+            hash = self.headers_index.get(&hash)?.pre_hash.clone();
+        }
+        Some(hash)
+    }
+
+    // 主链上从创世区块到链尾的区块哈希
+    pub fn main_chain(&self) -> Vec<String> {
+        let mut hash = self.curr_hash.clone();
+        let mut hashes = Vec::new();
+
+        while let Some(h) = self.headers_index.get(&hash) {
+            hashes.push(hash.clone());
             if hash == self.gnes_hash {
-                if let Some(b) = hmap.get(&hash) {
-                    blocks.push(b.clone());
-                }
                 break;
             }
+            hash = h.pre_hash.clone();
         }
-        blocks.reverse();
+        hashes.reverse();
 
-        for b in blocks {
-            println!("{:#?}", b);
+        hashes
+    }
+
+    // 从定位器中找到第一个位于主链上的区块，返回其后的区块头
+    pub fn headers_after(&self, locator: &[String], stop_hash: &str, max: usize)
+        -> Vec<BlockHeader>
+    {
+        let hashes = self.main_chain();
+        let start = locator.iter()
+                           .find_map(|hash| hashes.iter().position(|h| h == hash))
+                           .map(|pos| pos + 1)
+                           .unwrap_or(0);
+
+        let mut headers = Vec::new();
+        for hash in hashes.iter().skip(start).take(max) {
+            headers.push(self.headers_index[hash].clone());
+            if hash == stop_hash {
+                break;
+            }
         }
+
+        headers
+    }
+
+    pub fn merkle_block(&self, hash: &str, filter: &BloomFilter) -> Option<MerkleBlock> {
+        self.get_block(hash)
+            .map(|b| MerkleBlock::from_block(b, filter, self.params.hasher))
     }
 }
@@ -0,0 +1,118 @@
+// 改编自 chapter10/bloom_filter.rs
+// 原实现的随机哈希函数换成由 tweak 决定的链哈希，使轻节点和全节点对同一元素
+// 算出相同的下标，过滤器也可以序列化后发给全节点
+use std::hash::{BuildHasher, Hash, Hasher};
+use serde::{Deserialize, Serialize};
+use utils::hasher::{ChainHasher, HashAlgo};
+
+// BIP37 对过滤器大小和哈希函数个数的限制
+const MAX_FILTER_BITS: usize = 36000 * 8;
+const MAX_HASH_FUNCS: usize = 50;
+
+// 以种子开头收集待哈希的字节，结束时取链哈希的前 8 字节
+pub struct SeededHasher {
+    data: Vec<u8>,
+    hasher: HashAlgo,
+}
+
+impl Hasher for SeededHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let mut hash_u: [u8; 32] = [0; 32];
+        self.hasher.hash_u8(&self.data, &mut hash_u);
+
+        let mut h: [u8; 8] = [0; 8];
+        h.copy_from_slice(&hash_u[0..8]);
+        u64::from_le_bytes(h)
+    }
+}
+
+// 种子确定的哈希函数，取代原实现中的 RandomState
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeededState {
+    seed: u32,
+    hasher: HashAlgo,
+}
+
+impl BuildHasher for SeededState {
+    type Hasher = SeededHasher;
+
+    fn build_hasher(&self) -> SeededHasher {
+        SeededHasher { data: self.seed.to_le_bytes().to_vec(), hasher: self.hasher }
+    }
+}
+
+// 布隆过滤器，轻节点用其向全节点描述自己关心的地址和交易
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BloomFilter {
+    bits: Vec<bool>,           // 比特桶
+    hash_fn_count: usize,      // 哈希函数个数
+    hashers: [SeededState; 2], // 两个哈希函数，种子由 tweak 决定
+}
+
+impl BloomFilter {
+    pub fn new(cap: usize, ert: f64, tweak: u32, hasher: HashAlgo) -> Self {
+        let ln22 = std::f64::consts::LN_2.powf(2f64);
+        // 计算比特桶大小和哈希函数个数
+        let bits_count = -(cap.max(1) as f64) * ert.ln() / ln22;
+        let hash_fn_count = -ert.log2();
+
+        // tweak 不同则哈希函数不同，避免不同轻节点的过滤器相同
+        let hashers = [
+            SeededState { seed: tweak, hasher },
+            SeededState { seed: tweak.wrapping_add(1), hasher },
+        ];
+
+        Self {
+            bits: vec![false; (bits_count.ceil() as usize).clamp(1, MAX_FILTER_BITS)],
+            hash_fn_count: (hash_fn_count.ceil() as usize).clamp(1, MAX_HASH_FUNCS),
+            hashers,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        !self.bits.is_empty()
+            && self.bits.len() <= MAX_FILTER_BITS
+            && self.hash_fn_count > 0
+            && self.hash_fn_count <= MAX_HASH_FUNCS
+    }
+
+    // 按照 hash_fn_count 计算值并置比特桶相应位为 true
+    pub fn insert<T: ?Sized + Hash>(&mut self, elem: &T) {
+        let hashes = self.make_hash(elem);
+        for fn_i in 0..self.hash_fn_count {
+            let index = self.get_index(hashes, fn_i as u64);
+            self.bits[index] = true;
+        }
+    }
+
+    // 数据查询
+    pub fn contains<T: ?Sized + Hash>(&self, elem: &T) -> bool {
+        let hashes = self.make_hash(elem);
+        (0..self.hash_fn_count).all(|fn_i| {
+            let index = self.get_index(hashes, fn_i as u64);
+            self.bits[index]
+        })
+    }
+
+    // 计算哈希
+    fn make_hash<T: ?Sized + Hash>(&self, elem: &T) -> (u64, u64) {
+        let hasher1 = &mut self.hashers[0].build_hasher();
+        let hasher2 = &mut self.hashers[1].build_hasher();
+
+        elem.hash(hasher1);
+        elem.hash(hasher2);
+
+        (hasher1.finish(), hasher2.finish())
+    }
+
+    // 获取比特桶某位下标
+    fn get_index(&self, (h1, h2): (u64, u64), fn_i: u64) -> usize {
+        let ih2 = fn_i.wrapping_mul(h2);
+        let h1pih2 = h1.wrapping_add(ih2);
+        (h1pih2 % self.bits.len() as u64) as usize
+    }
+}
@@ -0,0 +1,271 @@
+use std::io;
+use std::sync::{Arc, Mutex, RwLock};
+use chrono::prelude::*;
+use crate::account::Account;
+use crate::block::{Block, BlockHeader};
+use crate::blockchain::BlockChain;
+use crate::bloom::BloomFilter;
+use crate::error::{BlockError, InspectError, TxError};
+use crate::fees::{EstimateMode, FeeEstimator};
+use crate::mempool::Mempool;
+use crate::message::MerkleBlock;
+use crate::params::NetworkParams;
+use crate::pow::ProofOfWork;
+use crate::transaction::Transaction;
+
+// 为区块头和 coinbase 交易预留的重量
+const BLOCK_RESERVED_WEIGHT: usize = 1000;
+
+// 挖矿模板，矿工据此在链锁之外独立挖矿
+#[derive(Debug, Clone)]
+pub struct BlockTemplate {
+    pub pre_hash: String,
+    pub bits: u32,
+    pub height: u64,
+    pub mtp: i64,
+    pub txs: Vec<Transaction>,
+}
+
+// 可克隆的链句柄：读操作可并发，区块连接串行执行
+#[derive(Clone)]
+pub struct ChainHandle {
+    chain: Arc<RwLock<BlockChain>>,
+    mempool: Arc<Mutex<Mempool>>,
+    fees: Arc<Mutex<FeeEstimator>>,
+    connect: Arc<Mutex<()>>,
+}
+
+impl ChainHandle {
+    pub fn new(chain: BlockChain) -> Self {
+        Self::with_mempool(chain, Mempool::new())
+    }
+
+    // 打开已保存的链和交易池，可继续发送交易或挖矿
+    pub fn load(params: &NetworkParams) -> Result<Self, InspectError> {
+        let chain = BlockChain::load(params)?;
+        let mempool = Mempool::load(&params.save_dir, &chain.state, chain.curr_height);
+        Ok(Self::with_mempool(chain, mempool))
+    }
+
+    fn with_mempool(chain: BlockChain, mempool: Mempool) -> Self {
+        let fees = FeeEstimator::load(&chain.params.save_dir, chain.curr_height);
+        ChainHandle {
+            chain: Arc::new(RwLock::new(chain)),
+            mempool: Arc::new(Mutex::new(mempool)),
+            fees: Arc::new(Mutex::new(fees)),
+            connect: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn save_mempool(&self) -> io::Result<()> {
+        let chain = self.chain.read().unwrap();
+        let mempool = self.mempool.lock().unwrap();
+        mempool.save(&chain.params.save_dir)
+    }
+
+    pub fn tip(&self) -> (String, u64) {
+        let chain = self.chain.read().unwrap();
+        (chain.curr_hash.clone(), chain.curr_height)
+    }
+
+    pub fn bits(&self) -> u32 {
+        let chain = self.chain.read().unwrap();
+        chain.curr_bits
+    }
+
+    pub fn height_of(&self, hash: &str) -> Option<u64> {
+        let chain = self.chain.read().unwrap();
+        chain.height_of(hash)
+    }
+
+    pub fn hash_at(&self, height: u64) -> Option<String> {
+        let chain = self.chain.read().unwrap();
+        chain.hash_at(height)
+    }
+
+    pub fn block(&self, hash: &str) -> Option<Block> {
+        let chain = self.chain.read().unwrap();
+        chain.get_block(hash).cloned()
+    }
+
+    pub fn blocks(&self) -> Vec<Block> {
+        let chain = self.chain.read().unwrap();
+        chain.blocks()
+    }
+
+    // 同一读锁下取链尾高度和主链区块，两者对应同一时刻的链
+    pub fn tip_blocks(&self) -> (u64, Vec<Block>) {
+        let chain = self.chain.read().unwrap();
+        (chain.curr_height, chain.blocks())
+    }
+
+    pub fn headers_after(&self, locator: &[String], stop_hash: &str, max: usize)
+        -> Vec<BlockHeader>
+    {
+        let chain = self.chain.read().unwrap();
+        chain.headers_after(locator, stop_hash, max)
+    }
+
+    pub fn merkle_block(&self, hash: &str, filter: &BloomFilter) -> Option<MerkleBlock> {
+        let chain = self.chain.read().unwrap();
+        chain.merkle_block(hash, filter)
+    }
+
+    pub fn balance(&self, address: &str) -> u64 {
+        let chain = self.chain.read().unwrap();
+        chain.state.balance(address)
+    }
+
+    pub fn nonce(&self, address: &str) -> u64 {
+        let chain = self.chain.read().unwrap();
+        chain.state.nonce(address)
+    }
+
+    pub fn address_used(&self, address: &str) -> bool {
+        let chain = self.chain.read().unwrap();
+        chain.state.contains(address)
+    }
+
+    pub fn submit_tx(&self, tx: Transaction) -> Result<(), TxError> {
+        let chain = self.chain.read().unwrap();
+        let mut mempool = self.mempool.lock().unwrap();
+        mempool.add(tx, &chain.state, chain.curr_height)
+    }
+
+    // 在 target 个区块内确认所需的费率(每 1000 字节)，样本不足时返回 None
+    pub fn estimate_fee(&self, target: usize, mode: EstimateMode) -> Option<u64> {
+        let pending = self.mempool.lock().unwrap().entries();
+        let fees = self.fees.lock().unwrap();
+        fees.estimate(target, mode, &pending)
+    }
+
+    // 链上余额减去池中待打包交易的花费，nonce 计入待打包交易
+    pub fn account(&self, address: &str) -> Account {
+        let chain = self.chain.read().unwrap();
+        let mempool = self.mempool.lock().unwrap();
+        let mut balance = chain.state.balance(address);
+        let mut nonce = chain.state.nonce(address);
+        for tx in mempool.pending_from(address) {
+            balance = balance.saturating_sub(tx.amount.saturating_add(tx.fee));
+            nonce = tx.nonce;
+        }
+        Account::restore(address.to_string(), balance, nonce, &chain.params)
+    }
+
+    pub fn pending_tx(&self, from: &str, nonce: u64) -> Option<Transaction> {
+        let mempool = self.mempool.lock().unwrap();
+        mempool.pending_from(from)
+               .into_iter()
+               .find(|tx| tx.nonce == nonce)
+               .cloned()
+    }
+
+    pub fn mempool_txs(&self) -> Vec<Transaction> {
+        let mempool = self.mempool.lock().unwrap();
+        mempool.pending()
+    }
+
+    pub fn template(&self) -> BlockTemplate {
+        let chain = self.chain.read().unwrap();
+        let mempool = self.mempool.lock().unwrap();
+        let height = chain.curr_height + 1;
+        let mtp = chain.median_time_past();
+        let max_weight = chain.params.max_block_weight;
+        BlockTemplate {
+            pre_hash: chain.curr_hash.clone(),
+            bits: chain.curr_bits,
+            height,
+            mtp,
+            txs: mempool.select(height, mtp,
+                                max_weight.saturating_sub(BLOCK_RESERVED_WEIGHT),
+                                chain.params.max_block_txs - 1),
+        }
+    }
+
+    // 校验在读锁下进行，只有写入区块时才持有写锁
+    pub fn submit_block(&self, block: Block) -> Result<(), BlockError> {
+        let _guard = self.connect.lock().unwrap();
+        let state = {
+            let chain = self.chain.read().unwrap();
+            let params = &chain.params;
+            if block.header.pre_hash != chain.curr_hash {
+                // 分叉点低于最后一个检查点的区块直接拒绝
+                let fork = chain.height_of(&block.header.pre_hash).map(|h| h + 1);
+                return match (fork, params.last_checkpoint()) {
+                    (Some(height), Some(last)) if height <= last =>
+                        Err(BlockError::ForkBeforeCheckpoint { height }),
+                    _ => Err(BlockError::Stale),
+                };
+            }
+
+            // 难度由链决定，不能采用区块自带的 bits
+            let bits = chain.curr_bits;
+            if block.header.bits != bits {
+                return Err(BlockError::BadBits { expected: bits, got: block.header.bits });
+            }
+            let hasher = chain.params.hasher;
+            let pow = ProofOfWork::new(bits, hasher);
+            if !pow.validate(&block) {
+                return Err(BlockError::BadProofOfWork);
+            }
+            if block.merkle_root(hasher) != block.header.txs_hash {
+                return Err(BlockError::BadMerkleRoot);
+            }
+
+            let height = chain.curr_height + 1;
+            if let Some(hash) = params.checkpoint(height) {
+                if hash != block.hash {
+                    return Err(BlockError::CheckpointMismatch { height });
+                }
+            }
+
+            let count = block.tranxs.len();
+            if count > params.max_block_txs {
+                return Err(BlockError::TooManyTxs { count, max: params.max_block_txs });
+            }
+            let weight = block.weight();
+            if weight > params.max_block_weight {
+                return Err(BlockError::TooHeavy { weight, max: params.max_block_weight });
+            }
+
+            // 区块时间须晚于中位时间，且不能超前本地时间太多
+            let mtp = chain.median_time_past();
+            if block.header.time <= mtp {
+                return Err(BlockError::TimeTooOld);
+            }
+            if block.header.time > Utc::now().timestamp() + chain.params.max_time_drift {
+                return Err(BlockError::TimeTooNew);
+            }
+
+            for tx in block.tranxs.iter().skip(1) {
+                tx.check_final(height, mtp)
+                  .map_err(|err| BlockError::Tx { hash: tx.hash.clone(), err })?;
+            }
+
+            let mut state = chain.state.clone();
+            match params.last_checkpoint() {
+                Some(last) if height <= last => state.apply_checkpointed(&block)?,
+                _ => state.apply_block(&block)?,
+            }
+            state
+        };
+
+        // 加锁顺序与其他方法一致：先链后交易池，最后手续费统计
+        let mut chain = self.chain.write().unwrap();
+        let confirmed = self.mempool.lock().unwrap().remove_block(&block, &state);
+        chain.add_block(block, state);
+
+        let mut fees = self.fees.lock().unwrap();
+        fees.process_block(chain.curr_height, &confirmed);
+        if let Err(e) = fees.save(&chain.params.save_dir) {
+            println!("failed to save fee estimates: {}", e);
+        }
+
+        Ok(())
+    }
+
+    pub fn params(&self) -> NetworkParams {
+        let chain = self.chain.read().unwrap();
+        chain.params.clone()
+    }
+}
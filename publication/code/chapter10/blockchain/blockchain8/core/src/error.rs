@@ -0,0 +1,230 @@
+use std::error::Error;
+use std::fmt;
+
+// 交易校验失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxError {
+    InsufficientFunds { need: u64, have: u64 },
+    Overflow,
+    BadNonce { expected: u64, got: u64 },
+    BadSignature,
+    SelfTransfer,
+    ZeroAmount,
+    Duplicate,
+    NotFinal { lock_time: u64 },
+    TooLarge { size: usize, max: usize },
+    FeeTooLow { fee: u64, min: u64 },
+    ReplacementFeeTooLow { fee: u64, min: u64 },
+    TooManyReplacements { max: u32 },
+    NothingToReplace,
+}
+
+impl fmt::Display for TxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TxError::InsufficientFunds { need, have } =>
+                write!(f, "not enough amount: need {}, have {}", need, have),
+            TxError::Overflow => write!(f, "amount overflow"),
+            TxError::BadNonce { expected, got } =>
+                write!(f, "bad nonce: expected {}, got {}", expected, got),
+            TxError::BadSignature => write!(f, "bad signature"),
+            TxError::SelfTransfer => write!(f, "transfer to self"),
+            TxError::ZeroAmount => write!(f, "zero amount"),
+            TxError::Duplicate => write!(f, "duplicate transaction"),
+            TxError::NotFinal { lock_time } =>
+                write!(f, "transaction locked until {}", lock_time),
+            TxError::TooLarge { size, max } =>
+                write!(f, "transaction size {} exceeds {}", size, max),
+            TxError::FeeTooLow { fee, min } =>
+                write!(f, "fee {} below minimum relay fee {}", fee, min),
+            TxError::ReplacementFeeTooLow { fee, min } =>
+                write!(f, "replacement fee {} below required {}", fee, min),
+            TxError::TooManyReplacements { max } =>
+                write!(f, "transaction already replaced {} times", max),
+            TxError::NothingToReplace => write!(f, "no pending transaction to replace"),
+        }
+    }
+}
+
+impl Error for TxError {}
+
+// 区块校验失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockError {
+    Stale,
+    BadProofOfWork,
+    BadBits { expected: u32, got: u32 },
+    BadMerkleRoot,
+    BadCoinbase,
+    TimeTooOld,
+    TimeTooNew,
+    TooHeavy { weight: usize, max: usize },
+    TooManyTxs { count: usize, max: usize },
+    Tx { hash: String, err: TxError },
+    CheckpointMismatch { height: u64 },
+    ForkBeforeCheckpoint { height: u64 },
+}
+
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BlockError::Stale => write!(f, "block does not extend the tip"),
+            BlockError::BadProofOfWork => write!(f, "invalid proof of work"),
+            BlockError::BadBits { expected, got } =>
+                write!(f, "bad difficulty bits: expected {:#010x}, got {:#010x}", expected, got),
+            BlockError::BadMerkleRoot => write!(f, "merkle root mismatch"),
+            BlockError::BadCoinbase => write!(f, "invalid coinbase"),
+            BlockError::TimeTooOld => write!(f, "block time not after median time past"),
+            BlockError::TimeTooNew => write!(f, "block time too far in the future"),
+            BlockError::TooHeavy { weight, max } =>
+                write!(f, "block weight {} exceeds {}", weight, max),
+            BlockError::TooManyTxs { count, max } =>
+                write!(f, "block has {} transactions, max {}", count, max),
+            BlockError::Tx { hash, err } => write!(f, "transaction {}: {}", hash, err),
+            BlockError::CheckpointMismatch { height } =>
+                write!(f, "block at height {} does not match checkpoint", height),
+            BlockError::ForkBeforeCheckpoint { height } =>
+                write!(f, "fork at height {} is below the last checkpoint", height),
+        }
+    }
+}
+
+impl Error for BlockError {}
+
+// 读取链数据用于查看时的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InspectError {
+    NoChain,
+    MissingHeader { hash: String },
+    MissingBlock { height: u64, hash: String },
+    BadRange { from: u64, to: u64, tip: u64 },
+}
+
+impl fmt::Display for InspectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InspectError::NoChain => write!(f, "no chain found in database"),
+            InspectError::MissingHeader { hash } => write!(f, "missing header {}", hash),
+            InspectError::MissingBlock { height, hash } =>
+                write!(f, "missing block {} at height {}", hash, height),
+            InspectError::BadRange { from, to, tip } =>
+                write!(f, "invalid range {}..={}, chain tip is {}", from, to, tip),
+        }
+    }
+}
+
+impl Error for InspectError {}
+
+// 助记词和密钥派生的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalletError {
+    UnknownWord(String),
+    BadLength(usize),
+    BadEntropy(usize),
+    BadChecksum,
+    BadPath(String),
+    InvalidKey,
+}
+
+impl fmt::Display for WalletError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WalletError::UnknownWord(word) => write!(f, "unknown mnemonic word `{}`", word),
+            WalletError::BadLength(n) => write!(f, "mnemonic must have 12 to 24 words, got {}", n),
+            WalletError::BadEntropy(n) => write!(f, "entropy must be 16 to 32 bytes, got {}", n),
+            WalletError::BadChecksum => write!(f, "mnemonic checksum mismatch"),
+            WalletError::BadPath(path) => write!(f, "invalid derivation path `{}`", path),
+            WalletError::InvalidKey => write!(f, "derived key is invalid"),
+        }
+    }
+}
+
+impl Error for WalletError {}
+
+// 对等节点被拒绝或消息被丢弃的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PeerError {
+    TooManyPeers,
+    UnknownPeer,
+    Banned { until: i64 },
+    MessageTooLarge { size: usize, max: usize },
+    RateLimited,
+    Block(BlockError),
+    Tx(TxError),
+}
+
+impl fmt::Display for PeerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PeerError::TooManyPeers => write!(f, "too many peers"),
+            PeerError::UnknownPeer => write!(f, "peer is not connected"),
+            PeerError::Banned { until } => write!(f, "peer is banned until {}", until),
+            PeerError::MessageTooLarge { size, max } =>
+                write!(f, "message of {} bytes exceeds {}", size, max),
+            PeerError::RateLimited => write!(f, "peer exceeded message rate"),
+            PeerError::Block(err) => write!(f, "invalid block: {}", err),
+            PeerError::Tx(err) => write!(f, "invalid transaction: {}", err),
+        }
+    }
+}
+
+impl Error for PeerError {}
+
+// 轻节点同步或全节点响应轻节点时的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpvError {
+    BadMessage(String),
+    BadBits { expected: u32, got: u32 },
+    BadProofOfWork,
+    ForkBelowCheckpoint { hash: String, height: u64 },
+    Disconnected { hash: String },
+    CheckpointMismatch { hash: String, height: u64 },
+    UnknownBlock { hash: String },
+    BadTxHash { hash: String },
+    BadMerkleProof { hash: String },
+    FilterTooLarge,
+    NoFilter,
+    UnexpectedMessage,
+}
+
+impl fmt::Display for SpvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpvError::BadMessage(err) => write!(f, "bad message: {}", err),
+            SpvError::BadBits { expected, got } =>
+                write!(f, "header bits {:#010x}, expected {:#010x}", got, expected),
+            SpvError::BadProofOfWork => write!(f, "header fails proof of work"),
+            SpvError::ForkBelowCheckpoint { hash, height } =>
+                write!(f, "header {} forks below checkpoint {}", hash, height),
+            SpvError::Disconnected { hash } => write!(f, "header {} does not connect to tip", hash),
+            SpvError::CheckpointMismatch { hash, height } =>
+                write!(f, "header {} does not match checkpoint {}", hash, height),
+            SpvError::UnknownBlock { hash } => write!(f, "unknown block {}", hash),
+            SpvError::BadTxHash { hash } => write!(f, "bad transaction hash {}", hash),
+            SpvError::BadMerkleProof { hash } => write!(f, "bad merkle proof for {}", hash),
+            SpvError::FilterTooLarge => write!(f, "filter too large"),
+            SpvError::NoFilter => write!(f, "no filter loaded"),
+            SpvError::UnexpectedMessage => write!(f, "unexpected message"),
+        }
+    }
+}
+
+impl Error for SpvError {}
+
+// 矿池参数不合法的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PoolError {
+    ShareBitsTooHard { share_bits: u32, block_bits: u32 },
+}
+
+impl fmt::Display for PoolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PoolError::ShareBitsTooHard { share_bits, block_bits } =>
+                write!(f, "share bits {:#010x} not easier than block bits {:#010x}",
+                       share_bits, block_bits),
+        }
+    }
+}
+
+impl Error for PoolError {}
@@ -0,0 +1,380 @@
+use std::fmt::Write as FmtWrite;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::thread;
+use crate::block::Block;
+use crate::chain::ChainHandle;
+use crate::transaction::Transaction;
+
+// 首页展示的最新区块数
+const LATEST_BLOCKS: usize = 20;
+// 请求行和每行请求头的最大字节数，以及请求头的最大行数
+const MAX_LINE: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub location: Option<String>, // 重定向目标
+    pub body: String,
+}
+
+impl Response {
+    fn page(title: &str, content: &str) -> Self {
+        let body = format!(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title></head><body>\n\
+             <p><a href=\"/\">home</a></p>\n\
+             <form action=\"/search\"><input name=\"q\" size=\"70\" \
+             placeholder=\"block hash, height, tx hash or address\"> \
+             <button>search</button></form>\n<h1>{}</h1>\n{}</body></html>\n",
+            escape(title), escape(title), content);
+        Response { status: 200, location: None, body }
+    }
+
+    fn not_found(what: &str) -> Self {
+        let mut resp = Self::page("Not Found", &format!("<p>{}</p>\n", escape(what)));
+        resp.status = 404;
+        resp
+    }
+
+    fn redirect(location: String) -> Self {
+        Response { status: 302, location: Some(location), body: String::new() }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            302 => "Found",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+// 区块浏览器：由节点内嵌的 HTTP 服务，服务端渲染 HTML，只读访问链数据
+#[derive(Clone)]
+pub struct Explorer {
+    chain: ChainHandle,
+}
+
+impl Explorer {
+    pub fn new(chain: ChainHandle) -> Self {
+        Explorer { chain }
+    }
+
+    // 只监听本机回环地址，不对外暴露
+    pub fn bind(port: u16) -> io::Result<TcpListener> {
+        TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+    }
+
+    pub fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let explorer = self.clone();
+            thread::spawn(move || {
+                if let Err(e) = explorer.serve_conn(stream) {
+                    println!("explorer connection closed: {}", e);
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    // 每个连接只处理一个请求，应答后关闭
+    fn serve_conn(&self, stream: TcpStream) -> io::Result<()> {
+        let mut writer = stream.try_clone()?;
+        let mut reader = BufReader::new(stream);
+        let request_line = Self::read_request(&mut reader)?.unwrap_or_default();
+
+        let mut parts = request_line.split_whitespace();
+        let resp = match (parts.next(), parts.next()) {
+            (Some("GET"), Some(target)) => self.route(target),
+            (Some(_), Some(_)) => Response { status: 405, location: None, body: String::new() },
+            _ => Response { status: 400, location: None, body: String::new() },
+        };
+
+        write!(writer, "HTTP/1.1 {} {}\r\n", resp.status, resp.reason())?;
+        if let Some(location) = &resp.location {
+            write!(writer, "Location: {}\r\n", location)?;
+        }
+        write!(writer, "Content-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\n\
+                        Connection: close\r\n\r\n{}", resp.body.len(), resp.body)?;
+        writer.flush()
+    }
+
+    // 读取请求行并跳过请求头，超出长度或行数限制时返回 None
+    pub fn read_request<R: BufRead>(reader: &mut R) -> io::Result<Option<String>> {
+        let mut request_line = String::new();
+        if !read_line(reader, &mut request_line)? {
+            return Ok(None);
+        }
+        for _ in 0..MAX_HEADERS {
+            let mut line = String::new();
+            if !read_line(reader, &mut line)? {
+                return Ok(None);
+            }
+            if line.trim().is_empty() {
+                return Ok(Some(request_line));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // 按请求路径分发到各页面
+    pub fn route(&self, target: &str) -> Response {
+        let (path, query) = match target.find('?') {
+            Some(i) => (&target[..i], &target[i + 1..]),
+            None => (target, ""),
+        };
+        let segments: Vec<String> = path.split('/')
+                                        .filter(|s| !s.is_empty())
+                                        .map(percent_decode)
+                                        .collect();
+
+        match segments.iter().map(|s| s.as_str()).collect::<Vec<&str>>().as_slice() {
+            [] => self.home(),
+            ["block", id] => self.block_page(id),
+            ["tx", hash] => self.tx_page(hash),
+            ["address", addr] => self.address_page(addr),
+            ["search"] => self.search(&query_param(query, "q").unwrap_or_default()),
+            _ => Response::not_found(&format!("no page at {}", path)),
+        }
+    }
+
+    // 主链上现有完整数据的区块及其高度，修剪掉的区块不包含在内
+    fn chain_blocks(&self) -> Vec<(u64, Block)> {
+        let (tip, blocks) = self.chain.tip_blocks();
+        let first = (tip + 1).saturating_sub(blocks.len() as u64);
+        (first..).zip(blocks).collect()
+    }
+
+    fn find_tx(&self, hash: &str) -> Option<(Transaction, Option<(u64, String)>)> {
+        for (height, block) in self.chain_blocks() {
+            if let Some(tx) = block.tranxs.iter().find(|tx| tx.hash == hash) {
+                return Some((tx.clone(), Some((height, block.hash.clone()))));
+            }
+        }
+        self.chain.mempool_txs()
+                  .into_iter()
+                  .find(|tx| tx.hash == hash)
+                  .map(|tx| (tx, None))
+    }
+
+    // 数字视为高度，64 位十六进制先按区块再按交易查找，其余视为地址
+    fn search(&self, q: &str) -> Response {
+        let q = q.trim();
+        if q.is_empty() {
+            return Response::redirect("/".to_string());
+        }
+
+        if q.chars().all(|c| c.is_ascii_digit()) {
+            if let Some(hash) = q.parse().ok().and_then(|h| self.chain.hash_at(h)) {
+                return Response::redirect(format!("/block/{}", hash));
+            }
+        } else if q.len() == 64 && q.chars().all(|c| c.is_ascii_hexdigit()) {
+            if self.chain.height_of(q).is_some() {
+                return Response::redirect(format!("/block/{}", q));
+            }
+            if self.find_tx(q).is_some() {
+                return Response::redirect(format!("/tx/{}", q));
+            }
+        } else {
+            return Response::redirect(format!("/address/{}", percent_encode(q)));
+        }
+
+        Response::not_found(&format!("nothing matches `{}`", q))
+    }
+
+    fn home(&self) -> Response {
+        let (tip, height) = self.chain.tip();
+        let mut html = String::new();
+        writeln!(html, "<p>network: {}, height: {}, tip: {}, mempool: {} txs</p>",
+                 escape(self.chain.params().name), height, block_link(&tip),
+                 self.chain.mempool_txs().len()).unwrap();
+        html.push_str("<h2>Latest blocks</h2>\n<table border=\"1\">\n\
+                       <tr><th>height</th><th>hash</th><th>time</th><th>txs</th></tr>\n");
+        for (height, block) in self.chain_blocks().iter().rev().take(LATEST_BLOCKS) {
+            writeln!(html, "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                     height, block_link(&block.hash), block.header.time, block.tranxs.len()).unwrap();
+        }
+        html.push_str("</table>\n");
+
+        Response::page("Block Explorer", &html)
+    }
+
+    fn block_page(&self, id: &str) -> Response {
+        let hash = match id.parse::<u64>() {
+            Ok(height) => self.chain.hash_at(height),
+            Err(_) => Some(id.to_string()),
+        };
+        let height = hash.as_ref().and_then(|h| self.chain.height_of(h));
+        let (hash, height) = match (hash, height) {
+            (Some(hash), Some(height)) => (hash, height),
+            _ => return Response::not_found(&format!("unknown block {}", id)),
+        };
+        let block = match self.chain.block(&hash) {
+            Some(block) => block,
+            None => return Response::not_found(&format!("block {} has been pruned", hash)),
+        };
+
+        let header = &block.header;
+        let merkle_ok = block.merkle_root(self.chain.params().hasher) == header.txs_hash;
+        let mut html = String::new();
+        html.push_str("<table border=\"1\">\n");
+        writeln!(html, "<tr><th>height</th><td>{}</td></tr>", height).unwrap();
+        writeln!(html, "<tr><th>hash</th><td>{}</td></tr>", escape(&block.hash)).unwrap();
+        writeln!(html, "<tr><th>previous</th><td>{}</td></tr>", block_link(&header.pre_hash)).unwrap();
+        writeln!(html, "<tr><th>merkle root</th><td>{} ({})</td></tr>", escape(&header.txs_hash),
+                 if merkle_ok { "valid" } else { "mismatch" }).unwrap();
+        writeln!(html, "<tr><th>time</th><td>{}</td></tr>", header.time).unwrap();
+        writeln!(html, "<tr><th>bits</th><td>{:#x}</td></tr>", header.bits).unwrap();
+        writeln!(html, "<tr><th>nonce</th><td>{}</td></tr>", header.nonce).unwrap();
+        html.push_str("</table>\n");
+        writeln!(html, "<h2>Transactions ({})</h2>", block.tranxs.len()).unwrap();
+        tx_table(&mut html, &block.tranxs);
+
+        Response::page(&format!("Block {}", height), &html)
+    }
+
+    fn tx_page(&self, hash: &str) -> Response {
+        let (tx, location) = match self.find_tx(hash) {
+            Some(found) => found,
+            None => return Response::not_found(&format!("unknown transaction {}", hash)),
+        };
+
+        let status = match location {
+            Some((height, block)) => format!("confirmed in block {} at height {}",
+                                             block_link(&block), height),
+            None => "unconfirmed (in mempool)".to_string(),
+        };
+        let mut html = String::new();
+        html.push_str("<table border=\"1\">\n");
+        writeln!(html, "<tr><th>hash</th><td>{}</td></tr>", escape(&tx.hash)).unwrap();
+        writeln!(html, "<tr><th>status</th><td>{}</td></tr>", status).unwrap();
+        writeln!(html, "<tr><th>from</th><td>{}</td></tr>", address_link(&tx.from)).unwrap();
+        writeln!(html, "<tr><th>to</th><td>{}</td></tr>", address_link(&tx.to)).unwrap();
+        writeln!(html, "<tr><th>amount</th><td>{}</td></tr>", tx.amount).unwrap();
+        writeln!(html, "<tr><th>fee</th><td>{}</td></tr>", tx.fee).unwrap();
+        writeln!(html, "<tr><th>nonce</th><td>{}</td></tr>", tx.nonce).unwrap();
+        writeln!(html, "<tr><th>lock time</th><td>{}</td></tr>", tx.lock_time).unwrap();
+        writeln!(html, "<tr><th>size</th><td>{}</td></tr>", tx.size()).unwrap();
+        html.push_str("</table>\n");
+
+        Response::page("Transaction", &html)
+    }
+
+    fn address_page(&self, addr: &str) -> Response {
+        let mut history = Vec::new();
+        for (height, block) in self.chain_blocks().into_iter().rev() {
+            for tx in block.tranxs.into_iter().filter(|tx| tx.from == addr || tx.to == addr) {
+                history.push((height, tx));
+            }
+        }
+
+        let mut html = String::new();
+        writeln!(html, "<p>balance: {}, nonce: {}</p>",
+                 self.chain.balance(addr), self.chain.nonce(addr)).unwrap();
+        writeln!(html, "<h2>History ({} txs)</h2>", history.len()).unwrap();
+        html.push_str("<table border=\"1\">\n\
+                       <tr><th>height</th><th>hash</th><th>from</th><th>to</th>\
+                       <th>amount</th><th>fee</th></tr>\n");
+        for (height, tx) in history.iter() {
+            writeln!(html, "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                     height, tx_link(&tx.hash), address_link(&tx.from), address_link(&tx.to),
+                     tx.amount, tx.fee).unwrap();
+        }
+        html.push_str("</table>\n");
+
+        Response::page(&format!("Address {}", addr), &html)
+    }
+}
+
+fn tx_table(html: &mut String, txs: &[Transaction]) {
+    html.push_str("<table border=\"1\">\n\
+                   <tr><th>hash</th><th>from</th><th>to</th><th>amount</th><th>fee</th></tr>\n");
+    for tx in txs {
+        writeln!(html, "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                 tx_link(&tx.hash), address_link(&tx.from), address_link(&tx.to),
+                 tx.amount, tx.fee).unwrap();
+    }
+    html.push_str("</table>\n");
+}
+
+fn block_link(hash: &str) -> String {
+    format!("<a href=\"/block/{}\">{}</a>", percent_encode(hash), escape(hash))
+}
+
+fn tx_link(hash: &str) -> String {
+    format!("<a href=\"/tx/{}\">{}</a>", percent_encode(hash), escape(hash))
+}
+
+fn address_link(addr: &str) -> String {
+    format!("<a href=\"/address/{}\">{}</a>", percent_encode(addr), escape(addr))
+}
+
+// 所有来自链上或请求的字符串输出前都要转义
+pub fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut out = String::new();
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) {
+            out.push(b as char);
+        } else {
+            write!(out, "%{:02X}", b).unwrap();
+        }
+    }
+    out
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3)
+                       .and_then(|h| std::str::from_utf8(h).ok())
+                       .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            },
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            },
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query.split('&')
+         .filter_map(|pair| pair.split_once('='))
+         .find(|(k, _)| *k == name)
+         .map(|(_, v)| percent_decode(&v.replace('+', " ")))
+}
+
+// 最多读取 MAX_LINE 字节，读满仍未遇到换行即视为超长
+fn read_line<R: BufRead>(reader: &mut R, line: &mut String) -> io::Result<bool> {
+    let n = reader.by_ref().take(MAX_LINE).read_line(line)?;
+    Ok(n == 0 || line.ends_with('\n'))
+}
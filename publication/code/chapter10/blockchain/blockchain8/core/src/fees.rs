@@ -0,0 +1,189 @@
+use std::{env, fs};
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use utils::serializer::{deserialize, serialize};
+
+const FEE_FILE: &str = "fee_estimates.dat";
+pub const MAX_TARGET: usize = 25; // 最多估算 25 个区块内确认所需的费率
+const MIN_FEE_RATE: f64 = 1.0;
+const MAX_FEE_RATE: f64 = 1e7;
+const BUCKET_SPACING: f64 = 1.5;
+const MIN_SAMPLES: f64 = 1.0;
+
+// 保守模式看得更久并要求更高成功率，经济模式只看最近的区块
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EstimateMode {
+    Conservative,
+    Economical,
+}
+
+// 按费率分桶统计：confirmed[t][b] 为 b 号桶中在 t+1 个区块内确认的交易数
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Stats {
+    decay: f64,
+    confirmed: Vec<Vec<f64>>,
+    total: Vec<f64>,
+}
+
+impl Stats {
+    fn new(decay: f64, buckets: usize) -> Self {
+        Stats {
+            decay,
+            confirmed: vec![vec![0f64; buckets]; MAX_TARGET],
+            total: vec![0f64; buckets],
+        }
+    }
+
+    // 旧数据按指数衰减，越近的区块权重越大
+    fn decay(&mut self) {
+        let decay = self.decay;
+        for row in self.confirmed.iter_mut() {
+            row.iter_mut().for_each(|v| *v *= decay);
+        }
+        self.total.iter_mut().for_each(|v| *v *= decay);
+    }
+
+    fn record(&mut self, bucket: usize, waited: usize) {
+        for t in waited.max(1)..=MAX_TARGET {
+            self.confirmed[t - 1][bucket] += 1f64;
+        }
+        self.total[bucket] += 1f64;
+    }
+
+    // 从高费率桶往低费率桶扫描，样本不足的桶与相邻的低费率桶合并，
+    // 返回成功率达到 threshold 的最低桶
+    fn estimate(&self, target: usize, threshold: f64, failed: &[f64]) -> Option<usize> {
+        let mut best = None;
+        let (mut conf, mut total) = (0f64, 0f64);
+        for b in (0..self.total.len()).rev() {
+            conf += self.confirmed[target - 1][b];
+            total += self.total[b] + failed[b];
+            if total < MIN_SAMPLES {
+                continue;
+            }
+
+            if conf / total >= threshold {
+                best = Some(b);
+                conf = 0f64;
+                total = 0f64;
+            } else {
+                break;
+            }
+        }
+
+        best
+    }
+}
+
+// 手续费估算：统计近期区块中各费率交易在交易池中等待的区块数
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FeeEstimator {
+    buckets: Vec<f64>,
+    short: Stats,
+    long: Stats,
+    best_height: u64,
+}
+
+impl Default for FeeEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FeeEstimator {
+    pub fn new() -> Self {
+        let mut buckets = Vec::new();
+        let mut rate = MIN_FEE_RATE;
+        while rate <= MAX_FEE_RATE {
+            buckets.push(rate);
+            rate *= BUCKET_SPACING;
+        }
+
+        let count = buckets.len();
+        FeeEstimator {
+            buckets,
+            short: Stats::new(0.9, count),
+            long: Stats::new(0.995, count),
+            best_height: 0,
+        }
+    }
+
+    fn path(save_dir: &str) -> PathBuf {
+        let mut dir = env::current_dir().unwrap();
+        dir.push(save_dir);
+        dir.push(FEE_FILE);
+        dir
+    }
+
+    // 读取上次保存的统计数据，没有或损坏时重新开始
+    // 节点重启后链从创世区块重新开始，保存的高度可能高于当前高度，
+    // 统计数据只记录等待的区块数，与高度无关，保留统计并把高度退回当前高度
+    pub fn load(save_dir: &str, tip_height: u64) -> Self {
+        let mut fees: Self = fs::read(Self::path(save_dir))
+            .ok()
+            .and_then(|bytes| deserialize(&bytes).ok())
+            .unwrap_or_default();
+        fees.best_height = fees.best_height.min(tip_height);
+        fees
+    }
+
+    pub fn save(&self, save_dir: &str) -> std::io::Result<()> {
+        fs::write(Self::path(save_dir), serialize(self))
+    }
+
+    fn bucket(&self, fee_rate: u64) -> usize {
+        let rate = fee_rate as f64;
+        self.buckets.iter()
+                    .rposition(|&b| b <= rate)
+                    .unwrap_or(0)
+    }
+
+    // confirmed 为新区块中来自交易池的交易 (手续费率, 进池高度)
+    pub fn process_block(&mut self, height: u64, confirmed: &[(u64, u64)]) {
+        if height <= self.best_height {
+            return;
+        }
+        self.best_height = height;
+
+        self.short.decay();
+        self.long.decay();
+        for &(fee_rate, entry_height) in confirmed {
+            let waited = height.saturating_sub(entry_height) as usize;
+            if waited > MAX_TARGET {
+                continue;
+            }
+
+            let bucket = self.bucket(fee_rate);
+            self.short.record(bucket, waited);
+            self.long.record(bucket, waited);
+        }
+    }
+
+    // 在 target 个区块内确认所需的费率(每 1000 字节)
+    // pending 为交易池中尚未确认交易的 (手续费率, 进池高度)，等待超过 target 的计为失败
+    pub fn estimate(&self, target: usize, mode: EstimateMode, pending: &[(u64, u64)])
+        -> Option<u64>
+    {
+        let target = target.clamp(1, MAX_TARGET);
+        let mut failed = vec![0f64; self.buckets.len()];
+        for &(fee_rate, entry_height) in pending {
+            if self.best_height.saturating_sub(entry_height) >= target as u64 {
+                failed[self.bucket(fee_rate)] += 1f64;
+            }
+        }
+
+        let bucket = match mode {
+            EstimateMode::Economical => self.short.estimate(target, 0.85, &failed),
+            EstimateMode::Conservative => {
+                let short = self.short.estimate(target, 0.95, &failed);
+                let long = self.long.estimate(target, 0.95, &failed);
+                match (short, long) {
+                    (Some(s), Some(l)) => Some(s.max(l)),
+                    _ => None,
+                }
+            },
+        };
+
+        bucket.map(|b| self.buckets[b].ceil() as u64)
+    }
+}
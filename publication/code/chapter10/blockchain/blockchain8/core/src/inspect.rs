@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Write;
+use std::str::FromStr;
+use serde::Serialize;
+use crate::bcdb::BlockChainDb;
+use crate::block::{Block, BlockHeader};
+use crate::blockchain::BlockChain;
+use crate::error::InspectError;
+use crate::params::NetworkParams;
+use crate::transaction::Transaction;
+
+const TOP_ADDRESSES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!("unknown format `{}`, expected table, json or csv", s)),
+        }
+    }
+}
+
+// 高度区间为闭区间，缺省时分别为创世区块和链尾
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub address: Option<String>,
+}
+
+// 主链上的一个区块；修剪后 block 为 None，只剩区块头
+#[derive(Debug, Clone)]
+pub struct BlockRecord {
+    pub height: u64,
+    pub hash: String,
+    pub header: BlockHeader,
+    pub block: Option<Block>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ChainStats {
+    pub blocks: usize,
+    pub txs: usize,
+    pub avg_block_time: Option<f64>,
+    pub difficulty: Vec<(u64, u32)>, // 难度变化的 (高度, bits)
+    pub fees: Vec<(u64, u64)>,       // 每个区块的 (高度, 手续费)
+    pub top_addresses: Vec<(String, u64)>, // 按转入转出总额排序
+}
+
+#[derive(Serialize)]
+struct BlockView<'a> {
+    height: u64,
+    hash: &'a str,
+    time: i64,
+    bits: u32,
+    nonce: u32,
+    pre_hash: &'a str,
+    txs_hash: &'a str,
+    fees: u64,
+    txs: Vec<&'a Transaction>,
+}
+
+// 区块链查看工具：按高度区间和地址过滤区块，并输出统计信息
+pub struct ChainInspector {
+    records: Vec<BlockRecord>,
+}
+
+impl ChainInspector {
+    // 从链尾沿区块头回溯到创世区块(其前一哈希为 pre_hash)，不修改数据库
+    pub fn load(params: &NetworkParams) -> Result<Self, InspectError> {
+        let mut dir = env::current_dir().unwrap();
+        dir.push(&params.save_dir);
+        if !dir.exists() {
+            return Err(InspectError::NoChain);
+        }
+
+        let db = BlockChainDb::open(&params.save_dir);
+        let mut hash = BlockChain::read_tail(&db).ok_or(InspectError::NoChain)?;
+        let mut records = Vec::new();
+        loop {
+            let header = BlockChain::read_header(&db, &hash, params.hasher)
+                .ok_or_else(|| InspectError::MissingHeader { hash: hash.clone() })?;
+            let block = BlockChain::read_block(&db, &hash);
+            let pre_hash = header.pre_hash.clone();
+            records.push(BlockRecord { height: 0, hash, header, block });
+            if pre_hash == params.pre_hash {
+                break;
+            }
+            hash = pre_hash;
+        }
+
+        records.reverse();
+        for (height, record) in records.iter_mut().enumerate() {
+            record.height = height as u64;
+        }
+
+        Ok(ChainInspector { records })
+    }
+
+    pub fn records(&self) -> &[BlockRecord] {
+        &self.records
+    }
+
+    pub fn tip_height(&self) -> u64 {
+        self.records.len().saturating_sub(1) as u64
+    }
+
+    // 区间内的区块必须都有完整数据，缺失时报错
+    pub fn select(&self, query: &Query) -> Result<Vec<(u64, &Block)>, InspectError> {
+        let tip = self.tip_height();
+        let from = query.from.unwrap_or(0);
+        let to = query.to.unwrap_or(tip);
+        if self.records.is_empty() || from > to || to > tip {
+            return Err(InspectError::BadRange { from, to, tip });
+        }
+
+        self.records[from as usize..=to as usize]
+            .iter()
+            .map(|r| match &r.block {
+                Some(block) => Ok((r.height, block)),
+                None => Err(InspectError::MissingBlock { height: r.height, hash: r.hash.clone() }),
+            })
+            .collect()
+    }
+
+    // 指定地址时只输出涉及该地址的交易及其所在区块
+    pub fn render(blocks: &[(u64, &Block)], address: Option<&str>, format: Format) -> String {
+        let views: Vec<BlockView> = blocks.iter()
+                                          .map(|(height, b)| Self::view(*height, b, address))
+                                          .filter(|v| address.is_none() || !v.txs.is_empty())
+                                          .collect();
+        let mut out = String::new();
+        match format {
+            Format::Table => {
+                writeln!(out, "{:>6}  {:<16}  {:>10}  {:>10}  {:>4}  {:>6}",
+                         "height", "hash", "time", "bits", "txs", "fees").unwrap();
+                for v in views.iter() {
+                    writeln!(out, "{:>6}  {:<16}  {:>10}  {:>#10x}  {:>4}  {:>6}",
+                             v.height, short(v.hash), v.time, v.bits, v.txs.len(), v.fees).unwrap();
+                    for tx in v.txs.iter() {
+                        writeln!(out, "        {:<16}  {} -> {}  amount {}  fee {}",
+                                 short(&tx.hash), tx.from, tx.to, tx.amount, tx.fee).unwrap();
+                    }
+                }
+            },
+            Format::Json => out = serde_json::to_string_pretty(&views).unwrap(),
+            Format::Csv => {
+                writeln!(out, "height,block_hash,time,bits,tx_hash,from,to,amount,fee").unwrap();
+                for v in views.iter() {
+                    for tx in v.txs.iter() {
+                        writeln!(out, "{},{},{},{},{},{},{},{},{}",
+                                 v.height, v.hash, v.time, v.bits, tx.hash,
+                                 csv_field(&tx.from), csv_field(&tx.to), tx.amount, tx.fee).unwrap();
+                    }
+                }
+            },
+        }
+
+        out
+    }
+
+    fn view<'a>(height: u64, block: &'a Block, address: Option<&str>) -> BlockView<'a> {
+        let txs = block.tranxs.iter()
+                              .filter(|tx| match address {
+                                  Some(addr) => tx.from == addr || tx.to == addr,
+                                  None => true,
+                              })
+                              .collect();
+        BlockView {
+            height,
+            hash: &block.hash,
+            time: block.header.time,
+            bits: block.header.bits,
+            nonce: block.header.nonce,
+            pre_hash: &block.header.pre_hash,
+            txs_hash: &block.header.txs_hash,
+            fees: block_fees(block),
+            txs,
+        }
+    }
+
+    pub fn stats(blocks: &[(u64, &Block)]) -> ChainStats {
+        let avg_block_time = match (blocks.first(), blocks.last()) {
+            (Some((_, first)), Some((_, last))) if blocks.len() > 1 => {
+                let span = last.header.time - first.header.time;
+                Some(span as f64 / (blocks.len() - 1) as f64)
+            },
+            _ => None,
+        };
+
+        let mut difficulty: Vec<(u64, u32)> = Vec::new();
+        let mut volumes: HashMap<&str, u64> = HashMap::new();
+        let mut txs = 0;
+        for (height, b) in blocks.iter() {
+            if difficulty.last().map(|(_, bits)| *bits) != Some(b.header.bits) {
+                difficulty.push((*height, b.header.bits));
+            }
+
+            // coinbase 不计入地址交易额
+            txs += b.tranxs.len();
+            for tx in b.tranxs.iter().skip(1) {
+                *volumes.entry(&tx.from).or_insert(0) += tx.amount;
+                *volumes.entry(&tx.to).or_insert(0) += tx.amount;
+            }
+        }
+
+        let mut top_addresses: Vec<(String, u64)> = volumes.into_iter()
+                                                           .map(|(a, v)| (a.to_string(), v))
+                                                           .collect();
+        top_addresses.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        top_addresses.truncate(TOP_ADDRESSES);
+
+        ChainStats {
+            blocks: blocks.len(),
+            txs,
+            avg_block_time,
+            difficulty,
+            fees: blocks.iter().map(|(height, b)| (*height, block_fees(b))).collect(),
+            top_addresses,
+        }
+    }
+
+    pub fn render_stats(stats: &ChainStats, format: Format) -> String {
+        let mut out = String::new();
+        match format {
+            Format::Table => {
+                writeln!(out, "blocks: {}", stats.blocks).unwrap();
+                writeln!(out, "transactions: {}", stats.txs).unwrap();
+                match stats.avg_block_time {
+                    Some(t) => writeln!(out, "average block time: {:.2}s", t).unwrap(),
+                    None => writeln!(out, "average block time: n/a").unwrap(),
+                }
+                writeln!(out, "difficulty history:").unwrap();
+                for (height, bits) in stats.difficulty.iter() {
+                    writeln!(out, "  {:>6}  {:#x}", height, bits).unwrap();
+                }
+                writeln!(out, "fees per block:").unwrap();
+                for (height, fee) in stats.fees.iter() {
+                    writeln!(out, "  {:>6}  {}", height, fee).unwrap();
+                }
+                writeln!(out, "top addresses by volume:").unwrap();
+                for (addr, volume) in stats.top_addresses.iter() {
+                    writeln!(out, "  {:<16}  {}", addr, volume).unwrap();
+                }
+            },
+            Format::Json => out = serde_json::to_string_pretty(stats).unwrap(),
+            Format::Csv => {
+                writeln!(out, "metric,key,value").unwrap();
+                writeln!(out, "blocks,,{}", stats.blocks).unwrap();
+                writeln!(out, "transactions,,{}", stats.txs).unwrap();
+                if let Some(t) = stats.avg_block_time {
+                    writeln!(out, "avg_block_time,,{:.2}", t).unwrap();
+                }
+                for (height, bits) in stats.difficulty.iter() {
+                    writeln!(out, "bits,{},{}", height, bits).unwrap();
+                }
+                for (height, fee) in stats.fees.iter() {
+                    writeln!(out, "fees,{},{}", height, fee).unwrap();
+                }
+                for (addr, volume) in stats.top_addresses.iter() {
+                    writeln!(out, "volume,{},{}", csv_field(addr), volume).unwrap();
+                }
+            },
+        }
+
+        out
+    }
+}
+
+fn block_fees(block: &Block) -> u64 {
+    block.tranxs.iter().skip(1).map(|tx| tx.fee).sum()
+}
+
+fn short(hash: &str) -> &str {
+    &hash[..hash.len().min(16)]
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -1,8 +1,854 @@
 pub mod account;
 pub mod bcdb;
 pub mod block;
+pub mod bloom;
 pub mod blockchain;
+pub mod chain;
+pub mod error;
+pub mod explorer;
+pub mod fees;
+pub mod inspect;
+pub mod mempool;
+pub mod merkle;
+pub mod message;
 pub mod mine;
 pub mod miner;
+pub mod params;
+pub mod peer;
+pub mod pool;
 pub mod pow;
+pub mod spv;
+pub mod state;
 pub mod transaction;
+pub mod wallet;
+pub mod wordlist;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use utils::hasher::HashAlgo;
+    use crate::account::Account;
+    use crate::block::{Block, BlockHeader};
+    use crate::blockchain::BlockChain;
+    use crate::bloom::BloomFilter;
+    use crate::chain::ChainHandle;
+    use crate::error::{BlockError, InspectError, PeerError, PoolError, SpvError, TxError,
+                       WalletError};
+    use crate::explorer::Explorer;
+    use crate::fees::{EstimateMode, FeeEstimator};
+    use crate::inspect::{ChainInspector, Format, Query};
+    use crate::mempool::{Mempool, MAX_REPLACEMENTS};
+    use crate::mine::Mine;
+    use crate::merkle::{merkle_proof, merkle_root};
+    use crate::message::{Message, MerkleBlock};
+    use crate::params::NetworkParams;
+    use crate::peer::{PeerConfig, PeerManager};
+    use crate::pool::{Pool, PoolEvent, PoolRequest, PoolResponse, PoolWorker, Pplns};
+    use crate::pow::ProofOfWork;
+    use crate::spv::LightClient;
+    use crate::state::State;
+    use crate::transaction::Transaction;
+    use crate::wallet::{AddressKind, DerivationPath, ExtendedKey, HdWallet, Mnemonic};
+
+    fn tx(from: &str, to: &str, amount: u64) -> Transaction {
+        Transaction::new(from.to_string(), to.to_string(), amount, 1, 1,
+                         "".to_string(), &NetworkParams::regtest())
+    }
+
+    fn transfer(from: &str, to: &str, amount: u64, fee: u64, nonce: u64) -> Transaction {
+        Transaction::new(from.to_string(), to.to_string(), amount, fee, nonce,
+                         "".to_string(), &NetworkParams::regtest())
+    }
+
+    fn regtest_params(name: &str) -> NetworkParams {
+        let mut params = NetworkParams::regtest();
+        let dir = std::env::temp_dir().join(format!("bc_test_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        params.save_dir = dir.to_str().unwrap().to_string();
+        params
+    }
+
+    fn regtest_mine(name: &str) -> Mine {
+        Mine::new(&regtest_params(name))
+    }
+
+    fn mined(txs: Vec<Transaction>, pre_hash: String) -> Block {
+        let params = NetworkParams::regtest();
+        let mut block = Block::new(txs, pre_hash, params.init_bits, params.hasher);
+        let pow = ProofOfWork::new(params.init_bits, params.hasher);
+        pow.run(&mut block);
+        block
+    }
+
+    #[test]
+    fn merkle_proofs_verify() {
+        let hasher = HashAlgo::Sha3_256;
+        for n in 1..8 {
+            let hashes: Vec<String> = (0..n).map(|i| format!("{:064x}", i)).collect();
+            let root = merkle_root(&hashes, hasher);
+            for (i, hash) in hashes.iter().enumerate() {
+                let proof = merkle_proof(&hashes, i, hasher).unwrap();
+                assert!(proof.verify(hash, &root, hasher));
+                assert!(!proof.verify("bad", &root, hasher));
+            }
+        }
+    }
+
+    #[test]
+    fn bloom_filter_round_trip() {
+        let mut bf = BloomFilter::new(10, 0.01, 7, HashAlgo::Sha3_256);
+        bf.insert("0xabcd");
+        let msg = Message::FilterLoad(bf).to_bytes();
+        match Message::from_bytes(&msg).unwrap() {
+            Message::FilterLoad(bf) => {
+                assert!(bf.contains("0xabcd"));
+                assert!(!bf.contains("0xabce"));
+            },
+            _ => panic!("wrong message"),
+        }
+    }
+
+    #[test]
+    fn light_client_syncs_headers_and_txs() {
+        let params = NetworkParams::regtest();
+        let hasher = params.hasher;
+        let genesis = BlockChain::genesis_block(&params);
+        let block1 = mined(vec![tx("0xabcd", "0xabce", 5), tx("0xabcf", "0x1b2d", 3),
+                                tx("0x1b2d", "0xabcd", 2)], genesis.hash.clone());
+
+        let mut filter = BloomFilter::new(10, 0.0001, 1, hasher);
+        filter.insert("0xabcd");
+        let mut client = LightClient::new(&params, filter.clone());
+
+        let bad = mined(vec![tx("0xabcd", "0xabce", 5)], "ff".repeat(32)).header;
+        assert!(matches!(client.add_headers(vec![bad]), Err(SpvError::Disconnected { .. })));
+
+        // 自选低难度的区块头链不被接受
+        let harder = NetworkParams { init_bits: 0x1F7FFFFF, ..NetworkParams::regtest() };
+        let mut strict = LightClient::new(&harder, filter.clone());
+        let easy = BlockHeader { pre_hash: strict.tip().0, ..block1.header.clone() };
+        assert_eq!(Err(SpvError::BadBits { expected: 0x1F7FFFFF, got: block1.header.bits }),
+                   strict.add_headers(vec![easy]));
+        assert_eq!(Ok(1), client.add_headers(vec![block1.header.clone()]));
+        assert_eq!(block1.hash, client.tip().0);
+
+        let merkle_block = MerkleBlock::from_block(&block1, &filter, hasher);
+        assert_eq!(2, merkle_block.matches.len());
+        assert_eq!(Ok(2), client.process_merkle_block(merkle_block.clone()));
+
+        let mut forged = merkle_block.clone();
+        forged.matches[0].tx.amount = 500;
+        assert!(matches!(client.process_merkle_block(forged), Err(SpvError::BadTxHash { .. })));
+
+        let mut replayed = merkle_block;
+        replayed.matches[0].tx.chain_id = NetworkParams::mainnet().chain_id;
+        replayed.matches[0].tx.set_hash(hasher);
+        assert!(client.process_merkle_block(replayed).is_err());
+    }
+
+    #[test]
+    fn chain_id_changes_tx_hash() {
+        let regtest = tx("0xabcd", "0xabce", 5);
+        let mainnet = Transaction::new("0xabcd".to_string(), "0xabce".to_string(), 5, 1, 1,
+                                       "".to_string(), &NetworkParams::mainnet());
+        assert_ne!(regtest.hash, mainnet.hash);
+    }
+
+    #[test]
+    fn genesis_is_deterministic() {
+        for params in [NetworkParams::mainnet(), NetworkParams::testnet(),
+                       NetworkParams::regtest()] {
+            let genesis1 = BlockChain::genesis_block(&params);
+            let genesis2 = BlockChain::genesis_block(&params);
+            assert_eq!(genesis1.hash, genesis2.hash);
+            assert_eq!(Some(genesis1.hash.as_str()), params.checkpoint(0));
+        }
+
+        // 各网络的难度和哈希算法互不相同
+        let (main, test, reg) = (NetworkParams::mainnet(), NetworkParams::testnet(),
+                                 NetworkParams::regtest());
+        assert!(main.init_bits != test.init_bits && test.init_bits != reg.init_bits
+                && main.init_bits != reg.init_bits);
+        assert!(main.hasher != test.hasher && test.hasher != reg.hasher && main.hasher != reg.hasher);
+    }
+
+    #[test]
+    fn mempool_rejects_invalid_txs() {
+        let state = State::new(&NetworkParams::regtest());
+        let mut pool = Mempool::new();
+
+        let huge = transfer("0xa", "0xb", 10, u64::MAX, 1);
+        assert_eq!(u64::MAX / huge.size() as u64, huge.fee_rate());
+
+        assert_eq!(Ok(()), pool.add(transfer("0xa", "0xb", 10, 1, 1), &state, 0));
+        assert_eq!(Ok(()), pool.add(transfer("0xa", "0xb", 10, 1, 2), &state, 0));
+        assert_eq!(Err(TxError::Duplicate), pool.add(transfer("0xa", "0xb", 10, 1, 2), &state, 0));
+        assert!(matches!(pool.add(transfer("0xa", "0xc", 5, 1, 2), &state, 0),
+                         Err(TxError::ReplacementFeeTooLow { fee: 1, .. })));
+        assert_eq!(Err(TxError::BadNonce { expected: 3, got: 5 }),
+                   pool.add(transfer("0xa", "0xb", 1, 1, 5), &state, 0));
+        assert_eq!(Err(TxError::InsufficientFunds { need: 80, have: 78 }),
+                   pool.add(transfer("0xa", "0xb", 79, 1, 3), &state, 0));
+        assert_eq!(Err(TxError::Overflow),
+                   pool.add(transfer("0xb", "0xa", u64::MAX, 1, 1), &state, 0));
+        assert_eq!(Err(TxError::SelfTransfer), pool.add(transfer("0xb", "0xb", 1, 1, 1), &state, 0));
+        assert_eq!(Err(TxError::ZeroAmount), pool.add(transfer("0xb", "0xa", 0, 1, 1), &state, 0));
+
+        let mut forged = transfer("0xb", "0xa", 1, 1, 1);
+        forged.amount = 2;
+        assert_eq!(Err(TxError::BadSignature), pool.add(forged, &state, 0));
+        assert_eq!(2, pool.len());
+    }
+
+    #[test]
+    fn mempool_replaces_by_fee() {
+        let params = NetworkParams::regtest();
+        let state = State::new(&params);
+        let mut pool = Mempool::new();
+        let first = transfer("0xa", "0xb", 10, 1, 1);
+        let second = transfer("0xa", "0xb", 80, 1, 2);
+        pool.add(first.clone(), &state, 0).unwrap();
+        pool.add(second.clone(), &state, 0).unwrap();
+
+        // 提高金额后第二笔交易余额不足，替换手续费须覆盖两笔
+        let bumped = transfer("0xa", "0xb", 19, 2, 1);
+        let min = Mempool::min_fee(&bumped, params.min_fee_per_byte).max(1) + 2;
+        assert_eq!(Err(TxError::ReplacementFeeTooLow { fee: 2, min }),
+                   pool.add(bumped, &state, 0));
+        let bumped = transfer("0xa", "0xb", 19, min, 1);
+        pool.add(bumped.clone(), &state, 0).unwrap();
+        assert_eq!(1, pool.len());
+        assert!(pool.contains(&bumped.hash) && !pool.contains(&second.hash));
+
+        let cancel = transfer("0xa", "0xa", 0, min + 2, 2);
+        assert_eq!(Err(TxError::NothingToReplace), pool.add(cancel, &state, 0));
+        let mut fee = min;
+        for _ in 1..MAX_REPLACEMENTS {
+            fee += 2;
+            pool.add(transfer("0xa", "0xa", 0, fee, 1), &state, 0).unwrap();
+        }
+        assert_eq!(Err(TxError::TooManyReplacements { max: MAX_REPLACEMENTS }),
+                   pool.add(transfer("0xa", "0xa", 0, fee + 2, 1), &state, 0));
+
+        let mut next = state.clone();
+        next.apply_tx(&pool.pending()[0]).unwrap();
+        assert_eq!(100 - fee, next.balance("0xa"));
+        assert_eq!(1, next.nonce("0xa"));
+    }
+
+    #[test]
+    fn accounts_bump_and_cancel() {
+        let mut mine = regtest_mine("rbf");
+        let chain = mine.chain.clone();
+        let params = chain.params();
+        let mut alice = Account::new("0xa".to_string(), "alice".to_string(), &params);
+        let mut bob = Account::new("0xb".to_string(), "bob".to_string(), &params);
+
+        let tx = alice.transfer_to(&mut bob, 10, 1).unwrap();
+        chain.submit_tx(tx.clone()).unwrap();
+        assert_eq!(Some(TxError::ReplacementFeeTooLow { fee: 1, min: 2 }),
+                   alice.bump_fee(&mut bob, &tx, 1).err());
+        let bumped = alice.bump_fee(&mut bob, &tx, 5).unwrap();
+        chain.submit_tx(bumped.clone()).unwrap();
+        assert_eq!(vec![bumped.hash.clone()],
+                   chain.mempool_txs().iter().map(|t| t.hash.clone()).collect::<Vec<_>>());
+
+        let cancel = alice.cancel(&mut bob, &bumped, 9).unwrap();
+        assert!(cancel.is_cancel());
+        chain.submit_tx(cancel).unwrap();
+        mine.mining().unwrap();
+        assert_eq!(91, chain.balance("0xa"));
+        assert_eq!(100, chain.balance("0xb"));
+        assert_eq!((91, 100, 1), (alice.balance, bob.balance, alice.nonce));
+    }
+
+    #[test]
+    fn chain_handle_mines_mempool() {
+        let mut mine = regtest_mine("mempool");
+        let chain: ChainHandle = mine.chain.clone();
+        chain.submit_tx(transfer("0xa", "0xb", 10, 2, 1)).unwrap();
+        chain.submit_tx(transfer("0xb", "0xc", 5, 1, 1)).unwrap();
+        mine.mining().unwrap();
+
+        let params = chain.params();
+        assert_eq!(1, chain.tip().1);
+        assert!(chain.mempool_txs().is_empty());
+        assert_eq!(88, chain.balance("0xa"));
+        assert_eq!(104, chain.balance("0xb"));
+        assert_eq!(params.init_balance + params.reward + 3, chain.balance(params.miner_address));
+        assert_eq!(1, chain.nonce("0xa"));
+    }
+
+    #[test]
+    fn blocks_must_use_chain_bits() {
+        let mut params = NetworkParams::mainnet();
+        params.init_bits = 0x1F7FFFFF;
+        params.save_dir = regtest_params("bits").save_dir;
+        let mine = Mine::new(&params);
+        let chain = mine.chain.clone();
+
+        // 区块自带的低难度不被接受，也不会改变链的难度
+        let easy = 0x2100FFFF;
+        let mut block = mine.miner.prepare_block(&chain.template());
+        block.header.bits = easy;
+        ProofOfWork::new(easy, params.hasher).run(&mut block);
+        assert_eq!(Err(BlockError::BadBits { expected: params.init_bits, got: easy }),
+                   chain.submit_block(block));
+        assert_eq!(params.init_bits, chain.template().bits);
+
+        let mut block = mine.miner.prepare_block(&chain.template());
+        ProofOfWork::new(params.init_bits, params.hasher).run(&mut block);
+        assert_eq!(Ok(()), chain.submit_block(block));
+        assert_eq!(params.init_bits, chain.template().bits);
+    }
+
+    #[test]
+    fn locked_tx_waits_in_mempool() {
+        let mut mine = regtest_mine("locktime");
+        let chain = mine.chain.clone();
+        let params = chain.params();
+        let locked = Transaction::new_locked("0xa".to_string(), "0xb".to_string(), 10, 1, 1,
+                                             "".to_string(), 2, &params);
+        chain.submit_tx(locked.clone()).unwrap();
+        chain.submit_tx(transfer("0xa", "0xb", 10, 1, 2)).unwrap();
+
+        mine.mining().unwrap();
+        assert_eq!(2, chain.mempool_txs().len());
+        assert_eq!(100, chain.balance("0xb"));
+
+        mine.mining().unwrap();
+        assert!(chain.mempool_txs().is_empty());
+        assert_eq!(120, chain.balance("0xb"));
+    }
+
+    #[test]
+    fn block_time_rules() {
+        let mut mine = regtest_mine("time");
+        let chain = mine.chain.clone();
+        let params = chain.params();
+        let pow = ProofOfWork::new(params.init_bits, params.hasher);
+
+        let mut block = mine.miner.mine_block(&chain.template());
+        block.header.time = params.genesis_time;
+        pow.run(&mut block);
+        assert_eq!(Err(BlockError::TimeTooOld), chain.submit_block(block.clone()));
+
+        block.header.time = chrono::Utc::now().timestamp() + params.max_time_drift + 60;
+        pow.run(&mut block);
+        assert_eq!(Err(BlockError::TimeTooNew), chain.submit_block(block));
+    }
+
+    #[test]
+    fn size_and_fee_limits() {
+        let mut params = NetworkParams::regtest();
+        params.max_tx_size = 200;
+        params.min_fee_per_byte = 1;
+        let state = State::new(&params);
+        let mut pool = Mempool::new();
+
+        let tx = transfer("0xa", "0xb", 1, 1, 1);
+        let min = tx.size() as u64;
+        assert_eq!(min, Mempool::min_fee(&tx, params.min_fee_per_byte));
+        assert_eq!(Err(TxError::FeeTooLow { fee: 1, min }), pool.add(tx, &state, 0));
+
+        let big = Transaction::new("0xa".to_string(), "0xb".to_string(), 1, 5, 1,
+                                   "x".repeat(300), &params);
+        let size = big.size();
+        assert_eq!(Err(TxError::TooLarge { size, max: 200 }), pool.add(big, &state, 0));
+
+        params.min_fee_per_byte = 0;
+        let state = State::new(&params);
+        for nonce in 1..=5 {
+            pool.add(transfer("0xa", "0xb", 1, 5, nonce), &state, 0).unwrap();
+        }
+        let size = transfer("0xa", "0xb", 1, 5, 1).size();
+        assert_eq!(3, pool.select(1, 0, size * 3, 100).len());
+        assert_eq!(2, pool.select(1, 0, size * 3, 2).len());
+    }
+
+    #[test]
+    fn fee_estimates_follow_confirmations() {
+        let mut fees = FeeEstimator::new();
+        assert_eq!(None, fees.estimate(1, EstimateMode::Economical, &[]));
+
+        // 高费率交易下一个区块即确认，低费率交易要等 10 个区块
+        for height in 11..=40 {
+            fees.process_block(height, &[(100, height - 1), (2, height - 10)]);
+        }
+        assert_eq!(Some(87), fees.estimate(1, EstimateMode::Economical, &[]));
+        assert_eq!(Some(87), fees.estimate(9, EstimateMode::Conservative, &[]));
+        assert_eq!(Some(2), fees.estimate(10, EstimateMode::Conservative, &[]));
+
+        // 池中久等未确认的低费率交易拉高估算
+        let stuck: Vec<(u64, u64)> = (0..30).map(|_| (2, 20)).collect();
+        assert_eq!(Some(87), fees.estimate(10, EstimateMode::Economical, &stuck));
+
+        let dir = std::env::temp_dir().join(format!("bc_test_fees_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir = dir.to_str().unwrap();
+        fees.save(dir).unwrap();
+        let mut loaded = FeeEstimator::load(dir, 40);
+        assert_eq!(Some(2), loaded.estimate(10, EstimateMode::Conservative, &[]));
+
+        // 重启后链从低处重新开始，新区块仍计入统计
+        let mut restarted = FeeEstimator::load(dir, 0);
+        for height in 1..=30 {
+            loaded.process_block(40 + height, &[(2, 40 + height - 1)]);
+            restarted.process_block(height, &[(2, height - 1)]);
+        }
+        assert_eq!(Some(2), loaded.estimate(1, EstimateMode::Economical, &[]));
+        assert_eq!(loaded.estimate(1, EstimateMode::Economical, &[]),
+                   restarted.estimate(1, EstimateMode::Economical, &[]));
+    }
+
+    #[test]
+    fn pruned_chain_keeps_headers() {
+        let mut params = regtest_params("prune");
+        params.prune_depth = Some(2);
+        let mut mine = Mine::new(&params);
+        for _ in 0..5 {
+            mine.mining().unwrap();
+        }
+
+        let chain = mine.chain.clone();
+        let (tip, height) = chain.tip();
+        assert_eq!(5, height);
+        assert_eq!(2, chain.blocks().len());
+        assert!(chain.block(&tip).is_some());
+        assert!(chain.block(&BlockChain::genesis_block(&params).hash).is_none());
+        assert_eq!(6, chain.headers_after(&[], "", 10).len());
+        assert_eq!(params.init_balance + 5 * params.reward, chain.balance(params.miner_address));
+        assert_eq!(chain.balance(params.miner_address), mine.miner.balance);
+    }
+
+    #[test]
+    fn checkpoints_skip_signatures_and_pin_chain() {
+        let genesis = BlockChain::genesis_block(&NetworkParams::regtest());
+        assert_eq!(Some(genesis.hash.as_str()), NetworkParams::regtest().checkpoint(0));
+
+        let mut forged = transfer("0xa", "0xb", 10, 1, 1);
+        forged.hash = "forged".to_string();
+        let coinbase = tx("0x0000", "0x3b2d", 50);
+        let block = mined(vec![coinbase.clone(), forged], genesis.hash.clone());
+
+        let plain = regtest_mine("nocheckpoint").chain;
+        let err = TxError::BadSignature;
+        assert_eq!(Err(BlockError::Tx { hash: "forged".to_string(), err }),
+                   plain.submit_block(block.clone()));
+
+        let hash: &'static str = Box::leak(block.hash.clone().into_boxed_str());
+        let checkpoints = vec![(0, NetworkParams::regtest().checkpoint(0).unwrap()), (1, hash)];
+        let mut params = regtest_params("checkpoint");
+        params.checkpoints = Box::leak(checkpoints.into_boxed_slice());
+        let other = mined(vec![coinbase, transfer("0xa", "0xb", 1, 1, 1)], genesis.hash);
+
+        let chain = Mine::new(&params).chain;
+        assert_eq!(Err(BlockError::CheckpointMismatch { height: 1 }),
+                   chain.submit_block(other.clone()));
+        assert_eq!(Ok(()), chain.submit_block(block));
+        assert_eq!(110, chain.balance("0xb"));
+        assert_eq!(Err(BlockError::ForkBeforeCheckpoint { height: 1 }),
+                   chain.submit_block(other));
+    }
+
+    // 测试网络：在回归测试网之上固定高度 1 的检查点，区块 1 由 pinned_block 确定地生成
+    const PINNED_CHECKPOINTS: &[(u64, &str)] = &[
+        (0, "1d809d57c84704e361561284bcba90f8673d6068869bb83e6aff5ab8134f3c43"),
+        (1, "3a535fa7a441974c41b4b1bde5904aabd73bd2a65741cdcff63201823ab0a435"),
+    ];
+
+    fn pinned_params(name: &str) -> NetworkParams {
+        let mut params = regtest_params(name);
+        params.checkpoints = PINNED_CHECKPOINTS;
+        params
+    }
+
+    // 时间和交易都固定，挖出的区块哈希每次相同
+    fn pinned_block() -> Block {
+        let params = NetworkParams::regtest();
+        let genesis = BlockChain::genesis_block(&params);
+        let coinbase = tx("0x0000", params.miner_address, params.reward);
+        let mut block = Block::new(vec![coinbase], genesis.hash, params.init_bits, params.hasher);
+        block.header.time = params.genesis_time + 600;
+        ProofOfWork::new(params.init_bits, params.hasher).run(&mut block);
+        block
+    }
+
+    #[test]
+    fn fork_below_pinned_checkpoint_is_rejected() {
+        let block = pinned_block();
+        assert_eq!(Some(block.hash.as_str()), pinned_params("pinned").checkpoint(1));
+
+        let mut mine = Mine::new(&pinned_params("pinned"));
+        let genesis = BlockChain::genesis_block(&NetworkParams::regtest());
+        assert_eq!(Ok(()), mine.chain.submit_block(block.clone()));
+        mine.mining().unwrap();
+        assert_eq!(2, mine.chain.tip().1);
+
+        let fork = mined(vec![tx("0x0000", "0xabcd", 50)], genesis.hash);
+        assert_eq!(Err(BlockError::ForkBeforeCheckpoint { height: 1 }),
+                   mine.chain.submit_block(fork));
+        let above = mined(vec![tx("0x0000", "0xabcd", 50)], block.hash);
+        assert_eq!(Err(BlockError::Stale), mine.chain.submit_block(above));
+    }
+
+    #[test]
+    fn pplns_splits_rewards() {
+        let mut ledger = Pplns::new(4);
+        for worker in ["a", "a", "b", "c", "a"].iter() {
+            ledger.add_share(worker);
+        }
+        let payouts = ledger.pay(10, "c");
+        assert_eq!(vec![("a".to_string(), 5), ("b".to_string(), 2), ("c".to_string(), 3)],
+                   payouts);
+        assert_eq!(5, ledger.balance("a"));
+    }
+
+    #[test]
+    fn pool_credits_workers_over_tcp() {
+        let mut params = regtest_params("pool");
+        params.init_bits = 0x1F7FFFFF;
+        let chain = Mine::new(&params).chain;
+        let err = PoolError::ShareBitsTooHard { share_bits: 0x1F00FFFF, block_bits: params.init_bits };
+        assert_eq!(Err(err), Pool::new(chain.clone(), 0x1F00FFFF, 50).map(|_| ()));
+        assert!(Pool::new(chain.clone(), params.init_bits, 50).is_err());
+        let pool = Pool::new(chain.clone(), 0x2007FFFF, 50).unwrap();
+
+        let mut anon = None;
+        assert_eq!((PoolResponse::Rejected { reason: "not logged in".to_string() }, None),
+                   pool.handle(&mut anon, PoolRequest::GetWork));
+
+        let listener = Pool::bind(0).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = pool.clone();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let found = events.clone();
+        std::thread::spawn(move || server.serve(listener, move |e| found.lock().unwrap().push(e)));
+
+        let workers: Vec<_> = ["alice", "bob"].iter().map(|name| {
+            let chain = chain.clone();
+            std::thread::spawn(move || {
+                let mut worker = PoolWorker::connect(addr, name).unwrap();
+                while chain.tip().1 < 2 {
+                    let work = worker.get_work().unwrap();
+                    worker.mine(&work).unwrap();
+                }
+                worker.shares
+            })
+        }).collect();
+        let shares: u64 = workers.into_iter().map(|w| w.join().unwrap()).sum();
+
+        let mut worker = PoolWorker::connect(addr, "carol").unwrap();
+        let work = worker.get_work().unwrap();
+        let req = PoolRequest::Submit { job_id: work.job_id, nonce: work.nonce_end + 1 };
+        assert_eq!(PoolResponse::Rejected { reason: "nonce out of range".to_string() },
+                   worker.request(&req).unwrap());
+
+        let ledger = pool.ledger();
+        assert!(shares >= 2);
+        assert_eq!(2, chain.tip().1);
+        assert_eq!(2 * params.reward, ledger.balances().values().sum::<u64>());
+        let paid: u64 = events.lock().unwrap().iter().map(|e| match e {
+            PoolEvent::BlockFound { payouts, .. } => payouts.iter().map(|(_, a)| a).sum(),
+            PoolEvent::BlockRejected(_) => 0,
+        }).sum();
+        assert_eq!(2 * params.reward, paid);
+        assert_eq!(params.init_balance + 2 * params.reward, chain.balance(params.miner_address));
+    }
+
+    #[test]
+    fn inspector_filters_and_reports_gaps() {
+        let mut params = regtest_params("inspect");
+        params.prune_depth = Some(3);
+        {
+            let mut mine = Mine::new(&params);
+            mine.mining().unwrap();
+            mine.chain.submit_tx(transfer("0xa", "0xb", 10, 1, 1)).unwrap();
+            for _ in 0..3 {
+                mine.mining().unwrap();
+            }
+        }
+
+        let inspector = ChainInspector::load(&params).unwrap();
+        assert_eq!(4, inspector.tip_height());
+        let genesis = BlockChain::genesis_block(&params).hash;
+        assert_eq!(Err(InspectError::MissingBlock { height: 0, hash: genesis }),
+                   inspector.select(&Query::default()).map(|b| b.len()));
+        let query = Query { to: Some(9), ..Query::default() };
+        assert_eq!(Err(InspectError::BadRange { from: 0, to: 9, tip: 4 }),
+                   inspector.select(&query).map(|b| b.len()));
+
+        let blocks = inspector.select(&Query { from: Some(2), ..Query::default() }).unwrap();
+        let stats = ChainInspector::stats(&blocks);
+        assert_eq!(3, stats.blocks);
+        assert_eq!(4, stats.txs);
+        assert_eq!(vec![(2, params.init_bits)], stats.difficulty);
+        assert_eq!(vec![(2, 1), (3, 0), (4, 0)], stats.fees);
+        assert_eq!(vec![("0xa".to_string(), 10), ("0xb".to_string(), 10)], stats.top_addresses);
+
+        let csv = ChainInspector::render(&blocks, Some("0xa"), Format::Csv);
+        assert_eq!(2, csv.lines().count());
+        assert!(csv.lines().nth(1).unwrap().starts_with("2,"));
+        let json = ChainInspector::render(&blocks, None, Format::Json);
+        assert_eq!(3, json.matches("\"height\"").count());
+    }
+
+    #[test]
+    fn mnemonic_vectors() {
+        let mnemonic = Mnemonic::from_entropy(&[0u8; 16]).unwrap();
+        let phrase = format!("{} about", "abandon ".repeat(11).trim_end());
+        assert_eq!(phrase, mnemonic.phrase());
+        assert_eq!("c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+                   utils::hasher::to_hex(&mnemonic.to_seed("TREZOR")));
+
+        let mnemonic = Mnemonic::from_entropy(&[0xff; 32]).unwrap();
+        assert_eq!(format!("{} vote", "zoo ".repeat(23).trim_end()), mnemonic.phrase());
+        assert_eq!(Ok(mnemonic.clone()), Mnemonic::parse(&mnemonic.phrase().to_uppercase()));
+
+        let bad = phrase.replace("about", "abandon");
+        assert_eq!(Err(WalletError::BadChecksum), Mnemonic::parse(&bad));
+        assert_eq!(Err(WalletError::UnknownWord("bitcoin".to_string())),
+                   Mnemonic::parse(&phrase.replace("about", "bitcoin")));
+        assert_eq!(Err(WalletError::BadLength(11)), Mnemonic::parse("abandon ".repeat(11).trim()));
+        assert_eq!(24, Mnemonic::generate(24).unwrap().phrase().split(' ').count());
+    }
+
+    #[test]
+    fn bip32_vectors() {
+        let seed: Vec<u8> = (0..16).collect();
+        let master = ExtendedKey::master(&seed).unwrap();
+        let hex = utils::hasher::to_hex;
+        assert_eq!("e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35",
+                   hex(&master.key));
+        assert_eq!("873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508",
+                   hex(&master.chain_code));
+
+        let path: DerivationPath = "m/0'/1/2h".parse().unwrap();
+        assert_eq!("m/0'/1/2'", path.to_string());
+        let key = master.derive_path(&path).unwrap();
+        assert_eq!(3, key.depth);
+        assert_eq!("cbce0d719ecf7431d88e6a89fa1483e02e35092af60c042b1df2ff59fa424dca",
+                   hex(&key.key));
+        assert_eq!("04466b9cc8e161e966409ca52986c584f07e9dc81f735db683c3ff6ec7b1503f",
+                   hex(&key.chain_code));
+        assert!("m/x".parse::<DerivationPath>().is_err());
+    }
+
+    #[test]
+    fn wallet_restores_with_gap_limit() {
+        let mut mine = regtest_mine("wallet");
+        let chain = mine.chain.clone();
+        let params = chain.params();
+        let mut wallet = HdWallet::new(&params).unwrap();
+        let phrase = wallet.mnemonic().phrase();
+
+        // 收款地址 0 和 3 收到转账，找零地址 1 收到转账
+        let receive: Vec<String> = (0..4).map(|_| wallet.next_address(AddressKind::Receive).unwrap())
+                                         .collect();
+        wallet.next_address(AddressKind::Change).unwrap();
+        let change = wallet.next_address(AddressKind::Change).unwrap();
+        let targets = [&receive[0], &receive[3], &change];
+        for (nonce, to) in targets.iter().enumerate() {
+            chain.submit_tx(transfer("0xa", to, 5, 1, nonce as u64 + 1)).unwrap();
+        }
+        mine.mining().unwrap();
+
+        let restored = HdWallet::restore(&phrase, "", &chain, 5).unwrap();
+        assert_eq!(4, restored.next_index(AddressKind::Receive));
+        assert_eq!(2, restored.next_index(AddressKind::Change));
+        assert_eq!(wallet.addresses(), restored.addresses());
+
+        let short_gap = HdWallet::restore(&phrase, "", &chain, 2).unwrap();
+        assert_eq!(1, short_gap.next_index(AddressKind::Receive));
+
+        let other = HdWallet::restore(&phrase, "passphrase", &chain, 5).unwrap();
+        assert_eq!(0, other.next_index(AddressKind::Receive));
+        assert_eq!("m/44'/3'/0'/1/1", wallet.path(AddressKind::Change, 1).to_string());
+    }
+
+    #[test]
+    fn peers_are_limited_and_banned() {
+        let params = regtest_params("peers");
+        let config = PeerConfig {
+            max_peers: 2,
+            msgs_per_sec: 1.0,
+            msg_burst: 2.0,
+            ban_secs: 60,
+            ..PeerConfig::default()
+        };
+        let (a, b, c) = ("10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap(),
+                         "10.0.0.3".parse().unwrap());
+        let mut peers = PeerManager::new(config.clone(), &params.save_dir);
+        peers.connect(a, 0).unwrap();
+        peers.connect(b, 0).unwrap();
+        assert_eq!(Err(PeerError::TooManyPeers), peers.connect(c, 0));
+
+        assert_eq!(Ok(()), peers.check_message(a, 10, 0));
+        assert_eq!(Ok(()), peers.check_message(a, 10, 0));
+        assert_eq!(Err(PeerError::RateLimited), peers.check_message(a, 10, 0));
+        assert_eq!(Ok(()), peers.check_message(a, 10, 1));
+        assert_eq!(Some(1), peers.score(&a));
+
+        let max = config.max_message_size;
+        assert_eq!(Err(PeerError::MessageTooLarge { size: max + 1, max }),
+                   peers.check_message(b, max + 1, 0));
+        assert_eq!(Err(PeerError::Banned { until: 60 }), peers.connect(b, 10));
+
+        // 封禁列表重启后仍然有效，到期后自动解除
+        let mut peers = PeerManager::new(config, &params.save_dir);
+        assert_eq!(1, peers.bans().len());
+        assert_eq!(Err(PeerError::Banned { until: 60 }), peers.connect(b, 30));
+        assert_eq!(Ok(()), peers.connect(b, 61));
+    }
+
+    #[test]
+    fn peers_feed_orphans_and_misbehave() {
+        let mut source = regtest_mine("peers_source");
+        source.mining().unwrap();
+        source.mining().unwrap();
+        let blocks = source.chain.blocks();
+
+        let chain = regtest_mine("peers_sink").chain;
+        let params = chain.params();
+        let mut peers = PeerManager::new(PeerConfig::default(), &params.save_dir);
+        let peer = "10.0.0.1".parse().unwrap();
+        peers.connect(peer, 0).unwrap();
+
+        peers.process_block(&chain, peer, blocks[2].clone(), 0).unwrap();
+        assert_eq!(1, peers.orphan_blocks());
+        peers.process_block(&chain, peer, blocks[1].clone(), 0).unwrap();
+        assert_eq!((blocks[2].hash.clone(), 2), chain.tip());
+        assert_eq!(0, peers.orphan_blocks());
+
+        peers.process_tx(&chain, peer, transfer("0xa", "0xb", 1, 1, 2), 0).unwrap();
+        assert_eq!(1, peers.orphan_txs());
+        peers.process_tx(&chain, peer, transfer("0xa", "0xb", 1, 1, 1), 0).unwrap();
+        assert_eq!(0, peers.orphan_txs());
+        assert_eq!(2, chain.mempool_txs().len());
+
+        assert_eq!(Err(PeerError::Tx(TxError::SelfTransfer)),
+                   peers.process_tx(&chain, peer, transfer("0xb", "0xb", 1, 1, 1), 0));
+        assert_eq!(Some(10), peers.score(&peer));
+
+        // 孤儿区块也按链的难度校验
+        let other = "10.0.0.2".parse().unwrap();
+        peers.connect(other, 0).unwrap();
+        let easy = 0x2000FFFF;
+        let mut orphan = blocks[2].clone();
+        orphan.header.pre_hash = "unknown".to_string();
+        orphan.header.bits = easy;
+        ProofOfWork::new(easy, params.hasher).run(&mut orphan);
+        assert_eq!(Err(PeerError::Block(BlockError::BadBits { expected: params.init_bits, got: easy })),
+                   peers.process_block(&chain, other, orphan, 0));
+        assert_eq!(0, peers.orphan_blocks());
+        assert_eq!(None, peers.score(&other));
+
+        let mut bad = blocks[2].clone();
+        bad.header.pre_hash = "unknown".to_string();
+        assert_eq!(Err(PeerError::Block(BlockError::BadProofOfWork)),
+                   peers.process_block(&chain, peer, bad, 0));
+        assert_eq!(None, peers.score(&peer));
+        assert!(peers.connect(peer, 1).is_err());
+    }
+
+    #[test]
+    fn stored_chain_loads_read_only() {
+        let params = regtest_params("load");
+        assert!(BlockChain::load(&params).is_err());
+
+        let mut mine = Mine::new(&params);
+        mine.chain.submit_tx(transfer("0xa", "0xb", 10, 2, 1)).unwrap();
+        mine.mining().unwrap();
+        mine.mining().unwrap();
+        let (tip, blocks) = (mine.chain.tip(), mine.chain.blocks().len());
+        drop(mine);
+
+        let chain = ChainHandle::new(BlockChain::load(&params).unwrap());
+        assert_eq!(tip, chain.tip());
+        assert_eq!(blocks, chain.blocks().len());
+        assert_eq!(88, chain.balance("0xa"));
+        assert_eq!(1, chain.nonce("0xa"));
+        assert_eq!(Some(tip.0.clone()), chain.hash_at(2));
+        drop(chain);
+
+        assert_eq!(tip, ChainHandle::new(BlockChain::load(&params).unwrap()).tip());
+    }
+
+    #[test]
+    fn stored_mempool_replacements() {
+        let params = regtest_params("stored_mempool");
+        let mut mine = Mine::new(&params);
+        mine.mining().unwrap();
+        drop(mine);
+
+        let chain = ChainHandle::load(&params).unwrap();
+        let (mut kim, mut tom) = (chain.account("0xa"), chain.account("0xb"));
+        chain.submit_tx(kim.transfer_to(&mut tom, 10, 1).unwrap()).unwrap();
+        chain.save_mempool().unwrap();
+        drop(chain);
+
+        let chain = ChainHandle::load(&params).unwrap();
+        let tx = chain.pending_tx("0xa", 1).unwrap();
+        assert_eq!((89, 1), (chain.account("0xa").balance, chain.account("0xa").nonce));
+        assert!(chain.pending_tx("0xa", 2).is_none());
+        let (mut kim, mut tom) = (chain.account("0xa"), chain.account(&tx.to));
+        chain.submit_tx(kim.bump_fee(&mut tom, &tx, 5).unwrap()).unwrap();
+        chain.save_mempool().unwrap();
+        drop(chain);
+
+        let mut mine = Mine::load(&params).unwrap();
+        let pending = mine.chain.mempool_txs();
+        assert_eq!(1, pending.len());
+        assert_eq!(5, pending[0].fee);
+        mine.mining().unwrap();
+        mine.chain.save_mempool().unwrap();
+        assert_eq!((85, 110), (mine.chain.balance("0xa"), mine.chain.balance("0xb")));
+        drop(mine);
+
+        assert!(ChainHandle::load(&params).unwrap().mempool_txs().is_empty());
+    }
+
+    #[test]
+    fn explorer_pages_and_search() {
+        let mut mine = regtest_mine("explorer");
+        let chain = mine.chain.clone();
+        let sent = transfer("0xa", "<b>", 10, 2, 1);
+        chain.submit_tx(sent.clone()).unwrap();
+        mine.mining().unwrap();
+        let pending = transfer("0xa", "0xb", 5, 1, 2);
+        chain.submit_tx(pending.clone()).unwrap();
+
+        let explorer = Explorer::new(chain.clone());
+        let (tip, _) = chain.tip();
+        let home = explorer.route("/");
+        assert_eq!(200, home.status);
+        assert!(home.body.contains(&tip));
+
+        let block = explorer.route("/block/1");
+        assert!(block.body.contains(&sent.hash));
+        assert!(block.body.contains("(valid)"));
+        assert!(block.body.contains("&lt;b&gt;") && !block.body.contains("<b>"));
+        assert_eq!(block, explorer.route(&format!("/block/{}", tip)));
+
+        assert!(explorer.route(&format!("/tx/{}", sent.hash)).body.contains("confirmed in block"));
+        assert!(explorer.route(&format!("/tx/{}", pending.hash)).body.contains("mempool"));
+        let addr = explorer.route("/address/%3Cb%3E");
+        assert!(addr.body.contains("balance: 110"));
+        assert!(addr.body.contains(&sent.hash));
+
+        let search = |q: &str| explorer.route(&format!("/search?q={}", q)).location;
+        assert_eq!(Some(format!("/block/{}", tip)), search("1"));
+        assert_eq!(Some(format!("/block/{}", tip)), search(&tip));
+        assert_eq!(Some(format!("/tx/{}", sent.hash)), search(&sent.hash));
+        assert_eq!(Some("/address/0xb".to_string()), search("+0xb"));
+        assert_eq!(404, explorer.route("/search?q=9").status);
+        assert_eq!(404, explorer.route("/block/ff").status);
+        assert_eq!(404, explorer.route("/nowhere").status);
+
+        let read = |req: String| Explorer::read_request(&mut req.as_bytes()).unwrap();
+        assert_eq!(Some("GET / HTTP/1.1\r\n".to_string()),
+                   read("GET / HTTP/1.1\r\nHost: x\r\n\r\n".to_string()));
+        assert_eq!(None, read(format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(9000))));
+        assert_eq!(None, read(format!("GET / HTTP/1.1\r\n{}\r\n", "X: y\r\n".repeat(101))));
+    }
+}
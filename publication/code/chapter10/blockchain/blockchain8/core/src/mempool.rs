@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::{env, fs};
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use utils::serializer::{deserialize, serialize};
+use crate::block::Block;
+use crate::error::TxError;
+use crate::state::State;
+use crate::transaction::Transaction;
+
+// 同一 nonce 的交易最多被替换的次数，防止反复替换占用转发带宽
+pub const MAX_REPLACEMENTS: u32 = 10;
+
+const MEMPOOL_FILE: &str = "mempool.dat";
+
+// 交易池：保存已校验但尚未打包的交易
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Mempool {
+    txs: HashMap<String, Transaction>,
+    heights: HashMap<String, u64>, // 交易进入交易池时的链高度
+    replaced: HashMap<String, u32>, // 交易所占 nonce 已被替换的次数
+}
+
+impl Mempool {
+    pub fn new() -> Self {
+        Mempool {
+            txs: HashMap::new(),
+            heights: HashMap::new(),
+            replaced: HashMap::new(),
+        }
+    }
+
+    fn path(save_dir: &str) -> PathBuf {
+        let mut dir = env::current_dir().unwrap();
+        dir.push(save_dir);
+        dir.push(MEMPOOL_FILE);
+        dir
+    }
+
+    // 读取上次保存的交易池，按当前链状态重新校验，已失效的交易丢弃
+    pub fn load(save_dir: &str, state: &State, height: u64) -> Self {
+        let stored: Self = fs::read(Self::path(save_dir))
+            .ok()
+            .and_then(|bytes| deserialize(&bytes).ok())
+            .unwrap_or_default();
+
+        let mut pool = Mempool::new();
+        for tx in stored.pending() {
+            let hash = tx.hash.clone();
+            let entry = stored.heights.get(&hash).map_or(height, |h| (*h).min(height));
+            if pool.add(tx, state, entry).is_ok() {
+                if let Some(count) = stored.replaced.get(&hash) {
+                    pool.replaced.insert(hash, *count);
+                }
+            }
+        }
+
+        pool
+    }
+
+    pub fn save(&self, save_dir: &str) -> std::io::Result<()> {
+        fs::write(Self::path(save_dir), serialize(self))
+    }
+
+    pub fn len(&self) -> usize {
+        self.txs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.txs.is_empty()
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.txs.contains_key(hash)
+    }
+
+    pub fn get(&self, hash: &str) -> Option<&Transaction> {
+        self.txs.get(hash)
+    }
+
+    // 同一发送者的交易按 nonce 排序
+    pub fn pending_from(&self, address: &str) -> Vec<&Transaction> {
+        let mut txs: Vec<&Transaction> = self.txs.values()
+                                             .filter(|tx| tx.from == address)
+                                             .collect();
+        txs.sort_by_key(|tx| tx.nonce);
+        txs
+    }
+
+    // (手续费率, 进池高度)，供手续费估算使用
+    pub fn entries(&self) -> Vec<(u64, u64)> {
+        self.txs.values()
+                .map(|tx| (tx.fee_rate(), self.heights[&tx.hash]))
+                .collect()
+    }
+
+    // 在链上状态之上叠加该发送者已在池中的交易后再校验，height 为当前链高度
+    // 与池中交易 nonce 相同时按替换规则处理
+    pub fn add(&mut self, tx: Transaction, state: &State, height: u64)
+        -> Result<(), TxError>
+    {
+        if self.txs.contains_key(&tx.hash) {
+            return Err(TxError::Duplicate);
+        }
+
+        let pending: Vec<Transaction> = self.pending_from(&tx.from)
+                                            .into_iter()
+                                            .cloned()
+                                            .collect();
+        let mut projected = state.clone();
+        for p in pending.iter().filter(|p| p.nonce < tx.nonce) {
+            projected.apply_tx(p)?;
+        }
+        let original = pending.iter().find(|p| p.nonce == tx.nonce);
+        if tx.is_cancel() && original.is_none() {
+            return Err(TxError::NothingToReplace);
+        }
+        tx.validate(&projected)?;
+
+        // 转发策略：手续费不得低于最低费率
+        let min = Self::min_fee(&tx, state.params().min_fee_per_byte);
+        if tx.fee < min {
+            return Err(TxError::FeeTooLow { fee: tx.fee, min });
+        }
+
+        if let Some(original) = original {
+            let later: Vec<&Transaction> = pending.iter().filter(|p| p.nonce > tx.nonce).collect();
+            let count = self.replace(original, &later, &tx, projected)?;
+            self.replaced.insert(tx.hash.clone(), count);
+        }
+
+        self.heights.insert(tx.hash.clone(), height);
+        self.txs.insert(tx.hash.clone(), tx);
+        Ok(())
+    }
+
+    // 替换规则：手续费须高于被替换交易及因此失效的后续交易的手续费之和，
+    // 且增量不低于新交易自身的最低转发费；满足时移除这些交易，返回替换次数
+    fn replace(&mut self, original: &Transaction, later: &[&Transaction],
+               tx: &Transaction, mut projected: State) -> Result<u32, TxError>
+    {
+        let count = self.replaced.get(&original.hash).copied().unwrap_or(0) + 1;
+        if count > MAX_REPLACEMENTS {
+            return Err(TxError::TooManyReplacements { max: MAX_REPLACEMENTS });
+        }
+
+        // 后续交易在新交易之上重新校验，第一笔失效后其余因 nonce 断档全部失效
+        projected.apply_tx(tx)?;
+        let mut evicted = vec![original];
+        for p in later {
+            if evicted.len() > 1 || projected.apply_tx(p).is_err() {
+                evicted.push(p);
+            }
+        }
+
+        let replaced_fee = evicted.iter()
+                                  .try_fold(0u64, |sum, p| sum.checked_add(p.fee))
+                                  .ok_or(TxError::Overflow)?;
+        // 最低费率为 0 时增量至少为 1，避免以相同手续费反复替换
+        let min = Self::min_fee(tx, projected.params().min_fee_per_byte)
+            .max(1)
+            .checked_add(replaced_fee)
+            .ok_or(TxError::Overflow)?;
+        if tx.fee < min {
+            return Err(TxError::ReplacementFeeTooLow { fee: tx.fee, min });
+        }
+
+        for p in evicted {
+            self.txs.remove(&p.hash);
+            self.heights.remove(&p.hash);
+            self.replaced.remove(&p.hash);
+        }
+
+        Ok(count)
+    }
+
+    // 打包顺序：同一发送者内按 nonce，发送者之间按首笔交易手续费从高到低
+    fn sender_chains(&self) -> Vec<Vec<&Transaction>> {
+        let mut senders: Vec<Vec<&Transaction>> = Vec::new();
+        let mut seen: Vec<&str> = Vec::new();
+        for tx in self.txs.values() {
+            if !seen.contains(&tx.from.as_str()) {
+                seen.push(&tx.from);
+                senders.push(self.pending_from(&tx.from));
+            }
+        }
+        senders.sort_by(|a, b| b[0].fee.cmp(&a[0].fee).then(a[0].from.cmp(&b[0].from)));
+
+        senders
+    }
+
+    pub fn pending(&self) -> Vec<Transaction> {
+        self.sender_chains().into_iter().flatten().cloned().collect()
+    }
+
+    pub fn min_fee(tx: &Transaction, fee_per_byte: u64) -> u64 {
+        (tx.size() as u64).saturating_mul(fee_per_byte)
+    }
+
+    // 按打包顺序挑选可打包的交易，直到区块重量或交易数达到上限
+    pub fn select(&self, height: u64, mtp: i64, max_weight: usize, max_txs: usize)
+        -> Vec<Transaction>
+    {
+        let mut selected = Vec::new();
+        let mut weight = 0;
+        for chain in self.sender_chains() {
+            for tx in chain {
+                let size = tx.size();
+                if !tx.is_final(height, mtp)
+                    || weight + size > max_weight
+                    || selected.len() >= max_txs {
+                    break;
+                }
+
+                weight += size;
+                selected.push(tx.clone());
+            }
+        }
+
+        selected
+    }
+
+    // 区块上链后移除已打包交易，并剔除在新状态下失效的交易
+    // 返回被打包交易的 (手续费率, 进池高度)
+    pub fn remove_block(&mut self, block: &Block, state: &State) -> Vec<(u64, u64)> {
+        let mut confirmed = Vec::new();
+        for tx in block.tranxs.iter() {
+            if self.txs.remove(&tx.hash).is_some() {
+                confirmed.push((tx.fee_rate(), self.heights[&tx.hash]));
+            }
+        }
+
+        let mut projected = state.clone();
+        let mut valid = HashMap::new();
+        for tx in self.pending() {
+            if projected.apply_tx(&tx).is_ok() {
+                valid.insert(tx.hash.clone(), tx);
+            }
+        }
+        self.heights.retain(|hash, _| valid.contains_key(hash));
+        self.replaced.retain(|hash, _| valid.contains_key(hash));
+        self.txs = valid;
+
+        confirmed
+    }
+}
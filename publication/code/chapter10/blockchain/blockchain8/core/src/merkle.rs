@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use utils::serializer::serialize;
+use utils::hasher::{ChainHasher, HashAlgo};
+
+pub const EMPTY_ROOT: &str = "00000000";
+
+// 默克尔包含证明：index 的每一位决定该层兄弟节点在左还是在右
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MerkleProof {
+    pub index: usize,
+    pub siblings: Vec<String>,
+}
+
+fn merge(hash1: &str, hash2: &str, hasher: HashAlgo) -> String {
+    let merge: String = format!("{}-{}", hash1, hash2);
+    let merge_ser = serialize(&merge);
+    hasher.hash_str(&merge_ser)
+}
+
+// 奇数个节点时最后一个节点与自身合并
+fn next_level(level: &[String], hasher: HashAlgo) -> Vec<String> {
+    level.chunks(2)
+         .map(|pair| {
+             let right = pair.last().unwrap();
+             merge(&pair[0], right, hasher)
+         })
+         .collect()
+}
+
+pub fn merkle_root(hashes: &[String], hasher: HashAlgo) -> String {
+    if hashes.is_empty() {
+        return EMPTY_ROOT.to_string();
+    }
+
+    let mut level = hashes.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level, hasher);
+    }
+
+    level.pop().unwrap()
+}
+
+pub fn merkle_proof(hashes: &[String], index: usize, hasher: HashAlgo)
+    -> Option<MerkleProof>
+{
+    if index >= hashes.len() {
+        return None;
+    }
+
+    let mut siblings = Vec::new();
+    let mut level = hashes.to_vec();
+    let mut idx = index;
+    while level.len() > 1 {
+        let sibling = if idx.is_multiple_of(2) {
+            level[usize::min(idx + 1, level.len() - 1)].clone()
+        } else {
+            level[idx - 1].clone()
+        };
+        siblings.push(sibling);
+
+        level = next_level(&level, hasher);
+        idx >>= 1;
+    }
+
+    Some(MerkleProof { index, siblings })
+}
+
+impl MerkleProof {
+    pub fn verify(&self, leaf: &str, root: &str, hasher: HashAlgo) -> bool {
+        let mut hash = leaf.to_string();
+        let mut idx = self.index;
+        for sibling in self.siblings.iter() {
+            hash = if idx.is_multiple_of(2) {
+                merge(&hash, sibling, hasher)
+            } else {
+                merge(sibling, &hash, hasher)
+            };
+            idx >>= 1;
+        }
+
+        hash == root
+    }
+}
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use utils::hasher::HashAlgo;
+use utils::serializer::{deserialize, serialize};
+use crate::block::{Block, BlockHeader};
+use crate::bloom::BloomFilter;
+use crate::error::SpvError;
+use crate::merkle::MerkleProof;
+use crate::transaction::Transaction;
+
+// 一次最多返回的区块头个数
+pub const MAX_HEADERS: usize = 2000;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MatchedTx {
+    pub tx: Transaction,
+    pub proof: MerkleProof,
+}
+
+// 区块头加上命中过滤器的交易及其默克尔证明
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MerkleBlock {
+    pub header: BlockHeader,
+    pub total_txs: usize,
+    pub matches: Vec<MatchedTx>,
+}
+
+impl MerkleBlock {
+    pub fn from_block(block: &Block, filter: &BloomFilter, hasher: HashAlgo) -> Self {
+        let mut matches = Vec::new();
+        for (i, tx) in block.tranxs.iter().enumerate() {
+            let hit = filter.contains(tx.hash.as_str())
+                || filter.contains(tx.from.as_str())
+                || filter.contains(tx.to.as_str());
+            if !hit {
+                continue;
+            }
+
+            if let Some(proof) = block.merkle_proof(i, hasher) {
+                matches.push(MatchedTx { tx: tx.clone(), proof });
+            }
+        }
+
+        MerkleBlock {
+            header: block.header.clone(),
+            total_txs: block.tranxs.len(),
+            matches,
+        }
+    }
+}
+
+// 节点间消息，目前只包含轻节点同步所需的部分
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum Message {
+    GetHeaders { locator: Vec<String>, stop_hash: String },
+    Headers(Vec<BlockHeader>),
+    FilterLoad(BloomFilter),
+    FilterClear,
+    GetMerkleBlock(String),
+    MerkleBlock(MerkleBlock),
+    NotFound(String),
+}
+
+impl Message {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serialize(self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SpvError> {
+        deserialize(bytes).map_err(|e| SpvError::BadMessage(e.to_string()))
+    }
+}
@@ -1,26 +1,36 @@
 use crate::miner::Miner;
 use crate::blockchain::BlockChain;
-use crate::transaction::Transaction;
-
-const MINER_ADDRESS: &str = "0x1b2d";
+use crate::chain::ChainHandle;
+use crate::params::NetworkParams;
+use crate::error::{BlockError, InspectError};
 
 pub struct Mine {
     pub miner: Miner,
-    pub blockchain: BlockChain,
+    pub chain: ChainHandle,
 }
 
 impl Mine {
-    pub fn new() -> Self {
+    pub fn new(params: &NetworkParams) -> Self {
         Mine {
-            blockchain: BlockChain::new(),
-            miner: Miner::new(MINER_ADDRESS.to_string()),
+            chain: ChainHandle::new(BlockChain::new(params)),
+            miner: Miner::new(params),
         }
     }
 
-    pub fn mining(&mut self, txs: &mut Vec<Transaction>) {
-        let pre_hash = self.blockchain.curr_hash.clone();
-        let bits = self.blockchain.curr_bits.clone();
-        let block = self.miner.mine_block(txs, pre_hash, bits);
-        self.blockchain.add_block(block);
+    // 在已保存的链和交易池上继续挖矿
+    pub fn load(params: &NetworkParams) -> Result<Self, InspectError> {
+        Ok(Mine {
+            chain: ChainHandle::load(params)?,
+            miner: Miner::new(params),
+        })
+    }
+
+    // 从交易池取交易打包
+    pub fn mining(&mut self) -> Result<(), BlockError> {
+        let template = self.chain.template();
+        let block = self.miner.mine_block(&template);
+        self.chain.submit_block(block.clone())?;
+        self.miner.credit(&block);
+        Ok(())
     }
 }
@@ -1,4 +1,8 @@
+use std::thread;
+use std::time::Duration;
 use crate::block::Block;
+use crate::chain::BlockTemplate;
+use crate::params::NetworkParams;
 use crate::pow::ProofOfWork;
 use crate::transaction::Transaction;
 
@@ -6,49 +10,65 @@ const MINER_NAME: &str = "anonymous";
 
 #[derive(Debug, Clone)]
 pub struct Miner {
-    name: String,
+    pub name: String,
     pub balance: u64,
     address: String,
+    params: NetworkParams,
 }
 
 impl Miner {
-    pub fn new(address: String) -> Self {
+    pub fn new(params: &NetworkParams) -> Self {
         Miner {
             name: MINER_NAME.to_string(),
-            balance: 100,
-            address: address,
+            balance: params.init_balance,
+            address: params.miner_address.to_string(),
+            params: params.clone(),
         }
     }
 
-    pub fn mine_block(&mut self, txs: &mut Vec<Transaction>, pre_hash: String, bits: u32)
-        -> Block {
+    // 只依赖模板快照，挖矿期间不持有任何链锁
+    pub fn mine_block(&mut self, template: &BlockTemplate) -> Block {
+        let mut block = self.prepare_block(template);
+        thread::sleep(Duration::from_secs(self.params.block_delay));
+
+        let pow = ProofOfWork::new(block.header.bits, self.params.hasher);
+        pow.run(&mut block);
+
+        block
+    }
+
+    // 在模板交易前加上 coinbase，得到待求解 nonce 的区块
+    pub fn prepare_block(&self, template: &BlockTemplate) -> Block {
+        let mut txs = template.txs.clone();
         let mut fee = 0; // 挖矿手续费
         for tx in txs.iter() {
-            fee += tx.fee.clone();
+            fee += tx.fee;
         }
 
+        let reward = self.params.reward; // 挖矿奖励，实际中会半衰 50、25、12.5
         let from = "0x0000".to_string();
         let to = self.address.clone();
-        let sign = format!("{} -> {}: 50 btc", from, to);
-        let coinbase = Transaction::new(from, to, 0, 0, 0, sign);
+        let sign = format!("{} -> {}: {} btc", from, to, reward);
+        let coinbase = Transaction::new(from, to, reward + fee, 0, 0, sign, &self.params);
 
         let mut txs_all: Vec<Transaction> = Vec::new();
         txs_all.push(coinbase);
-        txs_all.append(txs);
-        let block = Self::mine_job(txs_all, pre_hash, bits);
+        txs_all.append(&mut txs);
 
-        self.balance += 50; // 挖矿奖励，实际中会半衰 50、25、12.5
-        self.balance += fee;
+        let (hasher, bits) = (self.params.hasher, template.bits);
+        let mut block = Block::new(txs_all, template.pre_hash.clone(), bits, hasher);
+
+        // 出块过快时时间可能不晚于中位时间，需要顺延
+        block.header.time = block.header.time.max(template.mtp + 1);
 
         block
     }
 
-    fn mine_job(txs: Vec<Transaction>, pre_hash: String, bits: u32) -> Block {
-        let mut block = Block::new(txs, pre_hash, bits);
-        let pow = ProofOfWork::new(bits);
-        pow.run(&mut block);
-
-        block
+    // 区块被链接受后才计入 coinbase 收入
+    pub fn credit(&mut self, block: &Block) {
+        if let Some(coinbase) = block.tranxs.first() {
+            self.balance = self.balance.saturating_add(coinbase.amount);
+        }
     }
 
     pub fn miner_info(&self) {
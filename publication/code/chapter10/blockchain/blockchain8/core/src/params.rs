@@ -0,0 +1,118 @@
+use utils::hasher::HashAlgo;
+
+const PRE_HASH: &str = "22caaf24ef0aea3522c13d133912d2b722caaf24ef0aea3522c13d133912d2b7";
+
+// 各网络的检查点，目前只有创世区块
+const MAINNET_CHECKPOINTS: &[(u64, &str)] = &[
+    (0, "9acf9b8a17017bad38296117583efdcaea36b5614e7c91fede4035631842684a"),
+];
+const TESTNET_CHECKPOINTS: &[(u64, &str)] = &[
+    (0, "5880b1490b7dc82ee2301eacd84a19ed190785d7cd54f69a7eaca30eed15f871"),
+];
+const REGTEST_CHECKPOINTS: &[(u64, &str)] = &[
+    (0, "1d809d57c84704e361561284bcba90f8673d6068869bb83e6aff5ab8134f3c43"),
+];
+
+// 网络参数：创世数据、难度、奖励和存储目录等都由此提供
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkParams {
+    pub name: &'static str,
+    pub chain_id: u32,
+    pub hasher: HashAlgo,
+    pub pre_hash: &'static str,
+    pub genesis_sign: &'static str,
+    pub genesis_time: i64,
+    pub init_bits: u32,
+    pub miner_address: &'static str,
+    pub init_balance: u64, // 新账户和矿工的初始余额
+    pub reward: u64,
+    pub block_delay: u64, // 模拟出块耗时(秒)
+    pub max_time_drift: i64, // 区块时间最多领先本地时间的秒数
+    pub max_block_weight: usize, // 区块序列化后的最大字节数
+    pub max_block_txs: usize,
+    pub max_tx_size: usize,
+    pub min_fee_per_byte: u64, // 最低转发费率(每字节)，低于此费率的交易不进交易池
+    pub checkpoints: &'static [(u64, &'static str)], // (高度, 区块哈希)，按高度升序
+    pub prune_depth: Option<u64>, // 修剪模式下保留的最近完整区块数，None 为不修剪
+    pub save_dir: String,
+}
+
+impl NetworkParams {
+    pub fn mainnet() -> Self {
+        NetworkParams {
+            name: "mainnet",
+            chain_id: 1,
+            hasher: HashAlgo::Sha3_256,
+            pre_hash: PRE_HASH,
+            genesis_sign: "创世区块",
+            genesis_time: 1612137600,
+            init_bits: 0x2100FFFF,
+            miner_address: "0x1b2d",
+            init_balance: 100,
+            reward: 50,
+            block_delay: 3,
+            max_time_drift: 2 * 60 * 60,
+            max_block_weight: 1_000_000,
+            max_block_txs: 4000,
+            max_tx_size: 10_000,
+            min_fee_per_byte: 0, // 金额单位较大，默认不设下限
+            checkpoints: MAINNET_CHECKPOINTS,
+            prune_depth: None,
+            save_dir: "bc_db".to_string(),
+        }
+    }
+
+    // 测试网：难度和哈希算法都与主网不同，两条链的区块不能互用
+    pub fn testnet() -> Self {
+        NetworkParams {
+            name: "testnet",
+            chain_id: 2,
+            genesis_sign: "创世区块(测试网)",
+            genesis_time: 1612224000,
+            hasher: HashAlgo::DoubleSha256,
+            init_bits: 0x2000FFFF,
+            miner_address: "0x2b2d",
+            block_delay: 1,
+            checkpoints: TESTNET_CHECKPOINTS,
+            save_dir: "bc_db_testnet".to_string(),
+            ..Self::mainnet()
+        }
+    }
+
+    // 回归测试网：难度极低，无需等待即可出块，使用独立的哈希算法和难度
+    pub fn regtest() -> Self {
+        NetworkParams {
+            name: "regtest",
+            chain_id: 3,
+            genesis_sign: "创世区块(回归测试网)",
+            genesis_time: 1612310400,
+            hasher: HashAlgo::Blake2b,
+            init_bits: 0x207FFFFF,
+            miner_address: "0x3b2d",
+            block_delay: 0,
+            checkpoints: REGTEST_CHECKPOINTS,
+            save_dir: "bc_db_regtest".to_string(),
+            ..Self::mainnet()
+        }
+    }
+
+    pub fn checkpoint(&self, height: u64) -> Option<&'static str> {
+        self.checkpoints.iter()
+                        .find(|(h, _)| *h == height)
+                        .map(|(_, hash)| *hash)
+    }
+
+    // 不高于最后一个检查点的区块不再验证签名，也不允许在其下分叉
+    pub fn last_checkpoint(&self) -> Option<u64> {
+        self.checkpoints.last().map(|(height, _)| *height)
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "mainnet" => Some(Self::mainnet()),
+            "testnet" => Some(Self::testnet()),
+            "regtest" => Some(Self::regtest()),
+            _ => None,
+        }
+    }
+}
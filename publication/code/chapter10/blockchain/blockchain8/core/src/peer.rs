@@ -0,0 +1,348 @@
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::{env, fs};
+use serde::{Deserialize, Serialize};
+use utils::serializer::{deserialize, serialize};
+use crate::block::Block;
+use crate::chain::ChainHandle;
+use crate::error::{BlockError, PeerError, TxError};
+use crate::pow::ProofOfWork;
+use crate::transaction::Transaction;
+
+const BAN_FILE: &str = "banlist.dat";
+
+// 节点的连接和防护策略
+#[derive(Debug, Clone)]
+pub struct PeerConfig {
+    pub max_peers: usize,
+    pub max_message_size: usize, // 单条消息的最大字节数
+    pub msgs_per_sec: f64,       // 令牌桶每秒补充的消息数
+    pub msg_burst: f64,          // 令牌桶容量
+    pub ban_threshold: u32,      // 累计违规分达到此值即封禁
+    pub ban_secs: i64,
+    pub max_orphan_blocks: usize,
+    pub max_orphan_txs: usize,
+}
+
+impl Default for PeerConfig {
+    fn default() -> Self {
+        PeerConfig {
+            max_peers: 125,
+            max_message_size: 4_000_000,
+            msgs_per_sec: 50.0,
+            msg_burst: 200.0,
+            ban_threshold: 100,
+            ban_secs: 24 * 60 * 60,
+            max_orphan_blocks: 100,
+            max_orphan_txs: 1000,
+        }
+    }
+}
+
+// 封禁列表：地址到解封时间，保存在数据目录下
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BanList {
+    bans: HashMap<IpAddr, i64>,
+}
+
+impl BanList {
+    fn path(save_dir: &str) -> PathBuf {
+        let mut dir = env::current_dir().unwrap();
+        dir.push(save_dir);
+        dir.push(BAN_FILE);
+        dir
+    }
+
+    pub fn load(save_dir: &str) -> Self {
+        fs::read(Self::path(save_dir))
+            .ok()
+            .and_then(|bytes| deserialize(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, save_dir: &str) -> std::io::Result<()> {
+        fs::create_dir_all(Self::path(save_dir).parent().unwrap())?;
+        fs::write(Self::path(save_dir), serialize(self))
+    }
+
+    pub fn ban(&mut self, addr: IpAddr, until: i64) {
+        let entry = self.bans.entry(addr).or_insert(until);
+        *entry = (*entry).max(until);
+    }
+
+    pub fn unban(&mut self, addr: &IpAddr) -> bool {
+        self.bans.remove(addr).is_some()
+    }
+
+    pub fn banned_until(&self, addr: &IpAddr, now: i64) -> Option<i64> {
+        self.bans.get(addr).copied().filter(|&until| until > now)
+    }
+
+    // 清除已过期的封禁
+    pub fn sweep(&mut self, now: i64) {
+        self.bans.retain(|_, until| *until > now);
+    }
+
+    pub fn len(&self) -> usize {
+        self.bans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bans.is_empty()
+    }
+}
+
+// 有容量上限的孤儿池，满了之后淘汰最早加入的
+#[derive(Debug, Clone)]
+pub struct OrphanPool<T> {
+    cap: usize,
+    items: VecDeque<(String, T)>,
+}
+
+impl<T> OrphanPool<T> {
+    pub fn new(cap: usize) -> Self {
+        OrphanPool { cap, items: VecDeque::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.items.iter().any(|(k, _)| k == key)
+    }
+
+    pub fn insert(&mut self, key: String, item: T) {
+        if self.cap == 0 || self.contains(&key) {
+            return;
+        }
+        while self.items.len() >= self.cap {
+            self.items.pop_front();
+        }
+        self.items.push_back((key, item));
+    }
+
+    // 取出所有满足条件的条目
+    pub fn take<F: Fn(&T) -> bool>(&mut self, pred: F) -> Vec<T> {
+        let (taken, kept) = self.items.drain(..).partition(|(_, item)| pred(item));
+        self.items = kept;
+        taken.into_iter().map(|(_, item)| item).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Peer {
+    score: u32,
+    tokens: f64,
+    last_refill: f64,
+}
+
+// 对等节点管理：连接数、消息大小和频率限制，违规计分和封禁
+pub struct PeerManager {
+    config: PeerConfig,
+    save_dir: String,
+    peers: HashMap<IpAddr, Peer>,
+    bans: BanList,
+    orphan_blocks: OrphanPool<Block>,
+    orphan_txs: OrphanPool<Transaction>,
+}
+
+impl PeerManager {
+    pub fn new(config: PeerConfig, save_dir: &str) -> Self {
+        PeerManager {
+            orphan_blocks: OrphanPool::new(config.max_orphan_blocks),
+            orphan_txs: OrphanPool::new(config.max_orphan_txs),
+            config,
+            save_dir: save_dir.to_string(),
+            peers: HashMap::new(),
+            bans: BanList::load(save_dir),
+        }
+    }
+
+    pub fn bans(&self) -> &BanList {
+        &self.bans
+    }
+
+    pub fn score(&self, addr: &IpAddr) -> Option<u32> {
+        self.peers.get(addr).map(|p| p.score)
+    }
+
+    pub fn peer_count(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub fn orphan_blocks(&self) -> usize {
+        self.orphan_blocks.len()
+    }
+
+    pub fn orphan_txs(&self) -> usize {
+        self.orphan_txs.len()
+    }
+
+    pub fn connect(&mut self, addr: IpAddr, now: i64) -> Result<(), PeerError> {
+        if let Some(until) = self.bans.banned_until(&addr, now) {
+            return Err(PeerError::Banned { until });
+        }
+        if self.peers.contains_key(&addr) {
+            return Ok(());
+        }
+        if self.peers.len() >= self.config.max_peers {
+            return Err(PeerError::TooManyPeers);
+        }
+
+        self.peers.insert(addr, Peer {
+            score: 0,
+            tokens: self.config.msg_burst,
+            last_refill: now as f64,
+        });
+        Ok(())
+    }
+
+    pub fn disconnect(&mut self, addr: &IpAddr) {
+        self.peers.remove(addr);
+    }
+
+    // 每条消息处理前调用：先查大小，再按令牌桶限速
+    pub fn check_message(&mut self, addr: IpAddr, size: usize, now: i64)
+        -> Result<(), PeerError>
+    {
+        let max = self.config.max_message_size;
+        if size > max {
+            self.misbehave(addr, self.config.ban_threshold, now)?;
+            return Err(PeerError::MessageTooLarge { size, max });
+        }
+
+        let (rate, burst) = (self.config.msgs_per_sec, self.config.msg_burst);
+        let peer = self.peers.get_mut(&addr).ok_or(PeerError::UnknownPeer)?;
+        let elapsed = (now as f64 - peer.last_refill).max(0.0);
+        peer.tokens = (peer.tokens + elapsed * rate).min(burst);
+        peer.last_refill = now as f64;
+        if peer.tokens < 1.0 {
+            self.misbehave(addr, 1, now)?;
+            return Err(PeerError::RateLimited);
+        }
+        peer.tokens -= 1.0;
+
+        Ok(())
+    }
+
+    // 累计违规分，超过阈值时断开并封禁，返回是否被封禁
+    pub fn misbehave(&mut self, addr: IpAddr, points: u32, now: i64) -> Result<bool, PeerError> {
+        let peer = self.peers.get_mut(&addr).ok_or(PeerError::UnknownPeer)?;
+        peer.score = peer.score.saturating_add(points);
+        if peer.score < self.config.ban_threshold {
+            return Ok(false);
+        }
+
+        self.peers.remove(&addr);
+        self.bans.ban(addr, now + self.config.ban_secs);
+        self.bans.sweep(now);
+        if let Err(e) = self.bans.save(&self.save_dir) {
+            println!("failed to save ban list: {}", e);
+        }
+
+        Ok(true)
+    }
+
+    fn block_penalty(err: &BlockError) -> u32 {
+        match err {
+            BlockError::Stale | BlockError::TimeTooNew => 0,
+            BlockError::TimeTooOld => 10,
+            BlockError::ForkBeforeCheckpoint { .. } => 20,
+            _ => 100,
+        }
+    }
+
+    fn tx_penalty(err: &TxError) -> u32 {
+        match err {
+            TxError::BadSignature | TxError::Overflow | TxError::TooLarge { .. } => 100,
+            TxError::ZeroAmount | TxError::SelfTransfer => 10,
+            _ => 0,
+        }
+    }
+
+    // 父区块未知的区块先放入孤儿池，父区块到达后再依次连接
+    pub fn process_block(&mut self, chain: &ChainHandle, addr: IpAddr, block: Block, now: i64)
+        -> Result<(), PeerError>
+    {
+        let (tip, _) = chain.tip();
+        if block.header.pre_hash != tip && chain.height_of(&block.header.pre_hash).is_none() {
+            // 与链上区块一样按链的难度校验，不能采用区块自带的 bits
+            let bits = chain.bits();
+            if block.header.bits != bits {
+                self.misbehave(addr, 100, now)?;
+                let err = BlockError::BadBits { expected: bits, got: block.header.bits };
+                return Err(PeerError::Block(err));
+            }
+            let pow = ProofOfWork::new(bits, chain.params().hasher);
+            if !pow.validate(&block) {
+                self.misbehave(addr, 100, now)?;
+                return Err(PeerError::Block(BlockError::BadProofOfWork));
+            }
+            self.orphan_blocks.insert(block.hash.clone(), block);
+            return Ok(());
+        }
+
+        if let Err(err) = chain.submit_block(block) {
+            self.misbehave(addr, Self::block_penalty(&err), now)?;
+            return Err(PeerError::Block(err));
+        }
+
+        // 孤儿区块由其他节点转发而来，失败时不再追究
+        loop {
+            let (tip, _) = chain.tip();
+            let children = self.orphan_blocks.take(|b| b.header.pre_hash == tip);
+            if children.is_empty() {
+                break;
+            }
+            for child in children {
+                let _ = chain.submit_block(child);
+            }
+        }
+        self.retry_orphan_txs(chain);
+
+        Ok(())
+    }
+
+    // nonce 超前的交易先放入孤儿池，等前面的交易到达后重试
+    pub fn process_tx(&mut self, chain: &ChainHandle, addr: IpAddr, tx: Transaction, now: i64)
+        -> Result<(), PeerError>
+    {
+        match chain.submit_tx(tx.clone()) {
+            Ok(()) => {
+                self.retry_orphan_txs(chain);
+                Ok(())
+            },
+            Err(TxError::BadNonce { expected, got }) if got > expected => {
+                self.orphan_txs.insert(tx.hash.clone(), tx);
+                Ok(())
+            },
+            Err(err) => {
+                self.misbehave(addr, Self::tx_penalty(&err), now)?;
+                Err(PeerError::Tx(err))
+            },
+        }
+    }
+
+    fn retry_orphan_txs(&mut self, chain: &ChainHandle) {
+        loop {
+            let mut progress = false;
+            for tx in self.orphan_txs.take(|_| true) {
+                match chain.submit_tx(tx.clone()) {
+                    Ok(()) => progress = true,
+                    Err(TxError::BadNonce { expected, got }) if got > expected =>
+                        self.orphan_txs.insert(tx.hash.clone(), tx),
+                    Err(_) => (),
+                }
+            }
+            if !progress {
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,398 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use serde::{Deserialize, Serialize};
+use utils::hasher::HashAlgo;
+use crate::block::{Block, BlockHeader};
+use crate::chain::ChainHandle;
+use crate::error::{BlockError, PoolError};
+use crate::miner::Miner;
+use crate::pow::{ProofOfWork, MAX_NONCE};
+
+// 每个工作单元分配的 nonce 个数
+pub const NONCE_RANGE: u32 = 1 << 16;
+
+// 矿工发给矿池的请求，一行一个 JSON
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum PoolRequest {
+    Login { worker: String },
+    GetWork,
+    Submit { job_id: u64, nonce: u32 },
+}
+
+// 工作单元：区块头、可用的 nonce 区间和份额难度
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WorkUnit {
+    pub job_id: u64,
+    pub header: BlockHeader,
+    pub nonce_start: u32,
+    pub nonce_end: u32,
+    pub share_bits: u32,
+    pub hasher: HashAlgo,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum PoolResponse {
+    LoggedIn,
+    Work(WorkUnit),
+    Accepted { block_found: bool },
+    Rejected { reason: String },
+}
+
+// 矿池出块的结果，由调用方决定如何输出
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PoolEvent {
+    BlockRejected(BlockError),
+    BlockFound { hash: String, payouts: Vec<(String, u64)> },
+}
+
+// PPLNS 账本：出块奖励按最近 window 个份额中各矿工所占比例分配
+#[derive(Debug, Clone)]
+pub struct Pplns {
+    window: usize,
+    shares: VecDeque<String>,
+    balances: HashMap<String, u64>,
+}
+
+impl Pplns {
+    pub fn new(window: usize) -> Self {
+        Pplns {
+            window: window.max(1),
+            shares: VecDeque::new(),
+            balances: HashMap::new(),
+        }
+    }
+
+    pub fn add_share(&mut self, worker: &str) {
+        self.shares.push_back(worker.to_string());
+        while self.shares.len() > self.window {
+            self.shares.pop_front();
+        }
+    }
+
+    // 整除后的余数归出块矿工，返回本次各矿工所得
+    pub fn pay(&mut self, reward: u64, finder: &str) -> Vec<(String, u64)> {
+        let mut counts: HashMap<&str, u64> = HashMap::new();
+        for worker in self.shares.iter() {
+            *counts.entry(worker).or_insert(0) += 1;
+        }
+
+        let total = self.shares.len() as u64;
+        let mut payouts: HashMap<String, u64> = HashMap::new();
+        let mut paid = 0;
+        for (worker, count) in counts {
+            // 所得不超过 reward，用 u128 计算避免乘法溢出
+            let amount = (reward as u128 * count as u128 / total as u128) as u64;
+            paid += amount;
+            payouts.insert(worker.to_string(), amount);
+        }
+        *payouts.entry(finder.to_string()).or_insert(0) += reward - paid;
+
+        let mut payouts: Vec<(String, u64)> = payouts.into_iter().collect();
+        payouts.sort();
+        for (worker, amount) in payouts.iter() {
+            *self.balances.entry(worker.clone()).or_insert(0) += amount;
+        }
+
+        payouts
+    }
+
+    pub fn balance(&self, worker: &str) -> u64 {
+        self.balances.get(worker).copied().unwrap_or(0)
+    }
+
+    pub fn balances(&self) -> &HashMap<String, u64> {
+        &self.balances
+    }
+}
+
+struct Job {
+    worker: String,
+    block: Block,
+    nonce_start: u32,
+    nonce_end: u32,
+}
+
+struct PoolState {
+    block: Option<Block>, // 当前模板对应的区块，coinbase 付给矿池地址
+    next_nonce: u32,
+    next_job: u64,
+    jobs: HashMap<u64, Job>,
+    submitted: HashSet<(u64, u32)>,
+    ledger: Pplns,
+}
+
+// 矿池：生成区块模板，把 nonce 区间分给各矿工，按份额记账
+#[derive(Clone)]
+pub struct Pool {
+    chain: ChainHandle,
+    miner: Miner,
+    share_bits: u32,
+    state: Arc<Mutex<PoolState>>,
+}
+
+impl Pool {
+    // share_bits 须比区块难度更容易，否则满足区块难度的 nonce 会被当作无效份额拒绝
+    // window 为 PPLNS 统计的份额数
+    pub fn new(chain: ChainHandle, share_bits: u32, window: usize) -> Result<Self, PoolError> {
+        let (params, block_bits) = (chain.params(), chain.bits());
+        let share = ProofOfWork::new(share_bits, params.hasher);
+        if share.target() <= ProofOfWork::new(block_bits, params.hasher).target() {
+            return Err(PoolError::ShareBitsTooHard { share_bits, block_bits });
+        }
+
+        let miner = Miner::new(&params);
+        Ok(Pool {
+            chain,
+            miner,
+            share_bits,
+            state: Arc::new(Mutex::new(PoolState {
+                block: None,
+                next_nonce: 0,
+                next_job: 0,
+                jobs: HashMap::new(),
+                submitted: HashSet::new(),
+                ledger: Pplns::new(window),
+            })),
+        })
+    }
+
+    pub fn ledger(&self) -> Pplns {
+        self.state.lock().unwrap().ledger.clone()
+    }
+
+    // 处理一条请求，worker 为该连接登录的矿工名；份额满足区块难度时一并返回出块结果
+    pub fn handle(&self, worker: &mut Option<String>, req: PoolRequest)
+        -> (PoolResponse, Option<PoolEvent>)
+    {
+        match req {
+            PoolRequest::Login { worker: name } => {
+                if name.is_empty() {
+                    return (Self::reject("empty worker name"), None);
+                }
+                *worker = Some(name);
+                (PoolResponse::LoggedIn, None)
+            },
+            PoolRequest::GetWork => match worker {
+                Some(name) => (PoolResponse::Work(self.get_work(name)), None),
+                None => (Self::reject("not logged in"), None),
+            },
+            PoolRequest::Submit { job_id, nonce } => match worker {
+                Some(name) => self.submit(name, job_id, nonce),
+                None => (Self::reject("not logged in"), None),
+            },
+        }
+    }
+
+    fn reject(reason: &str) -> PoolResponse {
+        PoolResponse::Rejected { reason: reason.to_string() }
+    }
+
+    // 链尾变化或 nonce 用尽时重新生成模板，旧任务作废
+    fn get_work(&self, worker: &str) -> WorkUnit {
+        let mut state = self.state.lock().unwrap();
+        let (tip, _) = self.chain.tip();
+        let stale = match &state.block {
+            Some(block) => block.header.pre_hash != tip,
+            None => true,
+        };
+        if stale || state.next_nonce > MAX_NONCE - NONCE_RANGE {
+            state.block = Some(self.miner.prepare_block(&self.chain.template()));
+            state.next_nonce = 0;
+            state.jobs.clear();
+            state.submitted.clear();
+        }
+
+        let block = state.block.clone().unwrap();
+        let nonce_start = state.next_nonce;
+        let nonce_end = nonce_start + NONCE_RANGE - 1;
+        state.next_nonce = nonce_end + 1;
+        state.next_job += 1;
+        let job_id = state.next_job;
+
+        let work = WorkUnit {
+            job_id,
+            header: block.header.clone(),
+            nonce_start,
+            nonce_end,
+            share_bits: self.share_bits,
+            hasher: self.chain.params().hasher,
+        };
+        state.jobs.insert(job_id, Job {
+            worker: worker.to_string(),
+            block,
+            nonce_start,
+            nonce_end,
+        });
+
+        work
+    }
+
+    // 先按份额难度校验，同时满足区块难度时提交区块并分配奖励
+    fn submit(&self, worker: &str, job_id: u64, nonce: u32)
+        -> (PoolResponse, Option<PoolEvent>)
+    {
+        let mut state = self.state.lock().unwrap();
+        let mut block = match state.jobs.get(&job_id) {
+            Some(job) if job.worker != worker =>
+                return (Self::reject("job belongs to another worker"), None),
+            Some(job) if nonce < job.nonce_start || nonce > job.nonce_end =>
+                return (Self::reject("nonce out of range"), None),
+            Some(job) => job.block.clone(),
+            None => return (Self::reject("unknown or stale job"), None),
+        };
+        if block.header.pre_hash != self.chain.tip().0 {
+            return (Self::reject("unknown or stale job"), None);
+        }
+        if !state.submitted.insert((job_id, nonce)) {
+            return (Self::reject("duplicate share"), None);
+        }
+
+        let hasher = self.chain.params().hasher;
+        block.header.nonce = nonce;
+        if ProofOfWork::new(self.share_bits, hasher).check_header(&block.header).is_none() {
+            return (Self::reject("share above target"), None);
+        }
+        state.ledger.add_share(worker);
+
+        let pow = ProofOfWork::new(block.header.bits, hasher);
+        let hash = match pow.check_header(&block.header) {
+            Some(hash) => hash,
+            None => return (PoolResponse::Accepted { block_found: false }, None),
+        };
+
+        block.hash = hash.clone();
+        let reward = block.tranxs[0].amount;
+        if let Err(e) = self.chain.submit_block(block) {
+            let resp = PoolResponse::Accepted { block_found: false };
+            return (resp, Some(PoolEvent::BlockRejected(e)));
+        }
+
+        let payouts = state.ledger.pay(reward, worker);
+        state.jobs.clear();
+        state.block = None;
+
+        (PoolResponse::Accepted { block_found: true }, Some(PoolEvent::BlockFound { hash, payouts }))
+    }
+
+    // 只监听本机回环地址，不对外暴露
+    pub fn bind(port: u16) -> io::Result<TcpListener> {
+        TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+    }
+
+    // 每个连接一个线程，逐行读取请求并逐行应答，出块结果交给 on_event
+    pub fn serve<F>(&self, listener: TcpListener, on_event: F) -> io::Result<()>
+        where F: Fn(PoolEvent) + Send + Sync + 'static
+    {
+        let on_event = Arc::new(on_event);
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let pool = self.clone();
+            let on_event = on_event.clone();
+            thread::spawn(move || {
+                if let Err(e) = pool.serve_conn(stream, on_event.as_ref()) {
+                    println!("pool connection closed: {}", e);
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    fn serve_conn(&self, stream: TcpStream, on_event: &dyn Fn(PoolEvent)) -> io::Result<()> {
+        let mut writer = stream.try_clone()?;
+        let reader = BufReader::new(stream);
+        let mut worker = None;
+        for line in reader.lines() {
+            let resp = match serde_json::from_str(&line?) {
+                Ok(req) => {
+                    let (resp, event) = self.handle(&mut worker, req);
+                    if let Some(event) = event {
+                        on_event(event);
+                    }
+                    resp
+                },
+                Err(e) => Self::reject(&format!("bad request: {}", e)),
+            };
+            writeln!(writer, "{}", serde_json::to_string(&resp)?)?;
+        }
+
+        Ok(())
+    }
+}
+
+// 矿池矿工：领取工作单元，在分配的 nonce 区间内寻找份额并提交
+pub struct PoolWorker {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    pub shares: u64,
+    pub blocks: u64,
+}
+
+impl PoolWorker {
+    pub fn connect<A: ToSocketAddrs>(addr: A, name: &str) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        let mut worker = PoolWorker {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            shares: 0,
+            blocks: 0,
+        };
+
+        match worker.request(&PoolRequest::Login { worker: name.to_string() })? {
+            PoolResponse::LoggedIn => Ok(worker),
+            resp => Err(Self::unexpected(resp)),
+        }
+    }
+
+    fn unexpected(resp: PoolResponse) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, format!("unexpected response: {:?}", resp))
+    }
+
+    pub fn request(&mut self, req: &PoolRequest) -> io::Result<PoolResponse> {
+        writeln!(self.writer, "{}", serde_json::to_string(req)?)?;
+
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(serde_json::from_str(&line)?)
+    }
+
+    pub fn get_work(&mut self) -> io::Result<WorkUnit> {
+        match self.request(&PoolRequest::GetWork)? {
+            PoolResponse::Work(work) => Ok(work),
+            resp => Err(Self::unexpected(resp)),
+        }
+    }
+
+    // 扫描整个区间，找到区块或任务作废时提前结束，返回是否出块
+    pub fn mine(&mut self, work: &WorkUnit) -> io::Result<bool> {
+        let pow = ProofOfWork::new(work.share_bits, work.hasher);
+        let mut header = work.header.clone();
+        for nonce in work.nonce_start..=work.nonce_end {
+            header.nonce = nonce;
+            if pow.check_header(&header).is_none() {
+                continue;
+            }
+
+            let req = PoolRequest::Submit { job_id: work.job_id, nonce };
+            match self.request(&req)? {
+                PoolResponse::Accepted { block_found } => {
+                    self.shares += 1;
+                    if block_found {
+                        self.blocks += 1;
+                        return Ok(true);
+                    }
+                },
+                PoolResponse::Rejected { .. } => return Ok(false),
+                resp => return Err(Self::unexpected(resp)),
+            }
+        }
+
+        Ok(false)
+    }
+}
@@ -1,17 +1,17 @@
-use std::thread;
-use std::time::Duration;
 use bigint::U256;
-use utils::serializer::{serialize, hash_str, hash_u8};
-use crate::block::Block;
+use utils::serializer::serialize;
+use utils::hasher::{ChainHasher, HashAlgo};
+use crate::block::{Block, BlockHeader};
 
-const MAX_NONCE: u32 = 0x7FFFFFFF;
+pub const MAX_NONCE: u32 = 0x7FFFFFFF;
 
 pub struct ProofOfWork {
     target: U256,
+    hasher: HashAlgo,
 }
 
 impl ProofOfWork {
-    pub fn new(bits: u32) -> Self {
+    pub fn new(bits: u32, hasher: HashAlgo) -> Self {
         let (mant, expt) = {
             let unshifted_expt = bits >> 24;
             if unshifted_expt <= 3 {
@@ -24,27 +24,33 @@ impl ProofOfWork {
         if mant > 0x7FFFFF {
             Self {
                 target: Default::default(),
+                hasher,
             }
         } else {
             Self {
                 target: U256::from(mant as u64) << (expt as usize),
+                hasher,
             }
         }
     }
 
-    pub fn run(&self, mut block: &mut Block) {
+    // 目标值越大，难度越低
+    pub fn target(&self) -> U256 {
+        self.target
+    }
+
+    pub fn run(&self, block: &mut Block) {
         println!("Start mining .... ");
-        thread::sleep(Duration::from_secs(3));
 
         let mut nonce: u32 = 0;
         while nonce <= MAX_NONCE {
-            let header_ser = Self::prepare_data(&mut block, nonce);
+            let header_ser = Self::prepare_data(block, nonce);
             let mut hash_u: [u8; 32] = [0; 32];
-            hash_u8(&header_ser, &mut hash_u);
+            self.hasher.hash_u8(&header_ser, &mut hash_u);
 
             let hash_int = U256::from(hash_u);
             if hash_int <= self.target {
-                block.hash = hash_str(&header_ser);
+                block.hash = self.hasher.hash_str(&header_ser);
                 println!("Produced a new block!");
                 return;
             }
@@ -53,6 +59,32 @@ impl ProofOfWork {
         }
     }
 
+    pub fn validate(&self, block: &Block) -> bool {
+        match self.check_header(&block.header) {
+            Some(hash) => hash == block.hash,
+            None => false,
+        }
+    }
+
+    // 只凭区块头即可校验工作量，满足难度时返回区块哈希
+    pub fn check_header(&self, header: &BlockHeader) -> Option<String> {
+        let header_ser = serialize(header);
+        let mut hash_u: [u8; 32] = [0; 32];
+        self.hasher.hash_u8(&header_ser, &mut hash_u);
+
+        let hash_int = U256::from(hash_u);
+        if hash_int <= self.target {
+            Some(self.hasher.hash_str(&header_ser))
+        } else {
+            None
+        }
+    }
+
+    pub fn header_hash(&self, header: &BlockHeader) -> String {
+        let header_ser = serialize(header);
+        self.hasher.hash_str(&header_ser)
+    }
+
     pub fn prepare_data(block: &mut Block, nonce: u32) -> Vec<u8> {
         block.header.nonce = nonce;
         serialize(&(block.header))
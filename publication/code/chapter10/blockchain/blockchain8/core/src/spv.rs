@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use utils::hasher::HashAlgo;
+use crate::block::BlockHeader;
+use crate::blockchain::BlockChain;
+use crate::bloom::BloomFilter;
+use crate::chain::ChainHandle;
+use crate::error::SpvError;
+use crate::message::{Message, MerkleBlock, MAX_HEADERS};
+use crate::params::NetworkParams;
+use crate::pow::ProofOfWork;
+use crate::transaction::Transaction;
+
+// 轻节点：只保存区块头，交易由全节点按过滤器推送并附带默克尔证明
+pub struct LightClient {
+    hasher: HashAlgo,
+    bits: u32,
+    chain_id: u32,
+    checkpoints: &'static [(u64, &'static str)],
+    headers: Vec<BlockHeader>,
+    hashes: Vec<String>,
+    index: HashMap<String, usize>,
+    filter: BloomFilter,
+    pub txs: Vec<(u64, Transaction)>,
+}
+
+impl LightClient {
+    pub fn new(params: &NetworkParams, filter: BloomFilter) -> Self {
+        let genesis = BlockChain::genesis_block(params).header;
+        let hasher = params.hasher;
+        let hash = ProofOfWork::new(genesis.bits, hasher).header_hash(&genesis);
+
+        let mut index = HashMap::new();
+        index.insert(hash.clone(), 0);
+        LightClient {
+            hasher,
+            bits: params.init_bits,
+            chain_id: params.chain_id,
+            checkpoints: params.checkpoints,
+            headers: vec![genesis],
+            hashes: vec![hash],
+            index,
+            filter,
+            txs: Vec::new(),
+        }
+    }
+
+    pub fn tip(&self) -> (String, u64) {
+        let height = self.hashes.len() - 1;
+        (self.hashes[height].clone(), height as u64)
+    }
+
+    pub fn header(&self, height: u64) -> Option<&BlockHeader> {
+        self.headers.get(height as usize)
+    }
+
+    // 最近 10 个区块逐个列出，之后步长加倍，最后总是包含创世区块
+    pub fn locator(&self) -> Vec<String> {
+        let mut locator = Vec::new();
+        let mut step = 1;
+        let mut height = self.hashes.len() as i64 - 1;
+        while height > 0 {
+            locator.push(self.hashes[height as usize].clone());
+            if locator.len() >= 10 {
+                step *= 2;
+            }
+            height -= step;
+        }
+        locator.push(self.hashes[0].clone());
+
+        locator
+    }
+
+    pub fn get_headers(&self) -> Message {
+        Message::GetHeaders { locator: self.locator(), stop_hash: "".to_string() }
+    }
+
+    pub fn filter_load(&self) -> Message {
+        Message::FilterLoad(self.filter.clone())
+    }
+
+    // 校验区块头的工作量和前后链接关系，返回新增的区块头个数
+    pub fn add_headers(&mut self, headers: Vec<BlockHeader>) -> Result<usize, SpvError> {
+        let mut added = 0;
+        for header in headers {
+            // 难度由网络参数决定，否则对方可以发来不需要算力的区块头链
+            if header.bits != self.bits {
+                return Err(SpvError::BadBits { expected: self.bits, got: header.bits });
+            }
+            let pow = ProofOfWork::new(self.bits, self.hasher);
+            let hash = match pow.check_header(&header) {
+                Some(hash) => hash,
+                None => return Err(SpvError::BadProofOfWork),
+            };
+
+            if self.index.contains_key(&hash) {
+                continue;
+            }
+
+            // 区块头须与检查点一致，且不接受检查点之下的分叉
+            let (tip, height) = self.tip();
+            if header.pre_hash != tip {
+                let last = self.checkpoints.last().map(|(h, _)| *h);
+                return match (self.index.get(&header.pre_hash), last) {
+                    (Some(&pos), Some(last)) if (pos as u64) < last =>
+                        Err(SpvError::ForkBelowCheckpoint { hash, height: last }),
+                    _ => Err(SpvError::Disconnected { hash }),
+                };
+            }
+            let mismatch = self.checkpoints.iter()
+                               .any(|(h, cp)| *h == height + 1 && *cp != hash);
+            if mismatch {
+                return Err(SpvError::CheckpointMismatch { hash, height: height + 1 });
+            }
+
+            self.index.insert(hash.clone(), self.hashes.len());
+            self.hashes.push(hash);
+            self.headers.push(header);
+            added += 1;
+        }
+
+        Ok(added)
+    }
+
+    // 区块头必须已同步，每笔交易都要通过默克尔证明
+    pub fn process_merkle_block(&mut self, merkle_block: MerkleBlock)
+        -> Result<usize, SpvError>
+    {
+        let header = &merkle_block.header;
+        let hash = ProofOfWork::new(header.bits, self.hasher).header_hash(header);
+        let height = match self.index.get(&hash) {
+            Some(height) => *height,
+            None => return Err(SpvError::UnknownBlock { hash }),
+        };
+
+        let mut found = 0;
+        for matched in merkle_block.matches {
+            let mut tx = matched.tx.clone();
+            tx.set_hash(self.hasher);
+            if tx.hash != matched.tx.hash || tx.chain_id != self.chain_id {
+                return Err(SpvError::BadTxHash { hash: matched.tx.hash });
+            }
+
+            if matched.proof.index >= merkle_block.total_txs
+                || !matched.proof.verify(&tx.hash, &header.txs_hash, self.hasher) {
+                return Err(SpvError::BadMerkleProof { hash: tx.hash });
+            }
+
+            if !self.txs.iter().any(|(_, t)| t.hash == tx.hash) {
+                self.txs.push((height as u64, tx));
+                found += 1;
+            }
+        }
+
+        Ok(found)
+    }
+
+    // 收到区块头后继续请求区块头，并为新区块请求过滤后的交易
+    pub fn handle(&mut self, msg: Message) -> Result<Vec<Message>, SpvError> {
+        let mut replies = Vec::new();
+        match msg {
+            Message::Headers(headers) => {
+                let full = headers.len() >= MAX_HEADERS;
+                let start = self.hashes.len();
+                self.add_headers(headers)?;
+
+                for hash in self.hashes[start..].iter() {
+                    replies.push(Message::GetMerkleBlock(hash.clone()));
+                }
+                if full {
+                    replies.push(self.get_headers());
+                }
+            },
+            Message::MerkleBlock(merkle_block) => {
+                self.process_merkle_block(merkle_block)?;
+            },
+            Message::NotFound(_) => (),
+            _ => return Err(SpvError::UnexpectedMessage),
+        }
+
+        Ok(replies)
+    }
+}
+
+// 全节点为某个轻节点连接保存的会话状态
+pub struct SpvSession {
+    chain: ChainHandle,
+    filter: Option<BloomFilter>,
+}
+
+impl SpvSession {
+    pub fn new(chain: ChainHandle) -> Self {
+        SpvSession { chain, filter: None }
+    }
+
+    pub fn handle(&mut self, msg: Message) -> Result<Vec<Message>, SpvError> {
+        let mut replies = Vec::new();
+        match msg {
+            Message::GetHeaders { locator, stop_hash } => {
+                let headers = self.chain.headers_after(&locator, &stop_hash, MAX_HEADERS);
+                replies.push(Message::Headers(headers));
+            },
+            Message::FilterLoad(filter) => {
+                if !filter.is_valid() {
+                    return Err(SpvError::FilterTooLarge);
+                }
+                self.filter = Some(filter);
+            },
+            Message::FilterClear => self.filter = None,
+            Message::GetMerkleBlock(hash) => {
+                let filter = match &self.filter {
+                    Some(filter) => filter,
+                    None => return Err(SpvError::NoFilter),
+                };
+
+                match self.chain.merkle_block(&hash, filter) {
+                    Some(merkle_block) => replies.push(Message::MerkleBlock(merkle_block)),
+                    None => replies.push(Message::NotFound(hash)),
+                }
+            },
+            _ => return Err(SpvError::UnexpectedMessage),
+        }
+
+        Ok(replies)
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::block::Block;
+use crate::error::{BlockError, TxError};
+use crate::params::NetworkParams;
+use crate::transaction::Transaction;
+
+// 链上账户状态，由区块中的交易推导而来
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountState {
+    pub balance: u64,
+    pub nonce: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct State {
+    accounts: HashMap<String, AccountState>,
+    #[serde(skip, default = "NetworkParams::mainnet")]
+    params: NetworkParams,
+}
+
+impl State {
+    pub fn new(params: &NetworkParams) -> Self {
+        State {
+            accounts: HashMap::new(),
+            params: params.clone(),
+        }
+    }
+
+    // 数据库中的状态不含网络参数，读出后重新设置
+    pub fn with_params(mut self, params: &NetworkParams) -> Self {
+        self.params = params.clone();
+        self
+    }
+
+    pub fn params(&self) -> &NetworkParams {
+        &self.params
+    }
+
+    // 未出现过的地址按网络参数的初始余额计
+    pub fn get(&self, address: &str) -> AccountState {
+        self.accounts.get(address).copied().unwrap_or(self.fresh())
+    }
+
+    fn fresh(&self) -> AccountState {
+        AccountState { balance: self.params.init_balance, nonce: 0 }
+    }
+
+    fn account_mut(&mut self, address: &str) -> &mut AccountState {
+        let fresh = self.fresh();
+        self.accounts.entry(address.to_string()).or_insert(fresh)
+    }
+
+    // 地址是否在链上出现过
+    pub fn contains(&self, address: &str) -> bool {
+        self.accounts.contains_key(address)
+    }
+
+    pub fn balance(&self, address: &str) -> u64 {
+        self.get(address).balance
+    }
+
+    pub fn nonce(&self, address: &str) -> u64 {
+        self.get(address).nonce
+    }
+
+    pub fn apply_tx(&mut self, tx: &Transaction) -> Result<(), TxError> {
+        self.connect_tx(tx, true)
+    }
+
+    fn connect_tx(&mut self, tx: &Transaction, verify_sign: bool) -> Result<(), TxError> {
+        if verify_sign {
+            tx.validate(self)?;
+        } else {
+            tx.validate_trusted(self)?;
+        }
+
+        // 先检查溢出再修改，出错时状态保持不变；取消交易的收款方即发送方
+        let total = tx.amount.checked_add(tx.fee).ok_or(TxError::Overflow)?;
+        self.balance(&tx.to).checked_add(tx.amount).ok_or(TxError::Overflow)?;
+
+        let from = self.account_mut(&tx.from);
+        from.balance -= total;
+        from.nonce = tx.nonce;
+        self.account_mut(&tx.to).balance += tx.amount;
+
+        Ok(())
+    }
+
+    pub fn apply_block(&mut self, block: &Block) -> Result<(), BlockError> {
+        self.connect_block(block, true)
+    }
+
+    // 检查点之下的区块只检查余额和 nonce，不验证签名
+    pub fn apply_checkpointed(&mut self, block: &Block) -> Result<(), BlockError> {
+        self.connect_block(block, false)
+    }
+
+    // 第一笔交易为 coinbase，其余为普通转账；出错时状态保持不变
+    fn connect_block(&mut self, block: &Block, verify_sign: bool) -> Result<(), BlockError> {
+        let mut next = self.clone();
+        let mut fees: u64 = 0;
+        for tx in block.tranxs.iter().skip(1) {
+            next.connect_tx(tx, verify_sign)
+                .map_err(|err| BlockError::Tx { hash: tx.hash.clone(), err })?;
+            fees = fees.checked_add(tx.fee).ok_or(BlockError::BadCoinbase)?;
+        }
+
+        let coinbase = block.tranxs.first().ok_or(BlockError::BadCoinbase)?;
+        let max_amount = fees.checked_add(self.params.reward).ok_or(BlockError::BadCoinbase)?;
+        if coinbase.from != "0x0000" || coinbase.amount > max_amount {
+            return Err(BlockError::BadCoinbase);
+        }
+        let to = next.account_mut(&coinbase.to);
+        to.balance = to.balance.checked_add(coinbase.amount).ok_or(BlockError::BadCoinbase)?;
+
+        *self = next;
+        Ok(())
+    }
+}
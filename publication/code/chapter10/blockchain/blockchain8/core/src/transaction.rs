@@ -1,9 +1,18 @@
-use serde::Serialize;
-use utils::serializer::{serialize, hash_str};
+use serde::{Deserialize, Serialize};
+use utils::serializer::serialize;
+use utils::hasher::{ChainHasher, HashAlgo};
+use crate::error::TxError;
+use crate::params::NetworkParams;
+use crate::state::State;
 
-#[derive(Serialize, Debug, Clone)]
+// 小于该值的 lock_time 表示区块高度，否则表示 Unix 时间
+pub const LOCKTIME_THRESHOLD: u64 = 500_000_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Transaction {
+    pub chain_id: u32,
     pub nonce: u64,
+    pub lock_time: u64,
     pub amount: u64,
     pub fee: u64,
     pub from: String,
@@ -15,10 +24,24 @@ pub struct Transaction {
 impl Transaction {
     pub fn new(from: String, to: String,
                amount: u64, fee: u64,
-               nonce: u64, sign: String) -> Self
+               nonce: u64, sign: String,
+               params: &NetworkParams) -> Self
+    {
+        Self::new_locked(from, to, amount, fee, nonce, sign, 0, params)
+    }
+
+    // lock_time 之前不能被打包，0 表示不锁定
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_locked(from: String, to: String,
+                      amount: u64, fee: u64,
+                      nonce: u64, sign: String,
+                      lock_time: u64,
+                      params: &NetworkParams) -> Self
     {
         let mut tx = Transaction {
+            chain_id: params.chain_id,
             nonce,
+            lock_time,
             amount,
             fee,
             from,
@@ -26,13 +49,99 @@ impl Transaction {
             sign,
             hash: "".to_string(),
         };
-        tx.set_hash();
+        tx.set_hash(params.hasher);
 
         tx
     }
 
-    pub fn set_hash(&mut self) {
+    // chain_id 参与哈希，防止交易被重放到其他网络
+    pub fn set_hash(&mut self, hasher: HashAlgo) {
+        self.hash = "".to_string();
         let txs_ser = serialize(&self);
-        self.hash = hash_str(&txs_ser);
+        self.hash = hasher.hash_str(&txs_ser);
+    }
+
+    // 交易序列化后的字节数，用于计算区块重量和手续费率
+    pub fn size(&self) -> usize {
+        serialize(self).len()
+    }
+
+    // 手续费率(每 1000 字节)，手续费极大时饱和而不溢出
+    pub fn fee_rate(&self) -> u64 {
+        self.fee.saturating_mul(1000) / self.size() as u64
+    }
+
+    // height 为打包区块的高度，mtp 为前一区块的中位时间
+    pub fn is_final(&self, height: u64, mtp: i64) -> bool {
+        if self.lock_time == 0 {
+            return true;
+        }
+
+        if self.lock_time < LOCKTIME_THRESHOLD {
+            self.lock_time <= height
+        } else {
+            mtp >= 0 && self.lock_time <= mtp as u64
+        }
+    }
+
+    pub fn check_final(&self, height: u64, mtp: i64) -> Result<(), TxError> {
+        if self.is_final(height, mtp) {
+            Ok(())
+        } else {
+            Err(TxError::NotFinal { lock_time: self.lock_time })
+        }
+    }
+
+    // 按账户状态校验普通转账，coinbase 交易由区块校验单独处理
+    pub fn validate(&self, state: &State) -> Result<(), TxError> {
+        self.check(state, true)
+    }
+
+    // 检查点之下的区块已被信任，跳过签名验证
+    pub fn validate_trusted(&self, state: &State) -> Result<(), TxError> {
+        self.check(state, false)
+    }
+
+    // 取消交易：转给自己的零额交易，只消耗 nonce 并支付手续费
+    pub fn is_cancel(&self) -> bool {
+        self.amount == 0 && self.from == self.to
+    }
+
+    fn check(&self, state: &State, verify_sign: bool) -> Result<(), TxError> {
+        if !self.is_cancel() {
+            if self.amount == 0 {
+                return Err(TxError::ZeroAmount);
+            }
+            if self.from == self.to {
+                return Err(TxError::SelfTransfer);
+            }
+        }
+
+        let params = state.params();
+        let size = self.size();
+        if size > params.max_tx_size {
+            return Err(TxError::TooLarge { size, max: params.max_tx_size });
+        }
+
+        if verify_sign {
+            let mut tx = self.clone();
+            tx.set_hash(params.hasher);
+            if tx.hash != self.hash || self.chain_id != params.chain_id {
+                return Err(TxError::BadSignature);
+            }
+        }
+
+        let account = state.get(&self.from);
+        let need = self.amount.checked_add(self.fee).ok_or(TxError::Overflow)?;
+        if need > account.balance {
+            return Err(TxError::InsufficientFunds { need, have: account.balance });
+        }
+
+        let expected = account.nonce.checked_add(1).ok_or(TxError::Overflow)?;
+        if self.nonce != expected {
+            return Err(TxError::BadNonce { expected, got: self.nonce });
+        }
+
+        Ok(())
     }
 }
@@ -0,0 +1,344 @@
+use std::fmt;
+use std::str::FromStr;
+use hmac::{Hmac, Mac};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::SecretKey;
+use k256::elliptic_curve::ff::PrimeField;
+use k256::{FieldBytes, Scalar};
+use pbkdf2::pbkdf2_hmac;
+use rand::RngCore;
+use sha2::{Digest, Sha256, Sha512};
+use utils::hasher::ChainHasher;
+use crate::chain::ChainHandle;
+use crate::error::WalletError;
+use crate::params::NetworkParams;
+use crate::wordlist::WORDS;
+
+type HmacSha512 = Hmac<Sha512>;
+
+pub const HARDENED: u32 = 0x8000_0000;
+pub const GAP_LIMIT: u32 = 20; // 连续这么多个地址未使用即停止扫描
+const PBKDF2_ROUNDS: u32 = 2048;
+
+// BIP39 助记词：熵加校验和，每 11 位对应词表中的一个单词
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mnemonic {
+    words: Vec<&'static str>,
+}
+
+impl Mnemonic {
+    // 熵为 16 到 32 字节且是 4 的倍数，对应 12 到 24 个单词
+    pub fn from_entropy(entropy: &[u8]) -> Result<Self, WalletError> {
+        let len = entropy.len();
+        if !(16..=32).contains(&len) || !len.is_multiple_of(4) {
+            return Err(WalletError::BadEntropy(len));
+        }
+
+        let checksum = Sha256::digest(entropy)[0];
+        let mut bits: Vec<bool> = entropy.iter()
+                                         .flat_map(|b| (0..8).rev().map(move |i| b >> i & 1 == 1))
+                                         .collect();
+        bits.extend((0..len / 4).map(|i| checksum >> (7 - i) & 1 == 1));
+
+        let words = bits.chunks(11)
+                        .map(|c| c.iter().fold(0, |acc, &bit| acc << 1 | bit as usize))
+                        .map(|i| WORDS[i])
+                        .collect();
+
+        Ok(Mnemonic { words })
+    }
+
+    pub fn generate(word_count: usize) -> Result<Self, WalletError> {
+        if !(12..=24).contains(&word_count) || !word_count.is_multiple_of(3) {
+            return Err(WalletError::BadLength(word_count));
+        }
+
+        let mut entropy = vec![0u8; word_count * 4 / 3];
+        rand::thread_rng().fill_bytes(&mut entropy);
+        Self::from_entropy(&entropy)
+    }
+
+    // 校验单词和校验和，单词之间可以有多个空白
+    pub fn parse(phrase: &str) -> Result<Self, WalletError> {
+        let mut indexes = Vec::new();
+        for word in phrase.split_whitespace() {
+            let word = word.to_lowercase();
+            match WORDS.binary_search(&word.as_str()) {
+                Ok(i) => indexes.push(i),
+                Err(_) => return Err(WalletError::UnknownWord(word)),
+            }
+        }
+
+        let count = indexes.len();
+        if !(12..=24).contains(&count) || !count.is_multiple_of(3) {
+            return Err(WalletError::BadLength(count));
+        }
+
+        let bits: Vec<bool> = indexes.iter()
+                                     .flat_map(|i| (0..11).rev().map(move |b| i >> b & 1 == 1))
+                                     .collect();
+        let entropy_bits = count * 11 * 32 / 33;
+        let entropy: Vec<u8> = bits[..entropy_bits].chunks(8)
+                                                   .map(|c| c.iter().fold(0, |acc, &bit| acc << 1 | bit as u8))
+                                                   .collect();
+
+        let mnemonic = Self::from_entropy(&entropy)?;
+        if mnemonic.words.iter().zip(indexes.iter()).any(|(w, &i)| *w != WORDS[i]) {
+            return Err(WalletError::BadChecksum);
+        }
+
+        Ok(mnemonic)
+    }
+
+    pub fn phrase(&self) -> String {
+        self.words.join(" ")
+    }
+
+    // 种子 = PBKDF2-HMAC-SHA512(助记词, "mnemonic" + 口令, 2048 轮)
+    pub fn to_seed(&self, passphrase: &str) -> [u8; 64] {
+        let salt = format!("mnemonic{}", passphrase);
+        let mut seed = [0u8; 64];
+        pbkdf2_hmac::<Sha512>(self.phrase().as_bytes(), salt.as_bytes(), PBKDF2_ROUNDS, &mut seed);
+        seed
+    }
+}
+
+// 派生路径，如 m/44'/1'/0'/0/5，带 ' 或 h 的为强化派生
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivationPath(pub Vec<u32>);
+
+impl FromStr for DerivationPath {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || WalletError::BadPath(s.to_string());
+        let mut parts = s.split('/');
+        if parts.next() != Some("m") {
+            return Err(bad());
+        }
+
+        let mut path = Vec::new();
+        for part in parts {
+            let (num, hardened) = match part.strip_suffix(&['\'', 'h', 'H'][..]) {
+                Some(num) => (num, true),
+                None => (part, false),
+            };
+            let index: u32 = num.parse().map_err(|_| bad())?;
+            if index >= HARDENED {
+                return Err(bad());
+            }
+            path.push(if hardened { index | HARDENED } else { index });
+        }
+
+        Ok(DerivationPath(path))
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "m")?;
+        for index in self.0.iter() {
+            if index & HARDENED != 0 {
+                write!(f, "/{}'", index & !HARDENED)?;
+            } else {
+                write!(f, "/{}", index)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// BIP32 扩展私钥：私钥加链码
+#[derive(Clone, PartialEq, Eq)]
+pub struct ExtendedKey {
+    pub key: [u8; 32],
+    pub chain_code: [u8; 32],
+    pub depth: u8,
+    pub child_number: u32,
+}
+
+impl fmt::Debug for ExtendedKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ExtendedKey")
+         .field("depth", &self.depth)
+         .field("child_number", &self.child_number)
+         .finish()
+    }
+}
+
+impl ExtendedKey {
+    pub fn master(seed: &[u8]) -> Result<Self, WalletError> {
+        let i = Self::hmac(b"Bitcoin seed", &[seed]);
+        let (il, ir) = i.split_at(32);
+        if bool::from(Self::scalar(il)?.is_zero()) {
+            return Err(WalletError::InvalidKey);
+        }
+
+        let mut key = [0u8; 32];
+        let mut chain_code = [0u8; 32];
+        key.copy_from_slice(il);
+        chain_code.copy_from_slice(ir);
+        Ok(ExtendedKey { key, chain_code, depth: 0, child_number: 0 })
+    }
+
+    fn hmac(key: &[u8], data: &[&[u8]]) -> Vec<u8> {
+        let mut mac = HmacSha512::new_from_slice(key).expect("HMAC accepts any key length");
+        for d in data {
+            mac.update(d);
+        }
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    // 必须小于曲线的阶 n
+    fn scalar(bytes: &[u8]) -> Result<Scalar, WalletError> {
+        let repr = FieldBytes::clone_from_slice(bytes);
+        Option::from(Scalar::from_repr(repr)).ok_or(WalletError::InvalidKey)
+    }
+
+    // 压缩格式公钥，33 字节
+    pub fn public_key(&self) -> Vec<u8> {
+        let secret = SecretKey::from_slice(&self.key).expect("key checked on derivation");
+        secret.public_key().to_encoded_point(true).as_bytes().to_vec()
+    }
+
+    pub fn derive_child(&self, index: u32) -> Result<Self, WalletError> {
+        let i = if index & HARDENED != 0 {
+            Self::hmac(&self.chain_code, &[&[0u8], &self.key, &index.to_be_bytes()])
+        } else {
+            Self::hmac(&self.chain_code, &[&self.public_key(), &index.to_be_bytes()])
+        };
+        let (il, ir) = i.split_at(32);
+
+        let child = Self::scalar(il)? + Self::scalar(&self.key)?;
+        if bool::from(child.is_zero()) {
+            return Err(WalletError::InvalidKey);
+        }
+
+        let mut key = [0u8; 32];
+        let mut chain_code = [0u8; 32];
+        key.copy_from_slice(&child.to_repr());
+        chain_code.copy_from_slice(ir);
+        Ok(ExtendedKey {
+            key,
+            chain_code,
+            depth: self.depth + 1,
+            child_number: index,
+        })
+    }
+
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<Self, WalletError> {
+        path.0.iter().try_fold(self.clone(), |key, &index| key.derive_child(index))
+    }
+
+    // 地址取公钥哈希的后 20 字节
+    pub fn address(&self, params: &NetworkParams) -> String {
+        let hash = params.hasher.hash_str(&self.public_key());
+        format!("0x{}", &hash[hash.len() - 40..])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressKind {
+    Receive = 0,
+    Change = 1,
+}
+
+// 分层确定性钱包，路径为 m/44'/<chain_id>'/0'/<0 收款 | 1 找零>/<序号>
+pub struct HdWallet {
+    mnemonic: Mnemonic,
+    account: ExtendedKey,
+    params: NetworkParams,
+    next: [u32; 2], // 收款和找零链上下一个未使用的序号
+}
+
+impl HdWallet {
+    pub fn new(params: &NetworkParams) -> Result<Self, WalletError> {
+        Self::from_mnemonic(Mnemonic::generate(12)?, "", params)
+    }
+
+    pub fn from_mnemonic(mnemonic: Mnemonic, passphrase: &str, params: &NetworkParams)
+        -> Result<Self, WalletError>
+    {
+        let master = ExtendedKey::master(&mnemonic.to_seed(passphrase))?;
+        let path = DerivationPath(vec![44 | HARDENED, params.chain_id | HARDENED, HARDENED]);
+        let account = master.derive_path(&path)?;
+
+        Ok(HdWallet {
+            mnemonic,
+            account,
+            params: params.clone(),
+            next: [0, 0],
+        })
+    }
+
+    // 只凭助记词恢复，并按间隔上限扫描链上已使用的地址
+    pub fn restore(phrase: &str, passphrase: &str, chain: &ChainHandle, gap_limit: u32)
+        -> Result<Self, WalletError>
+    {
+        let mnemonic = Mnemonic::parse(phrase)?;
+        let mut wallet = Self::from_mnemonic(mnemonic, passphrase, &chain.params())?;
+        wallet.discover(|addr| chain.address_used(addr), gap_limit)?;
+        Ok(wallet)
+    }
+
+    pub fn mnemonic(&self) -> &Mnemonic {
+        &self.mnemonic
+    }
+
+    pub fn path(&self, kind: AddressKind, index: u32) -> DerivationPath {
+        let chain_id = self.params.chain_id;
+        DerivationPath(vec![44 | HARDENED, chain_id | HARDENED, HARDENED, kind as u32, index])
+    }
+
+    pub fn key(&self, kind: AddressKind, index: u32) -> Result<ExtendedKey, WalletError> {
+        self.account.derive_child(kind as u32)?.derive_child(index)
+    }
+
+    pub fn address(&self, kind: AddressKind, index: u32) -> Result<String, WalletError> {
+        Ok(self.key(kind, index)?.address(&self.params))
+    }
+
+    pub fn next_index(&self, kind: AddressKind) -> u32 {
+        self.next[kind as usize]
+    }
+
+    pub fn next_address(&mut self, kind: AddressKind) -> Result<String, WalletError> {
+        let index = self.next[kind as usize];
+        let address = self.address(kind, index)?;
+        self.next[kind as usize] = index + 1;
+        Ok(address)
+    }
+
+    // 已分配出去的全部地址
+    pub fn addresses(&self) -> Result<Vec<String>, WalletError> {
+        let mut addresses = Vec::new();
+        for kind in [AddressKind::Receive, AddressKind::Change].iter() {
+            for index in 0..self.next[*kind as usize] {
+                addresses.push(self.address(*kind, index)?);
+            }
+        }
+        Ok(addresses)
+    }
+
+    // 连续 gap_limit 个地址未使用时停止，下一个序号排在最后一个已用地址之后
+    pub fn discover<F: Fn(&str) -> bool>(&mut self, used: F, gap_limit: u32)
+        -> Result<(), WalletError>
+    {
+        for kind in [AddressKind::Receive, AddressKind::Change].iter() {
+            let (mut index, mut gap) = (0, 0);
+            let mut next = 0;
+            while gap < gap_limit {
+                if used(&self.address(*kind, index)?) {
+                    next = index + 1;
+                    gap = 0;
+                } else {
+                    gap += 1;
+                }
+                index += 1;
+            }
+            self.next[*kind as usize] = self.next[*kind as usize].max(next);
+        }
+
+        Ok(())
+    }
+}
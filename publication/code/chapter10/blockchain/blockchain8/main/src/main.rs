@@ -1,40 +1,313 @@
+use std::error::Error;
+use std::num::ParseIntError;
+use std::{process, thread};
+use clap::{Parser, Subcommand};
 use core::account::Account;
-use core::transaction::Transaction;
+use core::blockchain::BlockChain;
+use core::chain::ChainHandle;
+use core::error::TxError;
+use core::explorer::Explorer;
+use core::fees::EstimateMode;
+use core::inspect::{ChainInspector, Format, Query};
 use core::mine::Mine;
+use core::params::NetworkParams;
+use core::pool::{Pool, PoolEvent, PoolWorker};
+
+/// A toy blockchain node
+#[derive(Parser, Debug)]
+#[clap(version, about, long_about = None)]
+struct Args {
+    /// Network to use: mainnet, testnet or regtest
+    #[clap(short, long, default_value = "mainnet")]
+    network: String,
+
+    /// Keep only the most recent <DEPTH> full blocks, deleting older ones
+    #[clap(long, value_name = "DEPTH")]
+    prune: Option<u64>,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Mine a few blocks with demo transfers (default)
+    Demo,
+
+    /// Print blocks stored in the database
+    Printchain {
+        /// First height to print
+        #[clap(long)]
+        from: Option<u64>,
+
+        /// Last height to print
+        #[clap(long)]
+        to: Option<u64>,
+
+        /// Only show transactions involving this address
+        #[clap(long)]
+        address: Option<String>,
+
+        /// Output format: table, json or csv
+        #[clap(long, default_value = "table")]
+        format: Format,
+
+        /// Print summary statistics instead of blocks
+        #[clap(long)]
+        stats: bool,
+    },
+
+    /// Queue a transfer in the stored mempool
+    Send {
+        /// Sender address
+        #[clap(long)]
+        from: String,
+
+        /// Receiver address
+        #[clap(long)]
+        to: String,
+
+        /// Amount to send
+        #[clap(long)]
+        amount: u64,
+
+        /// Fee of the transfer
+        #[clap(long, default_value = "1")]
+        fee: u64,
+    },
+
+    /// Replace one of your pending transfers with a higher fee
+    BumpFee {
+        /// Sender of the pending transfer
+        #[clap(long)]
+        from: String,
+
+        /// Nonce of the pending transfer
+        #[clap(long)]
+        nonce: u64,
+
+        /// New fee, must be higher
+        #[clap(long)]
+        fee: u64,
+    },
+
+    /// Cancel one of your pending transfers before it is mined
+    Cancel {
+        /// Sender of the pending transfer
+        #[clap(long)]
+        from: String,
+
+        /// Nonce of the pending transfer
+        #[clap(long)]
+        nonce: u64,
+
+        /// Fee of the cancelling transaction, must be higher
+        #[clap(long)]
+        fee: u64,
+    },
+
+    /// Mine one block from the stored mempool on top of the stored chain
+    Mine,
+
+    /// Serve a read-only block explorer for the stored chain on localhost
+    Explorer {
+        /// Port to listen on (127.0.0.1 only)
+        #[clap(long, default_value = "8080")]
+        port: u16,
+    },
+
+    /// Run a mining pool on localhost that hands out nonce ranges to workers
+    Pool {
+        /// Port to listen on (127.0.0.1 only)
+        #[clap(long, default_value = "3333")]
+        port: u16,
+
+        /// Share difficulty in hex, must be easier than the block difficulty
+        #[clap(long, default_value = "0x2100FFFF", parse(try_from_str = parse_bits))]
+        share_bits: u32,
+
+        /// Number of recent shares that split each block reward
+        #[clap(long, default_value = "100")]
+        window: usize,
+    },
+
+    /// Mine shares for a pool until interrupted
+    Worker {
+        /// Pool address
+        #[clap(long, default_value = "127.0.0.1:3333")]
+        addr: String,
+
+        /// Worker name credited for the shares
+        #[clap(long)]
+        name: String,
+    },
+}
 
 fn main() {
-    let mut user1 = Account::new("0xabcd".to_string(), "Kim".to_string());
-    let mut user2 = Account::new("0xabce".to_string(), "Tom".to_string());
-    let mut user3 = Account::new("0xabcf".to_string(), "Jim".to_string());
+    let args = Args::parse();
+    if let Err(e) = run(args) {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let mut params = NetworkParams::from_name(&args.network)
+        .ok_or_else(|| format!("unknown network `{}`", args.network))?;
+    if args.prune.is_some() {
+        params.prune_depth = args.prune;
+    }
+
+    match args.command.unwrap_or(Command::Demo) {
+        Command::Demo => {
+            demo(&params);
+        },
+        Command::Send { from, to, amount, fee } => {
+            let chain = ChainHandle::load(&params)?;
+            let mut sender = chain.account(&from);
+            let mut receiver = chain.account(&to);
+            let tx = sender.transfer_to(&mut receiver, amount, fee)?;
+            chain.submit_tx(tx.clone())?;
+            chain.save_mempool()?;
+            println!("pending {} with nonce {} and fee {}", tx.hash, tx.nonce, tx.fee);
+        },
+        Command::BumpFee { from, nonce, fee } => replace(&params, &from, nonce, fee, false)?,
+        Command::Cancel { from, nonce, fee } => replace(&params, &from, nonce, fee, true)?,
+        Command::Mine => {
+            let mut mine = Mine::load(&params)?;
+            mine.mining()?;
+            mine.chain.save_mempool()?;
+            let (hash, height) = mine.chain.tip();
+            println!("tip: {} at height {}", hash, height);
+        },
+        Command::Printchain { from, to, address, format, stats } => {
+            let inspector = ChainInspector::load(&params)?;
+            let query = Query { from, to, address };
+            let blocks = inspector.select(&query)?;
+            if stats {
+                let stats = ChainInspector::stats(&blocks);
+                print!("{}", ChainInspector::render_stats(&stats, format));
+            } else {
+                print!("{}", ChainInspector::render(&blocks, query.address.as_deref(), format));
+            }
+        },
+        Command::Explorer { port } => {
+            let chain = BlockChain::load(&params)?;
+            let listener = Explorer::bind(port)?;
+            println!("explorer listening on http://{}", listener.local_addr()?);
+            Explorer::new(ChainHandle::new(chain)).serve(listener)?;
+        },
+        Command::Pool { port, share_bits, window } => {
+            let chain = ChainHandle::load(&params)?;
+            let pool = Pool::new(chain.clone(), share_bits, window)?;
+            let listener = Pool::bind(port)?;
+            println!("pool listening on {}", listener.local_addr()?);
+            pool.serve(listener, move |event| match event {
+                PoolEvent::BlockFound { hash, payouts } => {
+                    println!("pool found block {} at height {}", hash, chain.tip().1);
+                    for (name, amount) in payouts {
+                        println!("pool credits {}: {}", name, amount);
+                    }
+                    if let Err(e) = chain.save_mempool() {
+                        println!("failed to save mempool: {}", e);
+                    }
+                },
+                PoolEvent::BlockRejected(e) => println!("pool block rejected: {}", e),
+            })?;
+        },
+        Command::Worker { addr, name } => {
+            let mut worker = PoolWorker::connect(addr.as_str(), &name)?;
+            println!("worker {} connected to {}", name, addr);
+            loop {
+                let work = worker.get_work()?;
+                if worker.mine(&work)? {
+                    println!("worker {} found a block, {} shares accepted", name, worker.shares);
+                }
+            }
+        },
+    }
+
+    Ok(())
+}
+
+// 难度按十六进制读取，可带 0x 前缀
+fn parse_bits(s: &str) -> Result<u32, ParseIntError> {
+    u32::from_str_radix(s.trim_start_matches("0x"), 16)
+}
+
+// 用更高手续费的交易替换交易池中发送者的一笔待打包转账
+fn replace(params: &NetworkParams, from: &str, nonce: u64, fee: u64, cancel: bool)
+    -> Result<(), Box<dyn Error>>
+{
+    let chain = ChainHandle::load(params)?;
+    let tx = chain.pending_tx(from, nonce).ok_or(TxError::NothingToReplace)?;
+    let mut sender = chain.account(from);
+    let mut receiver = chain.account(&tx.to);
+
+    let replacement = if cancel {
+        sender.cancel(&mut receiver, &tx, fee)?
+    } else {
+        sender.bump_fee(&mut receiver, &tx, fee)?
+    };
+    chain.submit_tx(replacement.clone())?;
+    chain.save_mempool()?;
+    println!("replaced {} by {} with fee {}", tx.hash, replacement.hash, replacement.fee);
+
+    Ok(())
+}
+
+fn demo(params: &NetworkParams) {
+    let mut user1 = Account::new("0xabcd".to_string(), "Kim".to_string(), params);
+    let mut user2 = Account::new("0xabce".to_string(), "Tom".to_string(), params);
+    let mut user3 = Account::new("0xabcf".to_string(), "Jim".to_string(), params);
 
     println!("-------------------------Mine Info----------------------------");
-    let mut mine = Mine::new();
-    let mut txs: Vec<Transaction> = Vec::new();
+    let mut mine = Mine::new(params);
 
     let res = user1.transfer_to(&mut user2, 9, 1);
-    match res {
-        Ok(tx) => txs.push(tx),
+    match res.and_then(|tx| mine.chain.submit_tx(tx)) {
+        Ok(_) => (),
         Err(e) => panic!("{}", e),
     }
     let res = user1.transfer_to(&mut user2, 5, 1);
-    match res {
-        Ok(tx) => txs.push(tx),
+    match res.and_then(|tx| mine.chain.submit_tx(tx)) {
+        Ok(_) => (),
         Err(e) => panic!("{}", e),
     }
-    mine.mining(&mut txs);
+    if let Err(e) = mine.mining() {
+        panic!("{}", e);
+    }
+
+    // 挖矿期间其他线程仍可并发读取链
+    let chain = mine.chain.clone();
+    let reader = thread::spawn(move || {
+        let (hash, height) = chain.tip();
+        println!("tip: {} at height {}", hash, height);
+    });
 
-    let mut txs: Vec<Transaction> = Vec::new();
     let res = user2.transfer_to(&mut user3, 6, 1);
-    match res {
-        Ok(tx) => txs.push(tx),
+    match res.and_then(|tx| mine.chain.submit_tx(tx)) {
+        Ok(_) => (),
         Err(e) => panic!("{}", e),
     }
     let res = user2.transfer_to(&mut user3, 3, 1);
-    match res {
-        Ok(tx) => txs.push(tx),
+    match res.and_then(|tx| mine.chain.submit_tx(tx)) {
+        Ok(_) => (),
         Err(e) => panic!("{}", e),
     }
-    mine.mining(&mut txs);
+    if let Err(e) = mine.mining() {
+        panic!("{}", e);
+    }
+
+    reader.join().unwrap();
+
+    // 调用方可直接匹配失败原因
+    match user3.transfer_to(&mut user1, 1000, 1) {
+        Err(TxError::InsufficientFunds { need, have }) =>
+            println!("rejected: need {} but only have {}", need, have),
+        Err(e) => panic!("{}", e),
+        Ok(_) => panic!("overspend accepted"),
+    }
 
     println!("-------------------------Miner Info------------------------------");
     mine.miner.miner_info();
@@ -43,8 +316,21 @@ fn main() {
     let users = vec![&user1, &user2, &user3];
     for u in users {
         u.account_info();
+        println!("on-chain balance of {}: {}", u.address, mine.chain.balance(&u.address));
+    }
+
+    println!("-------------------------Fee Estimates---------------------------");
+    for target in [1, 3, 6].iter() {
+        for mode in [EstimateMode::Economical, EstimateMode::Conservative].iter() {
+            match mine.chain.estimate_fee(*target, *mode) {
+                Some(rate) => println!("{:?} within {} blocks: {} per kB", mode, target, rate),
+                None => println!("{:?} within {} blocks: not enough data", mode, target),
+            }
+        }
     }
 
     println!("-------------------------Block Info------------------------------");
-    mine.blockchain.block_info();
+    for block in mine.chain.blocks() {
+        println!("{:#?}", block);
+    }
 }
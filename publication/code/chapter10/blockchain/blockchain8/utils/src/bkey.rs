@@ -10,7 +10,7 @@ pub struct BKey {
 impl Key for BKey {
     fn as_slice<T, F: Fn(&[u8]) -> T>(&self, func: F) -> T {
         let val = unsafe {
-            transmute::<_, &[u8; 32]>(self)
+            transmute::<&Self, &[u8; 32]>(self)
         };
         func(val)
     }
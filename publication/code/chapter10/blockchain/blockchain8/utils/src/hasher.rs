@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sha3::{Digest, Sha3_256};
+use blake2::Blake2b;
+use blake2::digest::consts::U32;
+
+// 链上所有哈希(交易、账户、默克尔树、工作量证明)都通过该 trait 计算
+pub trait ChainHasher {
+    fn hash_u8(&self, value: &[u8], out: &mut [u8]);
+
+    fn hash_str(&self, value: &[u8]) -> String {
+        let mut out: [u8; 32] = [0; 32];
+        self.hash_u8(value, &mut out);
+        to_hex(&out)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sha3Hasher;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DoubleSha256Hasher;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Blake2bHasher;
+
+impl ChainHasher for Sha3Hasher {
+    fn hash_u8(&self, value: &[u8], out: &mut [u8]) {
+        let res = Sha3_256::digest(value);
+        out.copy_from_slice(&res);
+    }
+}
+
+impl ChainHasher for DoubleSha256Hasher {
+    fn hash_u8(&self, value: &[u8], out: &mut [u8]) {
+        let res = Sha256::digest(Sha256::digest(value));
+        out.copy_from_slice(&res);
+    }
+}
+
+impl ChainHasher for Blake2bHasher {
+    fn hash_u8(&self, value: &[u8], out: &mut [u8]) {
+        let res = Blake2b::<U32>::digest(value);
+        out.copy_from_slice(&res);
+    }
+}
+
+// 各网络选用的哈希算法，可随网络参数一起复制和序列化
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgo {
+    #[default]
+    Sha3_256,
+    DoubleSha256,
+    Blake2b,
+}
+
+impl ChainHasher for HashAlgo {
+    fn hash_u8(&self, value: &[u8], out: &mut [u8]) {
+        match self {
+            HashAlgo::Sha3_256 => Sha3Hasher.hash_u8(value, out),
+            HashAlgo::DoubleSha256 => DoubleSha256Hasher.hash_u8(value, out),
+            HashAlgo::Blake2b => Blake2bHasher.hash_u8(value, out),
+        }
+    }
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+
+    (0..hex.len()).step_by(2)
+                  .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+                  .collect()
+}
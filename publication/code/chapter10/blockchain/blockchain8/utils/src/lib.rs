@@ -1,2 +1,43 @@
 pub mod bkey;
+pub mod hasher;
 pub mod serializer;
+
+#[cfg(test)]
+mod tests {
+    use crate::hasher::{ChainHasher, HashAlgo};
+
+    #[test]
+    fn sha3_256_vectors() {
+        let hasher = HashAlgo::Sha3_256;
+        assert_eq!("a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a",
+                   hasher.hash_str(b""));
+        assert_eq!("3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+                   hasher.hash_str(b"abc"));
+    }
+
+    #[test]
+    fn double_sha256_vectors() {
+        let hasher = HashAlgo::DoubleSha256;
+        assert_eq!("5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456",
+                   hasher.hash_str(b""));
+        assert_eq!("4f8b42c22dd3729b519ba6f68d2da7cc5b2d606d05daed5ad5128cc03e6c6358",
+                   hasher.hash_str(b"abc"));
+    }
+
+    #[test]
+    fn blake2b_vectors() {
+        let hasher = HashAlgo::Blake2b;
+        assert_eq!("0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8",
+                   hasher.hash_str(b""));
+        assert_eq!("bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319",
+                   hasher.hash_str(b"abc"));
+    }
+
+    #[test]
+    fn hash_u8_matches_hash_str() {
+        let hasher = HashAlgo::Blake2b;
+        let mut out: [u8; 32] = [0; 32];
+        hasher.hash_u8(b"abc", &mut out);
+        assert_eq!(hasher.hash_str(b"abc"), crate::hasher::to_hex(&out));
+    }
+}